@@ -0,0 +1,21 @@
+#![no_main]
+
+use bitvec::{order::Msb0, view::BitView};
+use bitvec_nom2::BSlice;
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/d16/mod.rs"]
+mod d16;
+
+use d16::Packet;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((_, packet)) = Packet::parse(BSlice(data.view_bits::<Msb0>())) else {
+        return;
+    };
+
+    let encoded = packet.encode_with(None).unwrap();
+    let (_, reparsed) = Packet::parse(BSlice(&encoded)).expect("encoded packet should parse");
+    assert_eq!(packet, reparsed);
+});
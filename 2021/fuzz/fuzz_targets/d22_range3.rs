@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/d22/mod.rs"]
+mod d22;
+
+use d22::Range3;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok((_, range3)) = Range3::parse(input) else {
+        return;
+    };
+
+    let printed = range3.to_string();
+    assert_eq!(Ok(("", range3)), Range3::parse(&printed));
+});
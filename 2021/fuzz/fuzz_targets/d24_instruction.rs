@@ -0,0 +1,22 @@
+#![no_main]
+#![feature(test)]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/d24/mod.rs"]
+mod d24;
+
+use d24::Instruction;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok((_, instruction)) = Instruction::parse(input) else {
+        return;
+    };
+
+    let printed = instruction.to_string();
+    assert_eq!(Ok(("", instruction)), Instruction::parse(&printed));
+});
@@ -1,26 +1,28 @@
-use itertools::Itertools;
-use std::collections::{HashMap, HashSet, LinkedList};
+use advent_common::graph::{Edges, Graph};
+use std::collections::{HashSet, LinkedList};
 
 pub fn solve(problem: &str) -> (u64, u64) {
-    let problem = &problem.lines().collect_vec();
-    let mut cave_map = HashMap::<&str, Vec<&str>>::new();
-    for line in problem {
-        // assume every pairing is only listed once
-        let (l, r) = line.split('-').collect_tuple().unwrap();
-        cave_map.entry(l).or_default().push(r);
-        cave_map.entry(r).or_default().push(l);
-    }
+    // assume every pairing is only listed once
+    let cave_map = Graph::parse_pairs(problem, "-", Edges::Undirected);
     (solve1(&cave_map), solve2(&cave_map))
 }
 
-fn solve1(cave: &HashMap<&str, Vec<&str>>) -> u64 {
-    fn filter(curr_state: &State, next_location: &str) -> Option<State> {
-        if !curr_state.visited.contains(next_location) {
+fn is_small(cave: &Graph, location: usize) -> bool {
+    cave.label(location)
+        .chars()
+        .next()
+        .unwrap()
+        .is_ascii_lowercase()
+}
+
+fn solve1(cave: &Graph) -> u64 {
+    fn filter(cave: &Graph, curr_state: &State, next_location: usize) -> Option<State> {
+        if !curr_state.visited.contains(&next_location) {
             let mut next = curr_state.clone();
-            next.location = next_location.into();
+            next.location = next_location;
 
-            if next_location.chars().next().unwrap().is_ascii_lowercase() {
-                next.visited.insert(next_location.into());
+            if is_small(cave, next_location) {
+                next.visited.insert(next_location);
             }
 
             Some(next)
@@ -32,18 +34,18 @@ fn solve1(cave: &HashMap<&str, Vec<&str>>) -> u64 {
     unique_paths(cave, filter)
 }
 
-fn solve2(cave: &HashMap<&str, Vec<&str>>) -> u64 {
-    fn filter(curr_state: &State, next_location: &str) -> Option<State> {
+fn solve2(cave: &Graph) -> u64 {
+    fn filter(cave: &Graph, curr_state: &State, next_location: usize) -> Option<State> {
         let mut next_state = curr_state.clone();
-        next_state.location = String::from(next_location);
+        next_state.location = next_location;
 
-        if !curr_state.visited.contains(next_location) {
+        if !curr_state.visited.contains(&next_location) {
             // we haven't visited yet
-            if next_location.chars().next().unwrap().is_ascii_lowercase() {
-                next_state.visited.insert(String::from(next_location));
+            if is_small(cave, next_location) {
+                next_state.visited.insert(next_location);
             }
             Some(next_state)
-        } else if next_location != "start" && !curr_state.visited_twice {
+        } else if cave.label(next_location) != "start" && !curr_state.visited_twice {
             // we haven't visited any location twice yet (though "start" is banned)
             next_state.visited_twice = true;
             Some(next_state)
@@ -56,12 +58,14 @@ fn solve2(cave: &HashMap<&str, Vec<&str>>) -> u64 {
 }
 
 fn unique_paths(
-    cave: &HashMap<&str, Vec<&str>>,
-    next_state_filter: impl Fn(&State, &str) -> Option<State>,
+    cave: &Graph,
+    next_state_filter: impl Fn(&Graph, &State, usize) -> Option<State>,
 ) -> u64 {
+    let start = cave.id("start").unwrap();
+    let end = cave.id("end").unwrap();
     let start = State {
-        location: "start".into(),
-        visited: HashSet::from(["start".into()]),
+        location: start,
+        visited: HashSet::from([start]),
         visited_twice: false,
     };
     let mut to_visit = LinkedList::new();
@@ -69,16 +73,14 @@ fn unique_paths(
 
     let mut num_paths = 0;
     while let Some(curr) = to_visit.pop_front() {
-        if curr.location == "end" {
+        if curr.location == end {
             num_paths += 1;
             continue;
         }
 
-        let next_locations = cave.get(curr.location.as_str()).unwrap();
-
-        next_locations
+        cave.neighbours(curr.location)
             .iter()
-            .filter_map(|next_location| next_state_filter(&curr, next_location))
+            .filter_map(|next_location| next_state_filter(cave, &curr, *next_location))
             .for_each(|state| to_visit.push_back(state));
     }
     num_paths
@@ -86,8 +88,8 @@ fn unique_paths(
 
 #[derive(Clone)]
 struct State {
-    location: String,
-    visited: HashSet<String>,
+    location: usize,
+    visited: HashSet<usize>,
     visited_twice: bool,
 }
 
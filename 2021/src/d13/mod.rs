@@ -7,6 +7,14 @@ use std::{
 };
 
 pub fn solve(problem: &str) -> (u64, u64) {
+    let (points, fold_instructions) = parse(problem);
+    (
+        solve1(points.clone(), &fold_instructions),
+        solve2(points, &fold_instructions),
+    )
+}
+
+fn parse(problem: &str) -> (BTreeSet<Point>, Vec<FoldInstruction>) {
     let problem = &problem.lines().collect_vec();
     let split_point = problem.iter().position(|l| l == &"").unwrap();
     let points: BTreeSet<_> = parse_all_numbers(&problem[..split_point])
@@ -21,10 +29,7 @@ pub fn solve(problem: &str) -> (u64, u64) {
         .iter()
         .map(|l| FoldInstruction::parse(l))
         .collect_vec();
-    (
-        solve1(points.clone(), &fold_instructions),
-        solve2(points, &fold_instructions),
-    )
+    (points, fold_instructions)
 }
 
 fn solve1(points: BTreeSet<Point>, fold_instructions: &[FoldInstruction]) -> u64 {
@@ -34,7 +39,7 @@ fn solve1(points: BTreeSet<Point>, fold_instructions: &[FoldInstruction]) -> u64
 
 fn solve2(points: BTreeSet<Point>, fold_instructions: &[FoldInstruction]) -> u64 {
     let points = fold_completely(points, fold_instructions);
-    display(&points);
+    println!("{}", render(&points));
 
     // because the technically correct answer is a String instead of a u64, I'm going to coerce it
     // into a u64
@@ -78,7 +83,7 @@ fn fold_across_row(point: Point, fold_row: u64) -> Point {
     (new_row, col)
 }
 
-fn display(points: &BTreeSet<Point>) {
+fn render(points: &BTreeSet<Point>) -> String {
     let (min_row, max_row, min_col, max_col) =
         points
             .iter()
@@ -98,11 +103,10 @@ fn display(points: &BTreeSet<Point>) {
         let mod_col = (col - min_col) as usize;
         matrix[mod_row][mod_col] = '#';
     }
-    let to_display = matrix
+    matrix
         .iter()
         .map(|r| r.iter().collect::<String>())
-        .join("\n");
-    println!("{to_display}");
+        .join("\n")
 }
 
 fn reflect(stationary: u64, to_reflect: u64) -> u64 {
@@ -138,6 +142,14 @@ fn test() {
     basic_test(input, solve);
 }
 
+#[test]
+fn test_render_snapshot() {
+    use crate::utils::parse_example_testcases;
+    let example = &parse_example_testcases(include_str!("example.txt"))[0];
+    let (points, fold_instructions) = parse(&example.problem);
+    insta::assert_snapshot!(render(&fold_completely(points, &fold_instructions)));
+}
+
 #[test]
 fn test_reflect() {
     let (stay, to_reflect) = (2, 1);
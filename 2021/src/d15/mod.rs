@@ -1,6 +1,6 @@
 use crate::utils::{manhattan_distance, parse_matrix_of_nums};
+use advent_common::search::{astar, SearchState};
 use itertools::Itertools;
-use pathfinding::prelude::astar;
 use std::{collections::HashMap, hash::Hash};
 
 pub fn solve(problem: &str) -> (u64, u64) {
@@ -50,22 +50,30 @@ fn solve2(costs_to_enter: &HashMap<(usize, usize), u64>) -> u64 {
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 struct Pos((usize, usize));
 
-impl Pos {
-    fn manhattan_distance(&self, other: &Pos) -> u64 {
-        manhattan_distance(&self.0, &other.0) as u64
-    }
+struct Cavern<'a> {
+    distance_to: &'a HashMap<(usize, usize), u64>,
+    neighbors: HashMap<(usize, usize), Vec<(usize, usize)>>,
+    goal: Pos,
+}
 
-    fn successors(
-        &self,
-        distance_to: &HashMap<(usize, usize), u64>,
-        neighbors: &HashMap<(usize, usize), Vec<(usize, usize)>>,
-    ) -> Vec<(Pos, u64)> {
+impl SearchState<Cavern<'_>> for Pos {
+    type Cost = u64;
+
+    fn successors(&self, cavern: &Cavern) -> Vec<(Pos, u64)> {
         let &Pos(pos) = self;
-        neighbors[&pos]
+        cavern.neighbors[&pos]
             .iter()
-            .map(|neighbor| (Pos(*neighbor), distance_to[neighbor]))
+            .map(|neighbor| (Pos(*neighbor), cavern.distance_to[neighbor]))
             .collect()
     }
+
+    fn heuristic(&self, cavern: &Cavern) -> u64 {
+        manhattan_distance(&self.0, &cavern.goal.0) as u64
+    }
+
+    fn is_goal(&self, cavern: &Cavern) -> bool {
+        *self == cavern.goal
+    }
 }
 
 fn min_cost_astar(distance_to: &HashMap<(usize, usize), u64>) -> u64 {
@@ -91,15 +99,12 @@ fn min_cost_astar(distance_to: &HashMap<(usize, usize), u64>) -> u64 {
         })
         .collect();
 
-    let goal = Pos((*max_row, *max_col));
-    let result = astar(
-        &Pos((0, 0)),
-        |p| p.successors(distance_to, &neighbors),
-        |p| p.manhattan_distance(&goal),
-        |p| *p == goal,
-    )
-    .unwrap();
-    result.1
+    let cavern = Cavern {
+        distance_to,
+        neighbors,
+        goal: Pos((*max_row, *max_col)),
+    };
+    astar(Pos((0, 0)), &cavern).unwrap().cost
 }
 
 #[test]
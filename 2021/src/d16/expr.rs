@@ -0,0 +1,203 @@
+use super::{
+    Operation::{self, EqualTo, GreaterThan, LessThan, Product, Sum},
+    Packet,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, char, multispace0, u64},
+    combinator::{all_consuming, map, opt},
+    error::{Error, ErrorKind},
+    multi::separated_list1,
+    sequence::{delimited, pair},
+    Finish, IResult,
+};
+use std::fmt;
+
+/// Where in the source an expression stopped making sense.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ExprError {
+    offset: usize,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not parse expression at offset {}", self.offset)
+    }
+}
+
+/// Compiles an expression such as `1 + 2 * max(3, 4) < 20` into a packet that evaluates to the
+/// same value. `*` binds tighter than `+`, which binds tighter than `<`, `>` and `==`, and every
+/// operation is also available as a function named as in the S-expression form, e.g. `min(1, 2)`
+/// or `gt(3, 2)`. All packets have version 0.
+pub(crate) fn compile(source: &str) -> Result<Packet, ExprError> {
+    all_consuming(expr)(source)
+        .finish()
+        .map(|(_, packet)| packet)
+        .map_err(|error| ExprError {
+            offset: source.len() - error.input.len(),
+        })
+}
+
+fn expr(input: &str) -> IResult<&str, Packet> {
+    let (input, lhs) = sum(input)?;
+    let comparison = alt((
+        map(tag("=="), |_| EqualTo),
+        map(tag("<"), |_| LessThan),
+        map(tag(">"), |_| GreaterThan),
+    ));
+    let (input, rhs) = opt(pair(comparison, sum))(input)?;
+    let packet = match rhs {
+        Some((operation, rhs)) => Packet::operator(0, operation, vec![lhs, rhs]),
+        None => lhs,
+    };
+    Ok((input, packet))
+}
+
+fn sum(input: &str) -> IResult<&str, Packet> {
+    chain('+', product, Sum)(input)
+}
+
+fn product(input: &str) -> IResult<&str, Packet> {
+    chain('*', atom, Product)(input)
+}
+
+/// One or more operands separated by `symbol`, collected into a single operator packet.
+fn chain<'a>(
+    symbol: char,
+    operand: fn(&'a str) -> IResult<&'a str, Packet>,
+    operation: Operation,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Packet> {
+    map(
+        separated_list1(char(symbol), operand),
+        move |mut packets| {
+            if packets.len() == 1 {
+                packets.pop().unwrap()
+            } else {
+                Packet::operator(0, operation, packets)
+            }
+        },
+    )
+}
+
+fn atom(input: &str) -> IResult<&str, Packet> {
+    delimited(
+        multispace0,
+        alt((
+            map(u64, |value| Packet::literal(0, value)),
+            call,
+            delimited(char('('), expr, char(')')),
+        )),
+        multispace0,
+    )(input)
+}
+
+fn call(input: &str) -> IResult<&str, Packet> {
+    let (rest, name) = alpha1(input)?;
+    let Some(operation) = Operation::ALL
+        .into_iter()
+        .find(|operation| operation.name() == name)
+    else {
+        return Err(nom::Err::Failure(Error::new(input, ErrorKind::Tag)));
+    };
+    let (rest, packets) = delimited(
+        pair(multispace0, char('(')),
+        separated_list1(char(','), expr),
+        char(')'),
+    )(rest)?;
+    if operation.is_comparison() && packets.len() != 2 {
+        return Err(nom::Err::Failure(Error::new(input, ErrorKind::Count)));
+    }
+    Ok((rest, Packet::operator(0, operation, packets)))
+}
+
+#[test]
+fn test_compile_precedence() {
+    let packet = compile("1 + 2 * 3 < max(4, 5 * (6 + 7))").unwrap();
+    assert_eq!(
+        "(lt (sum (literal 1) (product (literal 2) (literal 3))) \
+         (max (literal 4) (product (literal 5) (sum (literal 6) (literal 7)))))",
+        packet.to_string()
+    );
+}
+
+#[test]
+fn test_compiled_transmissions_solve() {
+    let examples = [
+        ("1 + 2", 3),
+        ("6 * 9", 54),
+        ("min(7, 8, 9)", 7),
+        ("max(7, 8, 9)", 9),
+        ("5 < 15", 1),
+        ("5 > 15", 0),
+        ("5 == 15", 0),
+        ("1 + 3 == 2 * 2", 1),
+        ("sum(2021)", 2021),
+        ("eq(product(2, 3), 6) * 10", 10),
+    ];
+    for (source, value) in examples {
+        let hex = compile(source).unwrap().to_hex(None).unwrap();
+        assert_eq!(value, super::solve(&hex).1, "{source} compiled to {hex}");
+    }
+}
+
+#[test]
+fn test_compile_errors() {
+    assert_eq!(Err(ExprError { offset: 2 }), compile("1 +"));
+    assert_eq!(Err(ExprError { offset: 4 }), compile("1 + foo(2)"));
+    assert_eq!(Err(ExprError { offset: 0 }), compile("lt(1, 2, 3)"));
+    assert_eq!(Err(ExprError { offset: 6 }), compile("(1 + 2"));
+}
+
+/// Random expressions in the surface syntax, with the value they should evaluate to.
+#[cfg(test)]
+fn random_expr(rng: &mut rand::rngs::StdRng, depth: usize) -> (String, u64) {
+    use rand::Rng;
+
+    if depth == 0 || rng.gen_range(0..3) == 0 {
+        let value = rng.gen_range(0..4);
+        return (value.to_string(), value);
+    }
+    let (sources, values): (Vec<_>, Vec<_>) = (0..rng.gen_range(1..=3))
+        .map(|_| random_expr(rng, depth - 1))
+        .unzip();
+    let (first, last) = (values[0], values[values.len() - 1]);
+    let (first_source, last_source) = (&sources[0], &sources[sources.len() - 1]);
+    match rng.gen_range(0..6) {
+        0 => (format!("({})", sources.join(" + ")), values.iter().sum()),
+        1 => (
+            format!("({})", sources.join(" * ")),
+            values.iter().product(),
+        ),
+        2 => (
+            format!("min({})", sources.join(", ")),
+            *values.iter().min().unwrap(),
+        ),
+        3 => (
+            format!("max({})", sources.join(",")),
+            *values.iter().max().unwrap(),
+        ),
+        4 => (
+            format!("({first_source} < {last_source})"),
+            (first < last) as u64,
+        ),
+        _ => (
+            format!("gt({first_source},{last_source})"),
+            (first > last) as u64,
+        ),
+    }
+}
+
+#[test]
+fn test_property_compiled_value() {
+    use advent_common::differential::differential_test;
+
+    let result = differential_test(
+        500,
+        3,
+        random_expr,
+        |_| vec![],
+        |(source, value)| *value == super::perform_operation(&compile(source).unwrap()),
+    );
+    assert_eq!(Ok(()), result.map_err(|(source, _)| source));
+}
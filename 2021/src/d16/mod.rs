@@ -3,6 +3,7 @@ use bitvec::{
     bits,
     field::BitField,
     order::{Lsb0, Msb0},
+    vec::BitVec,
     view::BitView,
 };
 use bitvec_nom2::BSlice;
@@ -14,6 +15,9 @@ use nom::{
     sequence::tuple,
     IResult,
 };
+use std::fmt;
+
+mod expr;
 
 pub fn solve(problem: &str) -> (u64, u64) {
     let problem = &problem.lines().collect_vec();
@@ -23,6 +27,18 @@ pub fn solve(problem: &str) -> (u64, u64) {
     (solve1(&top_level_packet.1), solve2(&top_level_packet.1))
 }
 
+/// Compiles an expression and prints the packet tree, its value and the transmission.
+pub fn print_compiled(source: &str) {
+    match expr::compile(source) {
+        Ok(packet) => {
+            println!("{packet:#}");
+            println!("value: {}", perform_operation(&packet));
+            println!("{}", packet.to_hex(None).unwrap());
+        }
+        Err(error) => println!("{error}"),
+    }
+}
+
 fn solve1(packet: &Packet) -> u64 {
     sum_version_numbers_recursively(packet)
 }
@@ -90,16 +106,15 @@ fn perform_operation(packet: &Packet) -> u64 {
     }
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-struct Packet {
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Packet {
     version: u8,
     type_id: u8,
     contents: PacketContents,
 }
 
 impl Packet {
-    fn parse(input: BSlice<u8, Msb0>) -> IResult<BSlice<u8, Msb0>, Self> {
+    pub(crate) fn parse(input: BSlice<u8, Msb0>) -> IResult<BSlice<u8, Msb0>, Self> {
         let (input, (version, type_id)) = map(
             tuple((take::<_, BSlice<u8, Msb0>, _>(3_usize), take(3_usize))),
             |(version, type_id)| (version.load_be::<u8>(), type_id.load_be::<u8>()),
@@ -136,16 +151,7 @@ impl Packet {
                 map_parser(take(num_bits), many0(Packet::parse))(input)?
             };
 
-            let operation = match type_id {
-                0 => Sum,
-                1 => Product,
-                2 => Minimum,
-                3 => Maximum,
-                5 => GreaterThan,
-                6 => LessThan,
-                7 => EqualTo,
-                _ => panic!("how?"),
-            };
+            let operation = Operation::from_type_id(type_id).expect("how?");
             (input, PacketContents::Operator { operation, packets })
         };
 
@@ -158,10 +164,143 @@ impl Packet {
             },
         ))
     }
+
+    pub(crate) fn literal(version: u8, value: u64) -> Self {
+        Packet {
+            version,
+            type_id: 4,
+            contents: PacketContents::Literal(value),
+        }
+    }
+
+    pub(crate) fn operator(version: u8, operation: Operation, packets: Vec<Packet>) -> Self {
+        Packet {
+            version,
+            type_id: operation.type_id(),
+            contents: PacketContents::Operator { operation, packets },
+        }
+    }
+
+    /// Encodes with the length type picked for each operator, which can't fail.
+    #[cfg(test)]
+    pub(crate) fn encode(&self) -> BitVec<u8, Msb0> {
+        self.encode_with(None).unwrap()
+    }
+
+    /// Literals are written with as few groups as possible. Sub-packets are given by count unless
+    /// there are too many to fit in 11 bits, in which case their total length is used instead,
+    /// or every operator uses `length_type` if one is given.
+    pub(crate) fn encode_with(
+        &self,
+        length_type: Option<LengthType>,
+    ) -> Result<BitVec<u8, Msb0>, EncodeError> {
+        let mut bits = BitVec::new();
+        push_bits(&mut bits, self.version as u64, 3);
+        push_bits(&mut bits, self.type_id as u64, 3);
+
+        match &self.contents {
+            PacketContents::Literal(value) => {
+                let num_groups = (u64::BITS - value.leading_zeros()).div_ceil(4).max(1);
+                for group in (0..num_groups).rev() {
+                    bits.push(group != 0);
+                    push_bits(&mut bits, value >> (group * 4), 4);
+                }
+            }
+            PacketContents::Operator { packets, .. } => {
+                let mut sub_packet_bits = BitVec::<u8, Msb0>::new();
+                for packet in packets {
+                    sub_packet_bits.extend(packet.encode_with(length_type)?);
+                }
+                let length_type = length_type.unwrap_or(if packets.len() < 1 << 11 {
+                    LengthType::SubPacketCount
+                } else {
+                    LengthType::TotalBits
+                });
+                match length_type {
+                    LengthType::SubPacketCount if packets.len() < 1 << 11 => {
+                        bits.push(true);
+                        push_bits(&mut bits, packets.len() as u64, 11);
+                    }
+                    LengthType::TotalBits if sub_packet_bits.len() < 1 << 15 => {
+                        bits.push(false);
+                        push_bits(&mut bits, sub_packet_bits.len() as u64, 15);
+                    }
+                    LengthType::SubPacketCount => {
+                        return Err(EncodeError::TooManySubPackets(packets.len()))
+                    }
+                    LengthType::TotalBits => {
+                        return Err(EncodeError::SubPacketsTooLong(sub_packet_bits.len()))
+                    }
+                }
+                bits.extend(sub_packet_bits);
+            }
+        }
+
+        Ok(bits)
+    }
+
+    /// The transmission as it would appear in a puzzle input, padded with zeros to whole bytes.
+    pub(crate) fn to_hex(&self, length_type: Option<LengthType>) -> Result<String, EncodeError> {
+        let mut bits = self.encode_with(length_type)?;
+        bits.set_uninitialized(false);
+        Ok(hex::encode_upper(bits.into_vec()))
+    }
+}
+
+/// How an operator packet says where its sub-packets end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LengthType {
+    /// Length type ID 0, a 15 bit count of the bits the sub-packets take up.
+    TotalBits,
+    /// Length type ID 1, an 11 bit count of the sub-packets.
+    SubPacketCount,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum EncodeError {
+    /// More sub-packets than an 11 bit count can hold.
+    TooManySubPackets(usize),
+    /// Sub-packets too long for a 15 bit length.
+    SubPacketsTooLong(usize),
+}
+
+/// Writes packets as S-expressions, e.g. `(sum (literal 1) (max (literal 2) (literal 3)))`. The
+/// alternate form `{:#}` puts each sub-packet on its own indented line.
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_sexpr(f, 0)
+    }
 }
 
-#[derive(Debug)]
-enum PacketContents {
+impl Packet {
+    fn write_sexpr(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        match &self.contents {
+            PacketContents::Literal(value) => write!(f, "(literal {value})"),
+            PacketContents::Operator { operation, packets } => {
+                write!(f, "({operation}")?;
+                for packet in packets {
+                    if f.alternate() {
+                        write!(f, "\n{}", "  ".repeat(depth + 1))?;
+                    } else {
+                        write!(f, " ")?;
+                    }
+                    packet.write_sexpr(f, depth + 1)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Appends the lowest `num_bits` bits of `value`, most significant first.
+fn push_bits(bits: &mut BitVec<u8, Msb0>, value: u64, num_bits: u32) {
+    for bit in (0..num_bits).rev() {
+        bits.push((value >> bit) & 1 == 1);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PacketContents {
     Literal(u64),
     Operator {
         operation: Operation,
@@ -169,8 +308,8 @@ enum PacketContents {
     },
 }
 
-#[derive(Debug)]
-enum Operation {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Operation {
     Sum,
     Product,
     Minimum,
@@ -180,9 +319,159 @@ enum Operation {
     EqualTo,
 }
 
+impl Operation {
+    const ALL: [Operation; 7] = [
+        Sum,
+        Product,
+        Minimum,
+        Maximum,
+        GreaterThan,
+        LessThan,
+        EqualTo,
+    ];
+
+    fn from_type_id(type_id: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|operation| operation.type_id() == type_id)
+    }
+
+    fn type_id(&self) -> u8 {
+        match self {
+            Sum => 0,
+            Product => 1,
+            Minimum => 2,
+            Maximum => 3,
+            GreaterThan => 5,
+            LessThan => 6,
+            EqualTo => 7,
+        }
+    }
+
+    /// Comparisons take exactly two sub-packets, everything else at least one.
+    fn is_comparison(&self) -> bool {
+        matches!(self, GreaterThan | LessThan | EqualTo)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Sum => "sum",
+            Product => "product",
+            Minimum => "min",
+            Maximum => "max",
+            GreaterThan => "gt",
+            LessThan => "lt",
+            EqualTo => "eq",
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 #[test]
 fn test() {
     use crate::utils::basic_test;
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_encode_round_trip() {
+    use crate::utils::parse_example_testcases;
+    let input = include_str!("example.txt");
+    for example in parse_example_testcases(input) {
+        let bytes = hex::decode(example.problem.trim()).unwrap();
+        let (_, packet) = Packet::parse(BSlice(bytes.view_bits::<Msb0>())).unwrap();
+        let encoded = packet.encode();
+        let (_, reparsed) = Packet::parse(BSlice(&encoded)).unwrap();
+        assert_eq!(packet, reparsed);
+    }
+}
+
+#[cfg(test)]
+fn random_packet(rng: &mut rand::rngs::StdRng, depth: usize) -> Packet {
+    use rand::Rng;
+
+    let version = rng.gen_range(0..8);
+    if depth == 0 || rng.gen_range(0..3) == 0 {
+        let value = match rng.gen_range(0..3) {
+            0 => rng.gen_range(0..16),
+            1 => rng.gen_range(0..1 << 32) << rng.gen_range(0..32),
+            _ => u64::MAX - rng.gen_range(0..16),
+        };
+        return Packet::literal(version, value);
+    }
+    let operation = Operation::ALL[rng.gen_range(0..7)];
+    let num_packets = if operation.is_comparison() {
+        2
+    } else {
+        rng.gen_range(1..=4)
+    };
+    let packets = (0..num_packets)
+        .map(|_| random_packet(rng, depth - 1))
+        .collect();
+    Packet::operator(version, operation, packets)
+}
+
+#[test]
+fn test_property_encode_round_trip() {
+    use advent_common::differential::differential_test;
+
+    let round_trips = |packet: &Packet| {
+        [
+            None,
+            Some(LengthType::TotalBits),
+            Some(LengthType::SubPacketCount),
+        ]
+        .into_iter()
+        .all(|length_type| {
+            let bytes = hex::decode(packet.to_hex(length_type).unwrap()).unwrap();
+            let (_, reparsed) = Packet::parse(BSlice(bytes.view_bits::<Msb0>())).unwrap();
+            *packet == reparsed
+        })
+    };
+    let sub_packets = |packet: &Packet| match &packet.contents {
+        PacketContents::Operator { packets, .. } => packets.clone(),
+        PacketContents::Literal(_) => vec![],
+    };
+
+    let result = differential_test(500, 4, random_packet, sub_packets, round_trips);
+    assert_eq!(Ok(()), result.map_err(|packet| packet.to_string()));
+}
+
+#[test]
+fn test_encode_length_types() {
+    // the first two examples from the puzzle, which differ only in length type
+    let packet = Packet::operator(
+        1,
+        LessThan,
+        vec![Packet::literal(6, 10), Packet::literal(2, 20)],
+    );
+    assert_eq!(
+        "38006F45291200",
+        packet.to_hex(Some(LengthType::TotalBits)).unwrap()
+    );
+    assert_eq!(
+        Err(EncodeError::TooManySubPackets(1 << 11)),
+        Packet::operator(0, Sum, vec![Packet::literal(0, 0); 1 << 11])
+            .encode_with(Some(LengthType::SubPacketCount))
+    );
+}
+
+#[test]
+fn test_display_sexpr() {
+    let bytes = hex::decode("9C0141080250320F1802104A08").unwrap();
+    let (_, packet) = Packet::parse(BSlice(bytes.view_bits::<Msb0>())).unwrap();
+    assert_eq!(
+        "(eq (sum (literal 1) (literal 3)) (product (literal 2) (literal 2)))",
+        packet.to_string()
+    );
+    assert_eq!(
+        "(eq\n  (sum\n    (literal 1)\n    (literal 3))\n  (product\n    (literal 2)\n    (literal 2)))",
+        format!("{packet:#}")
+    );
+}
@@ -1,210 +1,293 @@
 use itertools::Itertools;
-use std::{collections::LinkedList, rc::Rc};
+use std::{
+    fmt::{self, Display, Formatter},
+    iter::{self, Sum},
+    ops::Add,
+    str::FromStr,
+};
 
 pub fn solve(problem: &str) -> (u64, u64) {
-    let problem = &problem.lines().collect_vec();
-    // The addition section is a bit easier to solve in a flat structure because travelling up and
-    // down trees arbitrarily is hard
-    // I originally solved this using Vec, then switched to LinkedList to see what happened with
-    // performance. Performance is slightly worse. Oh well!
     let snailfish_numbers = problem
-        .iter()
-        .map(|number| {
-            number
-                .chars()
-                .filter_map(|c| match c {
-                    '[' => Some(SnailItem::Open),
-                    ']' => Some(SnailItem::Close),
-                    ',' => None,
-                    _ => Some(SnailItem::Value(c.to_digit(10).unwrap() as u64)),
-                })
-                .collect::<LinkedList<_>>()
-        })
+        .lines()
+        .map(|line| line.parse::<SnailfishNumber>().unwrap())
         .collect_vec();
-    (solve1(snailfish_numbers.clone()), solve2(snailfish_numbers))
+    (solve1(&snailfish_numbers), solve2(&snailfish_numbers))
 }
 
-fn solve1(snailfish_numbers: Vec<LinkedList<SnailItem>>) -> u64 {
-    let mut final_number = snailfish_numbers
-        .into_iter()
-        .reduce(add_snailfish_numbers)
-        .unwrap();
+/// Adds up the homework one number at a time, printing each sum before it is reduced and then
+/// every explode and split along with the number it leaves.
+pub fn print_trace(problem: &str) {
+    let mut numbers = problem
+        .lines()
+        .map(|line| line.parse::<SnailfishNumber>().unwrap());
+    let Some(mut total) = numbers.next() else {
+        return;
+    };
+    for number in numbers {
+        let sum = SnailfishNumber::pair(total, number);
+        println!("{sum}");
+        total = sum.clone();
+        for (action, reduced) in sum.reduction_trace() {
+            println!("{action}: {reduced}");
+            total = reduced;
+        }
+        println!();
+    }
+    println!("total: {total}");
+}
 
-    let tree = SnailFishNumberTree::parse(&mut final_number);
-    tree.magnitude()
+fn solve1(snailfish_numbers: &[SnailfishNumber]) -> u64 {
+    snailfish_numbers
+        .iter()
+        .cloned()
+        .sum::<SnailfishNumber>()
+        .magnitude()
 }
 
-fn solve2(snailfish_numbers: Vec<LinkedList<SnailItem>>) -> u64 {
+fn solve2(snailfish_numbers: &[SnailfishNumber]) -> u64 {
+    // addition isn't commutative, so both orders of every pair are tried
     snailfish_numbers
-        .into_iter()
-        .tuple_combinations()
-        .map(|(num1, num2)| {
-            let mut addition1 = add_snailfish_numbers(num1.clone(), num2.clone());
-            let sum1 = SnailFishNumberTree::parse(&mut addition1).magnitude();
-            let mut addition2 = add_snailfish_numbers(num2, num1);
-            let sum2 = SnailFishNumberTree::parse(&mut addition2).magnitude();
-            sum1.max(sum2)
-        })
+        .iter()
+        .permutations(2)
+        .map(|pair| (pair[0] + pair[1]).magnitude())
         .max()
         .unwrap()
 }
 
-#[derive(Debug)]
-enum SnailFishNumberTree {
-    Leaf(u64),
-    Branch {
-        left: Rc<SnailFishNumberTree>,
-        right: Rc<SnailFishNumberTree>,
-    },
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    Regular(u64),
+    Pair(Box<Element>, Box<Element>),
 }
 
-impl SnailFishNumberTree {
-    fn parse(num: &mut LinkedList<SnailItem>) -> Self {
-        match num.pop_front().unwrap() {
-            SnailItem::Open => {
-                let left = Self::parse(num);
-                let right = Self::parse(num);
+/// A pair whose elements are regular numbers or further pairs, e.g. `[[1,2],3]`. Adding two
+/// numbers pairs them up and then reduces the result until nothing explodes or splits any more.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SnailfishNumber(Element);
 
-                let close = num.pop_front().unwrap();
-                assert_eq!(SnailItem::Close, close);
+/// One step of reducing a snailfish number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Action {
+    /// The leftmost pair nested inside four pairs, with the values it held, turned into a 0.
+    Explode([u64; 2]),
+    /// The leftmost regular number of 10 or more was split into a pair.
+    Split(u64),
+}
 
-                SnailFishNumberTree::Branch {
-                    left: Rc::new(left),
-                    right: Rc::new(right),
-                }
-            }
-            SnailItem::Value(v) => SnailFishNumberTree::Leaf(v),
-            SnailItem::Close => panic!("expected something else, I guess"),
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Explode(_) => write!(f, "explode"),
+            Action::Split(_) => write!(f, "split"),
         }
     }
+}
+
+/// What an exploding pair still has to hand out to the regular numbers beside it.
+struct Explosion {
+    left: Option<u64>,
+    right: Option<u64>,
+    pair: [u64; 2],
+}
 
+impl Element {
     fn magnitude(&self) -> u64 {
         match self {
-            SnailFishNumberTree::Leaf(v) => *v,
-            SnailFishNumberTree::Branch { left, right } => {
-                left.magnitude() * 3 + right.magnitude() * 2
+            Element::Regular(value) => *value,
+            Element::Pair(left, right) => left.magnitude() * 3 + right.magnitude() * 2,
+        }
+    }
+
+    fn explode(&mut self, depth: usize) -> Option<Explosion> {
+        let Element::Pair(left, right) = self else {
+            return None;
+        };
+        if let (true, Element::Regular(a), Element::Regular(b)) = (depth >= 4, &**left, &**right) {
+            let pair = [*a, *b];
+            *self = Element::Regular(0);
+            return Some(Explosion {
+                left: Some(pair[0]),
+                right: Some(pair[1]),
+                pair,
+            });
+        }
+
+        if let Some(mut explosion) = left.explode(depth + 1) {
+            if let Some(value) = explosion.right.take() {
+                right.add_to_edge(value, false);
+            }
+            return Some(explosion);
+        }
+        if let Some(mut explosion) = right.explode(depth + 1) {
+            if let Some(value) = explosion.left.take() {
+                left.add_to_edge(value, true);
             }
+            return Some(explosion);
         }
+        None
     }
-}
 
-#[allow(unused)]
-fn display(num: &[SnailItem]) {
-    let num = num
-        .iter()
-        .map(|item| match item {
-            SnailItem::Open => "[".to_string(),
-            SnailItem::Close => "]".to_string(),
-            SnailItem::Value(v) => v.to_string(),
-        })
-        .join("");
-    println!("{num}");
-}
-
-fn add_snailfish_numbers(
-    mut num1: LinkedList<SnailItem>,
-    mut num2: LinkedList<SnailItem>,
-) -> LinkedList<SnailItem> {
-    num1.push_front(SnailItem::Open);
-    num1.append(&mut num2);
-    num1.push_back(SnailItem::Close);
-
-    let mut combined = num1;
-    while let Some(action) = next_action(&combined) {
-        match action {
-            SnailAction::Explode(idx) => explode_at(&mut combined, idx),
-            SnailAction::Split(idx) => split_at(&mut combined, idx),
+    /// Adds `value` to the rightmost regular number if `rightmost`, otherwise the leftmost.
+    fn add_to_edge(&mut self, value: u64, rightmost: bool) {
+        match self {
+            Element::Regular(regular) => *regular += value,
+            Element::Pair(_, right) if rightmost => right.add_to_edge(value, rightmost),
+            Element::Pair(left, _) => left.add_to_edge(value, rightmost),
+        }
+    }
+
+    fn split(&mut self) -> Option<u64> {
+        match self {
+            Element::Regular(value) if *value >= 10 => {
+                let value = *value;
+                *self = Element::Pair(
+                    Box::new(Element::Regular(value / 2)),
+                    Box::new(Element::Regular(value - value / 2)),
+                );
+                Some(value)
+            }
+            Element::Regular(_) => None,
+            Element::Pair(left, right) => left.split().or_else(|| right.split()),
         }
     }
-    combined
 }
 
-fn explode_at(snailfish_num: &mut LinkedList<SnailItem>, idx: usize) {
-    // |   |    idx     |             |   |
-    // | [ | left_value | right_value | ] |
-    let left = snailfish_num;
-    let mut middle = left.split_off(idx - 1);
-    let mut right = middle.split_off(4);
+impl SnailfishNumber {
+    /// Pairs up two numbers without reducing the result.
+    pub(crate) fn pair(lhs: Self, rhs: Self) -> Self {
+        Self(Element::Pair(Box::new(lhs.0), Box::new(rhs.0)))
+    }
 
-    middle.pop_front();
-    let left_value = match middle.pop_front().unwrap() {
-        SnailItem::Value(v) => v,
-        _ => panic!("this was supposed to be a raw value"),
-    };
-    let right_value = match middle.pop_front().unwrap() {
-        SnailItem::Value(v) => v,
-        _ => panic!("this was supposed to be a raw value"),
-    };
+    pub(crate) fn magnitude(&self) -> u64 {
+        self.0.magnitude()
+    }
 
-    for item in left.iter_mut().rev() {
-        if let SnailItem::Value(v) = item {
-            *v += left_value;
-            break;
+    /// Applies the first reduce action that applies, if any.
+    pub(crate) fn reduce_step(&mut self) -> Option<Action> {
+        if let Some(explosion) = self.0.explode(0) {
+            return Some(Action::Explode(explosion.pair));
         }
+        self.0.split().map(Action::Split)
+    }
+
+    pub(crate) fn reduce(&mut self) {
+        while self.reduce_step().is_some() {}
+    }
+
+    /// Every action taken while reducing this number, along with the number right after it.
+    pub(crate) fn reduction_trace(mut self) -> impl Iterator<Item = (Action, SnailfishNumber)> {
+        iter::from_fn(move || {
+            let action = self.reduce_step()?;
+            Some((action, self.clone()))
+        })
+    }
+}
+
+impl Add for SnailfishNumber {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let mut sum = Self::pair(self, rhs);
+        sum.reduce();
+        sum
+    }
+}
+
+impl Add for &SnailfishNumber {
+    type Output = SnailfishNumber;
+
+    fn add(self, rhs: Self) -> SnailfishNumber {
+        self.clone() + rhs.clone()
     }
-    for item in right.iter_mut() {
-        if let SnailItem::Value(v) = item {
-            *v += right_value;
-            break;
+}
+
+/// Adds the numbers up in order. There is no snailfish zero, so the list can't be empty.
+impl Sum for SnailfishNumber {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(Add::add)
+            .expect("can't sum an empty list of snailfish numbers")
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Element::Regular(value) => write!(f, "{value}"),
+            Element::Pair(left, right) => write!(f, "[{left},{right}]"),
         }
     }
+}
 
-    left.push_back(SnailItem::Value(0));
-    left.append(&mut right);
+impl Display for SnailfishNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
 }
 
-fn split_at(snailfish_num: &mut LinkedList<SnailItem>, idx: usize) {
-    let left = snailfish_num;
-    let mut right = left.split_off(idx);
+/// Where in the source a snailfish number stopped making sense.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ParseSnailfishError {
+    offset: usize,
+}
 
-    let value = match right.pop_front().unwrap() {
-        SnailItem::Value(v) => v,
-        _ => panic!("this was supposed to be a raw value"),
-    };
+impl Display for ParseSnailfishError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "could not parse snailfish number at offset {}",
+            self.offset
+        )
+    }
+}
 
-    let new_left_value = value / 2;
-    let new_right_value = value - new_left_value;
-
-    left.push_back(SnailItem::Open);
-    left.push_back(SnailItem::Value(new_left_value));
-    left.push_back(SnailItem::Value(new_right_value));
-    left.push_back(SnailItem::Close);
-    left.append(&mut right);
-}
-
-fn next_action(snailfish_num: &LinkedList<SnailItem>) -> Option<SnailAction> {
-    let mut open_count = 0;
-    let mut maybe_split = None;
-    for (idx, item) in snailfish_num.iter().enumerate() {
-        match item {
-            SnailItem::Open => {
-                open_count += 1;
-                if open_count > 4 {
-                    return Some(SnailAction::Explode(idx + 1));
-                }
-            }
-            SnailItem::Close => open_count -= 1,
-            SnailItem::Value(v) => {
-                if v > &9 && maybe_split.is_none() {
-                    maybe_split = Some(SnailAction::Split(idx))
-                }
-            }
-        };
+impl FromStr for SnailfishNumber {
+    type Err = ParseSnailfishError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        let mut offset = 0;
+        let element = parse_element(bytes, &mut offset)?;
+        if offset != bytes.len() || !matches!(element, Element::Pair(..)) {
+            return Err(ParseSnailfishError { offset });
+        }
+        Ok(Self(element))
     }
-    maybe_split
 }
 
-#[derive(Debug)]
-enum SnailAction {
-    Explode(usize),
-    Split(usize),
+fn parse_element(bytes: &[u8], offset: &mut usize) -> Result<Element, ParseSnailfishError> {
+    let expect = |byte: u8, offset: &mut usize| {
+        if bytes.get(*offset) == Some(&byte) {
+            *offset += 1;
+            Ok(())
+        } else {
+            Err(ParseSnailfishError { offset: *offset })
+        }
+    };
+
+    if bytes.get(*offset) == Some(&b'[') {
+        *offset += 1;
+        let left = parse_element(bytes, offset)?;
+        expect(b',', offset)?;
+        let right = parse_element(bytes, offset)?;
+        expect(b']', offset)?;
+        return Ok(Element::Pair(Box::new(left), Box::new(right)));
+    }
+
+    let digits = bytes[*offset..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    let value = std::str::from_utf8(&bytes[*offset..*offset + digits])
+        .unwrap()
+        .parse()
+        .map_err(|_| ParseSnailfishError { offset: *offset })?;
+    *offset += digits;
+    Ok(Element::Regular(value))
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum SnailItem {
-    Open,
-    Close,
-    Value(u64),
+#[cfg(test)]
+fn snailfish(number: &str) -> SnailfishNumber {
+    number.parse().unwrap()
 }
 
 #[test]
@@ -213,3 +296,217 @@ fn test() {
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_parse_and_display() {
+    let numbers = [
+        "[1,2]",
+        "[[1,2],3]",
+        "[9,[8,7]]",
+        "[[1,9],[8,5]]",
+        "[[[[1,2],[3,4]],[[5,6],[7,8]]],9]",
+        "[[[9,[3,8]],[[0,9],6]],[[[3,7],[4,9]],3]]",
+        "[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]",
+    ];
+    for number in numbers {
+        assert_eq!(number, snailfish(number).to_string());
+    }
+    assert_eq!(
+        "[[1,2],[[3,4],5]]",
+        (snailfish("[1,2]") + snailfish("[[3,4],5]")).to_string()
+    );
+
+    let error = |offset| Err(ParseSnailfishError { offset });
+    assert_eq!(error(0), "".parse::<SnailfishNumber>());
+    assert_eq!(error(1), "7".parse::<SnailfishNumber>());
+    assert_eq!(error(2), "[1]".parse::<SnailfishNumber>());
+    assert_eq!(error(5), "[1,2]]".parse::<SnailfishNumber>());
+    assert_eq!(error(3), "[1,x]".parse::<SnailfishNumber>());
+}
+
+#[test]
+fn test_single_explode() {
+    let examples = [
+        ("[[[[[9,8],1],2],3],4]", [9, 8], "[[[[0,9],2],3],4]"),
+        ("[7,[6,[5,[4,[3,2]]]]]", [3, 2], "[7,[6,[5,[7,0]]]]"),
+        ("[[6,[5,[4,[3,2]]]],1]", [3, 2], "[[6,[5,[7,0]]],3]"),
+        (
+            "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]",
+            [7, 3],
+            "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
+        ),
+        (
+            "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
+            [3, 2],
+            "[[3,[2,[8,0]]],[9,[5,[7,0]]]]",
+        ),
+    ];
+    for (before, pair, after) in examples {
+        let mut number = snailfish(before);
+        assert_eq!(Some(Action::Explode(pair)), number.reduce_step());
+        assert_eq!(after, number.to_string());
+    }
+}
+
+#[test]
+fn test_split() {
+    for (value, pair) in [(10, "[5,5]"), (11, "[5,6]"), (12, "[6,6]")] {
+        let mut number = snailfish(&format!("[{value},0]"));
+        assert_eq!(Some(Action::Split(value)), number.reduce_step());
+        assert_eq!(format!("[{pair},0]"), number.to_string());
+    }
+}
+
+#[test]
+fn test_reduction_trace() {
+    let sum = SnailfishNumber::pair(
+        snailfish("[[[[4,3],4],4],[7,[[8,4],9]]]"),
+        snailfish("[1,1]"),
+    );
+    assert_eq!("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]", sum.to_string());
+
+    let trace = sum
+        .reduction_trace()
+        .map(|(action, number)| format!("after {action}: {number}"))
+        .collect_vec();
+    let expected = [
+        "after explode: [[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+        "after explode: [[[[0,7],4],[15,[0,13]]],[1,1]]",
+        "after split: [[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+        "after split: [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+        "after explode: [[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+    ];
+    assert_eq!(expected.as_slice(), trace);
+
+    let actions = snailfish("[[[[4,3],4],4],[7,[[8,4],9]]]");
+    let actions = SnailfishNumber::pair(actions, snailfish("[1,1]"))
+        .reduction_trace()
+        .map(|(action, _)| action)
+        .collect_vec();
+    assert_eq!(
+        vec![
+            Action::Explode([4, 3]),
+            Action::Explode([8, 4]),
+            Action::Split(15),
+            Action::Split(13),
+            Action::Explode([6, 7]),
+        ],
+        actions
+    );
+}
+
+#[test]
+fn test_sum_lists() {
+    let sum_of = |count: u64| {
+        (1..=count)
+            .map(|n| snailfish(&format!("[{n},{n}]")))
+            .sum::<SnailfishNumber>()
+            .to_string()
+    };
+    assert_eq!("[[[[1,1],[2,2]],[3,3]],[4,4]]", sum_of(4));
+    assert_eq!("[[[[3,0],[5,3]],[4,4]],[5,5]]", sum_of(5));
+    assert_eq!("[[[[5,0],[7,4]],[5,5]],[6,6]]", sum_of(6));
+}
+
+#[test]
+fn test_larger_example_steps() {
+    let steps = [
+        (
+            "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]",
+            "[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]",
+            "[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]",
+        ),
+        (
+            "[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]",
+            "[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]",
+            "[[[[6,7],[6,7]],[[7,7],[0,7]]],[[[8,7],[7,7]],[[8,8],[8,0]]]]",
+        ),
+        (
+            "[[[[6,7],[6,7]],[[7,7],[0,7]]],[[[8,7],[7,7]],[[8,8],[8,0]]]]",
+            "[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]",
+            "[[[[7,0],[7,7]],[[7,7],[7,8]]],[[[7,7],[8,8]],[[7,7],[8,7]]]]",
+        ),
+        (
+            "[[[[7,0],[7,7]],[[7,7],[7,8]]],[[[7,7],[8,8]],[[7,7],[8,7]]]]",
+            "[7,[5,[[3,8],[1,4]]]]",
+            "[[[[7,7],[7,8]],[[9,5],[8,7]]],[[[6,8],[0,8]],[[9,9],[9,0]]]]",
+        ),
+        (
+            "[[[[7,7],[7,8]],[[9,5],[8,7]]],[[[6,8],[0,8]],[[9,9],[9,0]]]]",
+            "[[2,[2,2]],[8,[8,1]]]",
+            "[[[[6,6],[6,6]],[[6,0],[6,7]]],[[[7,7],[8,9]],[8,[8,1]]]]",
+        ),
+        (
+            "[[[[6,6],[6,6]],[[6,0],[6,7]]],[[[7,7],[8,9]],[8,[8,1]]]]",
+            "[2,9]",
+            "[[[[6,6],[7,7]],[[0,7],[7,7]]],[[[5,5],[5,6]],9]]",
+        ),
+        (
+            "[[[[6,6],[7,7]],[[0,7],[7,7]]],[[[5,5],[5,6]],9]]",
+            "[1,[[[9,3],9],[[9,0],[0,7]]]]",
+            "[[[[7,8],[6,7]],[[6,8],[0,8]]],[[[7,7],[5,0]],[[5,5],[5,6]]]]",
+        ),
+        (
+            "[[[[7,8],[6,7]],[[6,8],[0,8]]],[[[7,7],[5,0]],[[5,5],[5,6]]]]",
+            "[[[5,[7,4]],7],1]",
+            "[[[[7,7],[7,7]],[[8,7],[8,7]]],[[[7,0],[7,7]],9]]",
+        ),
+        (
+            "[[[[7,7],[7,7]],[[8,7],[8,7]]],[[[7,0],[7,7]],9]]",
+            "[[[[4,2],2],6],[8,7]]",
+            "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]",
+        ),
+    ];
+    for (lhs, rhs, sum) in steps {
+        assert_eq!(sum, (snailfish(lhs) + snailfish(rhs)).to_string());
+    }
+
+    let total = iter::once(steps[0].0)
+        .chain(steps.iter().map(|(_, rhs, _)| *rhs))
+        .map(snailfish)
+        .sum::<SnailfishNumber>();
+    assert_eq!(steps[8].2, total.to_string());
+}
+
+#[test]
+fn test_homework_sums() {
+    let homework = crate::utils::parse_example_testcases(include_str!("example.txt"))
+        .remove(0)
+        .problem;
+    let numbers = homework.lines().map(snailfish).collect_vec();
+
+    let total = numbers.iter().cloned().sum::<SnailfishNumber>();
+    assert_eq!(
+        "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]",
+        total.to_string()
+    );
+    assert_eq!(
+        "[[[[7,8],[6,6]],[[6,0],[7,7]]],[[[7,8],[8,8]],[[7,9],[0,6]]]]",
+        (&numbers[8] + &numbers[0]).to_string()
+    );
+}
+
+#[test]
+fn test_magnitude() {
+    let examples = [
+        ("[9,1]", 29),
+        ("[1,9]", 21),
+        ("[[9,1],[1,9]]", 129),
+        ("[[1,2],[[3,4],5]]", 143),
+        ("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", 1384),
+        ("[[[[1,1],[2,2]],[3,3]],[4,4]]", 445),
+        ("[[[[3,0],[5,3]],[4,4]],[5,5]]", 791),
+        ("[[[[5,0],[7,4]],[5,5]],[6,6]]", 1137),
+        (
+            "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]",
+            3488,
+        ),
+        (
+            "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]",
+            4140,
+        ),
+    ];
+    for (number, magnitude) in examples {
+        assert_eq!(magnitude, snailfish(number).magnitude(), "{number}");
+    }
+}
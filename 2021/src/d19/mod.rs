@@ -1,24 +1,35 @@
 use itertools::Itertools;
 use nom::{
-    branch::alt,
     bytes::complete::tag,
-    character::complete::digit1,
-    combinator::recognize,
-    multi::{many0, many1, separated_list1},
+    character::complete::{digit1, i32 as parse_i32},
+    combinator::map,
+    multi::{many0, many1},
     sequence::{preceded, terminated, tuple},
     IResult,
 };
 use std::{
-    collections::{BTreeSet, LinkedList},
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
     ops::Sub,
 };
 
+mod registration;
+
 pub fn solve(problem: &str) -> (u64, u64) {
     let scanners = parse_scanners(problem);
-    let calibrated = calibrated_scanners(&scanners);
+    let calibrated = calibrated_scanners(&scanners).unwrap_or_else(|err| panic!("{err}"));
     (solve1(&calibrated), solve2(&calibrated))
 }
 
+/// Prints which scanners overlap and the transform between each pair.
+pub fn print_registration(problem: &str) {
+    let scanners = parse_scanners(problem);
+    print!(
+        "{}",
+        registration::register(&scanners, registration::DEFAULT_OVERLAP)
+    );
+}
+
 fn solve1(calibrated_scanners: &[Scanner]) -> u64 {
     let deduped_beacons = dedup_beacon_locations(calibrated_scanners);
     deduped_beacons.len() as u64
@@ -38,21 +49,31 @@ fn parse_scanners(input: &str) -> Vec<Scanner> {
     scanners
 }
 
-fn calibrated_scanners(scanners: &[Scanner]) -> Vec<Scanner> {
-    let mut calibrated = vec![scanners[0].clone()];
-    let mut to_calibrate: LinkedList<_> = scanners[1..].iter().cloned().map(|s| (0, s)).collect();
-
-    while !to_calibrate.is_empty() {
-        let (next_idx_to_calibrate_against, uncalibrated) = to_calibrate.pop_front().unwrap();
-        let to_calibrate_against = &calibrated[next_idx_to_calibrate_against];
-        let maybe_calibrated = uncalibrated.calibrated_against(to_calibrate_against);
-        match maybe_calibrated {
-            None => to_calibrate.push_back((next_idx_to_calibrate_against + 1, uncalibrated)),
-            Some(scanner) => calibrated.push(scanner),
-        }
+#[derive(Debug, PartialEq)]
+struct Disconnected(Vec<usize>);
+
+impl Display for Disconnected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "scanners {} overlap nothing connected to scanner 0",
+            self.0.iter().join(", ")
+        )
     }
+}
 
-    calibrated
+/// Every scanner in scanner 0's coordinates, or the ones that can't be placed relative to it.
+fn calibrated_scanners(scanners: &[Scanner]) -> Result<Vec<Scanner>, Disconnected> {
+    let registration = registration::register(scanners, registration::DEFAULT_OVERLAP);
+    let unplaced = registration.unplaced();
+    if !unplaced.is_empty() {
+        return Err(Disconnected(unplaced));
+    }
+    Ok(scanners
+        .iter()
+        .zip(&registration.placements)
+        .map(|(scanner, placement)| scanner.transformed(placement.as_ref().unwrap()))
+        .collect())
 }
 
 fn dedup_beacon_locations(scanners: &[Scanner]) -> BTreeSet<Point> {
@@ -63,13 +84,13 @@ fn dedup_beacon_locations(scanners: &[Scanner]) -> BTreeSet<Point> {
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Ord, Eq)]
-struct Scanner {
+pub(crate) struct Scanner {
     center: Point,
     beacon_locations: BTreeSet<Point>,
 }
 
 impl Scanner {
-    fn parse(input: &str) -> IResult<&str, Self> {
+    pub(crate) fn parse(input: &str) -> IResult<&str, Self> {
         let (input, coords) = preceded(
             tuple((tag("--- scanner "), many1(digit1), tag(" ---"))),
             terminated(many1(Point::parse), many1(tag("\n"))),
@@ -83,78 +104,6 @@ impl Scanner {
             },
         ))
     }
-
-    fn all_permutations(&self) -> Vec<Self> {
-        // make each side face front
-        let front = self.clone();
-        let right = front.rotated_about(&Axis::Z);
-        let back = right.rotated_about(&Axis::Z);
-        let left = back.rotated_about(&Axis::Z);
-        let top = front.rotated_about(&Axis::Y);
-        let bottom = top.rotated_about(&Axis::Y).rotated_about(&Axis::Y);
-        [front, right, back, left, top, bottom]
-            .into_iter()
-            .flat_map(|scanner| {
-                let rot_0 = scanner;
-                let rot_1 = rot_0.rotated_about(&Axis::X);
-                let rot_2 = rot_1.rotated_about(&Axis::X);
-                let rot_3 = rot_2.rotated_about(&Axis::X);
-                [rot_0, rot_1, rot_2, rot_3]
-            })
-            .collect()
-    }
-    fn calibrated_against(&self, other: &Scanner) -> Option<Self> {
-        self.all_permutations()
-            .iter()
-            .filter_map(|scanner| scanner.calibrated_translational_against(other))
-            .at_most_one()
-            .expect("there should not be more than one match")
-    }
-
-    fn calibrated_translational_against(&self, other: &Scanner) -> Option<Scanner> {
-        self.beacon_locations
-            .iter()
-            .cartesian_product(&other.beacon_locations)
-            .filter_map(|(self_point, other_point)| {
-                let diff = other_point - self_point;
-                let translated = self.translate(&diff);
-                let intersection = translated
-                    .beacon_locations
-                    .intersection(&other.beacon_locations)
-                    .collect_vec();
-
-                if intersection.len() >= 12 {
-                    return Some(translated);
-                }
-                None
-            })
-            .next()
-    }
-
-    /// 90 degrees counter-clockwise
-    fn rotated_about(&self, axis: &Axis) -> Self {
-        Self {
-            center: self.center.clone(),
-            beacon_locations: self
-                .beacon_locations
-                .iter()
-                .cloned()
-                .map(|p| p.rotated_about(axis))
-                .collect(),
-        }
-    }
-
-    fn translate(&self, vector3: &Vector3) -> Self {
-        Self {
-            center: self.center.plus(vector3),
-            beacon_locations: self
-                .beacon_locations
-                .iter()
-                .cloned()
-                .map(|p| p.plus(vector3))
-                .collect(),
-        }
-    }
 }
 
 enum Axis {
@@ -164,7 +113,7 @@ enum Axis {
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Ord, Eq, Hash)]
-struct Point {
+pub(crate) struct Point {
     x: i32,
     y: i32,
     z: i32,
@@ -172,18 +121,17 @@ struct Point {
 
 impl Point {
     fn parse(input: &str) -> IResult<&str, Self> {
-        let (input, nums): (_, Vec<&str>) = preceded(
-            tag("\n"),
-            separated_list1(tag(","), recognize(many1(alt((tag("-"), digit1))))),
-        )(input)?;
-
-        let (x, y, z) = nums.into_iter().collect_tuple().unwrap();
-
-        let x = x.parse::<i32>().unwrap();
-        let y = y.parse::<i32>().unwrap();
-        let z = z.parse::<i32>().unwrap();
-
-        Ok((input, Self { x, y, z }))
+        map(
+            preceded(
+                tag("\n"),
+                tuple((
+                    parse_i32,
+                    preceded(tag(","), parse_i32),
+                    preceded(tag(","), parse_i32),
+                )),
+            ),
+            |(x, y, z)| Self { x, y, z },
+        )(input)
     }
 
     /// 90 degrees counter-clockwise
@@ -220,6 +168,23 @@ impl Point {
     }
 }
 
+impl Display for Point {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+impl Display for Scanner {
+    /// The scanner's number is not kept when parsing, so it is always written as 0.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "--- scanner 0 ---")?;
+        for beacon in &self.beacon_locations {
+            write!(f, "\n{beacon}")?;
+        }
+        writeln!(f)
+    }
+}
+
 impl Sub for &Point {
     type Output = Vector3;
 
@@ -233,7 +198,7 @@ impl Sub for &Point {
 }
 
 #[derive(Debug, Eq, PartialOrd, PartialEq, Hash, Clone)]
-struct Vector3 {
+pub(crate) struct Vector3 {
     x: i32,
     y: i32,
     z: i32,
@@ -310,6 +275,17 @@ fn test_scanners_parse() {
     )
 }
 
+#[test]
+fn test_disconnected_scanners() {
+    let example = crate::utils::parse_example_testcases(include_str!("example.txt"));
+    let scanners = parse_scanners(&example[0].problem);
+    // scanner 2 only overlaps scanner 4
+    assert_eq!(
+        Err(Disconnected(vec![2])),
+        calibrated_scanners(&scanners[..4]).map(|calibrated| calibrated.len())
+    );
+}
+
 #[test]
 fn test_point_rotation() {
     let p = Point { x: 1, y: 1, z: 1 };
@@ -324,9 +300,14 @@ fn test_scanner_permutations() {
         beacon_locations: BTreeSet::from([Point { x: 1, y: 2, z: 3 }]),
     };
 
-    let actual = scanner
-        .all_permutations()
+    let actual = registration::Rotation::all()
         .into_iter()
+        .map(|rotation| {
+            scanner.transformed(&registration::Transform {
+                rotation,
+                translation: Vector3 { x: 0, y: 0, z: 0 },
+            })
+        })
         .sorted()
         .collect_vec();
     let center_zero = Point { x: 0, y: 0, z: 0 };
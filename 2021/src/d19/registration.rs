@@ -0,0 +1,412 @@
+use super::{Axis, Point, Scanner, Vector3};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Display, Formatter},
+};
+
+/// How many beacons two scanners have to see in common before they're taken to overlap.
+pub(crate) const DEFAULT_OVERLAP: usize = 12;
+
+/// One of the 24 ways a scanner can be facing, as a matrix applied to column vectors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Rotation([[i32; 3]; 3]);
+
+impl Rotation {
+    const IDENTITY: Self = Self([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+
+    /// Every orientation, found by turning each side to face front and then rolling it around
+    /// the x axis.
+    pub(crate) fn all() -> Vec<Self> {
+        let quarter_turns = |axis: Axis, turns: usize| {
+            let basis = Self::IDENTITY.columns();
+            let turned = basis.map(|column| (0..turns).fold(column, |p, _| p.rotated_about(&axis)));
+            Self::from_columns(turned)
+        };
+        let facings = [
+            Self::IDENTITY,
+            quarter_turns(Axis::Z, 1),
+            quarter_turns(Axis::Z, 2),
+            quarter_turns(Axis::Z, 3),
+            quarter_turns(Axis::Y, 1),
+            quarter_turns(Axis::Y, 3),
+        ];
+        facings
+            .into_iter()
+            .flat_map(|facing| (0..4).map(move |turns| quarter_turns(Axis::X, turns).then(&facing)))
+            .collect()
+    }
+
+    fn columns(&self) -> [Point; 3] {
+        let m = self.0;
+        [0, 1, 2].map(|col| Point {
+            x: m[0][col],
+            y: m[1][col],
+            z: m[2][col],
+        })
+    }
+
+    fn from_columns(columns: [Point; 3]) -> Self {
+        let [a, b, c] = columns;
+        Self([[a.x, b.x, c.x], [a.y, b.y, c.y], [a.z, b.z, c.z]])
+    }
+
+    fn apply(&self, [x, y, z]: [i32; 3]) -> [i32; 3] {
+        self.0.map(|row| row[0] * x + row[1] * y + row[2] * z)
+    }
+
+    /// This rotation applied after `first`.
+    fn then(&self, first: &Self) -> Self {
+        Self::from_columns(first.columns().map(|column| self.rotate(&column)))
+    }
+
+    fn inverse(&self) -> Self {
+        let m = self.0;
+        Self([0, 1, 2].map(|row| [m[0][row], m[1][row], m[2][row]]))
+    }
+
+    fn rotate(&self, point: &Point) -> Point {
+        let [x, y, z] = self.apply([point.x, point.y, point.z]);
+        Point { x, y, z }
+    }
+
+    fn rotate_vector(&self, vector: &Vector3) -> Vector3 {
+        let [x, y, z] = self.apply([vector.x, vector.y, vector.z]);
+        Vector3 { x, y, z }
+    }
+}
+
+/// Takes coordinates relative to one scanner to coordinates relative to another: rotate, then
+/// translate.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Transform {
+    pub(crate) rotation: Rotation,
+    pub(crate) translation: Vector3,
+}
+
+impl Transform {
+    const IDENTITY: Self = Self {
+        rotation: Rotation::IDENTITY,
+        translation: Vector3 { x: 0, y: 0, z: 0 },
+    };
+
+    pub(crate) fn apply(&self, point: &Point) -> Point {
+        self.rotation.rotate(point).plus(&self.translation)
+    }
+
+    /// This transform applied after `first`.
+    fn then(&self, first: &Self) -> Self {
+        let translation = self.rotation.rotate_vector(&first.translation);
+        Self {
+            rotation: self.rotation.then(&first.rotation),
+            translation: Vector3 {
+                x: translation.x + self.translation.x,
+                y: translation.y + self.translation.y,
+                z: translation.z + self.translation.z,
+            },
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let back = rotation.rotate_vector(&self.translation);
+        Self {
+            rotation,
+            translation: Vector3 {
+                x: -back.x,
+                y: -back.y,
+                z: -back.z,
+            },
+        }
+    }
+}
+
+/// The squared distances between every pair of a scanner's beacons, which stay the same however
+/// the scanner is turned or moved. Two scanners sharing `n` beacons share at least `n * (n - 1) / 2`
+/// of these, so comparing them rules out most pairs before trying any rotations.
+#[derive(Debug)]
+pub(crate) struct Fingerprint(HashMap<i64, usize>);
+
+impl Fingerprint {
+    pub(crate) fn new(scanner: &Scanner) -> Self {
+        let mut distances = HashMap::new();
+        for (a, b) in scanner.beacon_locations.iter().tuple_combinations() {
+            let d = a - b;
+            let squared = [d.x, d.y, d.z].map(|c| c as i64 * c as i64).iter().sum();
+            *distances.entry(squared).or_default() += 1;
+        }
+        Self(distances)
+    }
+
+    /// How many distances the two scanners have in common, counting repeats.
+    pub(crate) fn shared(&self, other: &Self) -> usize {
+        self.0
+            .iter()
+            .map(|(distance, count)| other.0.get(distance).map_or(0, |other| *count.min(other)))
+            .sum()
+    }
+}
+
+/// The transform taking `other`'s coordinates to `reference`'s, if at least `overlap` of their
+/// beacons line up under it.
+pub(crate) fn align(reference: &Scanner, other: &Scanner, overlap: usize) -> Option<Transform> {
+    Rotation::all().into_iter().find_map(|rotation| {
+        let mut votes = HashMap::new();
+        for beacon in &other.beacon_locations {
+            let rotated = rotation.rotate(beacon);
+            for fixed in &reference.beacon_locations {
+                let offset = fixed - &rotated;
+                let count = votes.entry(offset.clone()).or_insert(0);
+                *count += 1;
+                if *count >= overlap {
+                    return Some(Transform {
+                        rotation,
+                        translation: offset,
+                    });
+                }
+            }
+        }
+        None
+    })
+}
+
+/// How a set of scanners fit together.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    /// Every overlapping pair `(i, j)` with `i < j`, and the transform taking scanner `j`'s
+    /// coordinates to scanner `i`'s.
+    pub(crate) edges: Vec<(usize, usize, Transform)>,
+    /// For each scanner, the transform taking its coordinates to scanner 0's, unless no chain of
+    /// overlaps connects the two.
+    pub(crate) placements: Vec<Option<Transform>>,
+}
+
+/// Finds every overlapping pair of scanners in parallel, then places each scanner relative to
+/// scanner 0 by walking outwards along the overlaps.
+pub(crate) fn register(scanners: &[Scanner], overlap: usize) -> Registration {
+    let fingerprints = scanners
+        .par_iter()
+        .map(Fingerprint::new)
+        .collect::<Vec<_>>();
+    let shared_needed = overlap * overlap.saturating_sub(1) / 2;
+    let mut edges = (0..scanners.len())
+        .tuple_combinations()
+        .collect_vec()
+        .into_par_iter()
+        .filter(|&(i, j)| fingerprints[i].shared(&fingerprints[j]) >= shared_needed)
+        .filter_map(|(i, j)| Some((i, j, align(&scanners[i], &scanners[j], overlap)?)))
+        .collect::<Vec<_>>();
+    edges.sort_by_key(|&(i, j, _)| (i, j));
+
+    let mut neighbours = vec![vec![]; scanners.len()];
+    for (i, j, transform) in &edges {
+        neighbours[*i].push((*j, transform.clone()));
+        neighbours[*j].push((*i, transform.inverse()));
+    }
+    let mut placements = vec![None; scanners.len()];
+    let mut queue = VecDeque::new();
+    if !scanners.is_empty() {
+        placements[0] = Some(Transform::IDENTITY);
+        queue.push_back(0);
+    }
+    while let Some(current) = queue.pop_front() {
+        let placement = placements[current].clone().unwrap();
+        for (next, transform) in &neighbours[current] {
+            if placements[*next].is_none() {
+                placements[*next] = Some(placement.then(transform));
+                queue.push_back(*next);
+            }
+        }
+    }
+
+    Registration { edges, placements }
+}
+
+impl Registration {
+    /// The scanners no chain of overlaps connects to scanner 0.
+    pub(crate) fn unplaced(&self) -> Vec<usize> {
+        self.placements
+            .iter()
+            .positions(|placement| placement.is_none())
+            .collect()
+    }
+}
+
+impl Display for Registration {
+    /// One line per overlapping pair, then any scanners left out.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (
+            i,
+            j,
+            Transform {
+                rotation,
+                translation,
+            },
+        ) in &self.edges
+        {
+            let Vector3 { x, y, z } = translation;
+            writeln!(
+                f,
+                "{j} -> {i}: rotate {:?}, move by {x},{y},{z}",
+                rotation.0
+            )?;
+        }
+        for unplaced in self.unplaced() {
+            writeln!(f, "{unplaced} overlaps nothing connected to 0")?;
+        }
+        Ok(())
+    }
+}
+
+impl Scanner {
+    /// The scanner and its beacons in the coordinates `transform` leads to.
+    pub(crate) fn transformed(&self, transform: &Transform) -> Self {
+        Self {
+            center: transform.apply(&self.center),
+            beacon_locations: self
+                .beacon_locations
+                .iter()
+                .map(|beacon| transform.apply(beacon))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+fn example_scanners() -> Vec<Scanner> {
+    let example = crate::utils::parse_example_testcases(include_str!("example.txt"));
+    super::parse_scanners(&example[0].problem)
+}
+
+#[test]
+fn test_rotations_are_distinct_and_proper() {
+    let rotations = Rotation::all();
+    assert_eq!(24, rotations.iter().unique().count());
+    for rotation in &rotations {
+        let [a, b, c] = rotation.0;
+        let determinant = a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+            + a[2] * (b[0] * c[1] - b[1] * c[0]);
+        assert_eq!(1, determinant, "{rotation:?}");
+        assert_eq!(Rotation::IDENTITY, rotation.then(&rotation.inverse()));
+    }
+}
+
+#[test]
+fn test_transform_composition() {
+    let rotations = Rotation::all();
+    let first = Transform {
+        rotation: rotations[7],
+        translation: Vector3 { x: 5, y: -3, z: 11 },
+    };
+    let second = Transform {
+        rotation: rotations[17],
+        translation: Vector3 { x: -8, y: 2, z: 0 },
+    };
+    let point = Point { x: 3, y: -14, z: 7 };
+    assert_eq!(
+        second.apply(&first.apply(&point)),
+        second.then(&first).apply(&point)
+    );
+    assert_eq!(point, first.inverse().apply(&first.apply(&point)));
+}
+
+#[test]
+fn test_example_transform_graph() {
+    let scanners = example_scanners();
+    let registration = register(&scanners, DEFAULT_OVERLAP);
+
+    let pairs = registration
+        .edges
+        .iter()
+        .map(|(i, j, _)| (*i, *j))
+        .collect_vec();
+    assert_eq!(vec![(0, 1), (1, 3), (1, 4), (2, 4)], pairs);
+    assert!(registration
+        .to_string()
+        .starts_with("1 -> 0: rotate [[-1, 0, 0], [0, 1, 0], [0, 0, -1]], move by 68,-1246,-43\n"));
+
+    let centers = registration
+        .placements
+        .iter()
+        .map(|placement| placement.as_ref().unwrap().translation.clone())
+        .map(|Vector3 { x, y, z }| (x, y, z))
+        .collect_vec();
+    assert_eq!(
+        vec![
+            (0, 0, 0),
+            (68, -1246, -43),
+            (1105, -1205, 1229),
+            (-92, -2380, -20),
+            (-20, -1133, 1061),
+        ],
+        centers
+    );
+}
+
+#[test]
+fn test_overlap_threshold() {
+    let scanners = example_scanners();
+    // scanners 0 and 1 share exactly 12 beacons
+    let strict = register(&scanners[..2], DEFAULT_OVERLAP + 1);
+    assert!(strict.edges.is_empty());
+    assert_eq!(None, strict.placements[1]);
+    assert_eq!(vec![1], strict.unplaced());
+
+    let loose = register(&scanners[..2], 3);
+    assert_eq!(1, loose.edges.len());
+    assert_eq!(
+        Vector3 {
+            x: 68,
+            y: -1246,
+            z: -43
+        },
+        loose.placements[1].as_ref().unwrap().translation
+    );
+}
+
+#[test]
+fn test_synthetic_scanners() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // a line of overlapping scanners, each turned and moved at random
+    let mut rng = StdRng::seed_from_u64(19);
+    let mut next = |below: i32| rng.gen_range(0..below);
+    let beacons = (0..120)
+        .map(|i| Point {
+            x: i * 25 + next(20),
+            y: next(1000) - 500,
+            z: next(1000) - 500,
+        })
+        .collect_vec();
+
+    let rotations = Rotation::all();
+    let mut scanners = vec![];
+    let mut truths = vec![];
+    for start in (0..100).step_by(20) {
+        let truth = Transform {
+            rotation: rotations[next(24) as usize],
+            translation: Vector3 {
+                x: next(2000) - 1000,
+                y: next(2000) - 1000,
+                z: next(2000) - 1000,
+            },
+        };
+        let local = truth.inverse();
+        let scanner = Scanner {
+            center: Point { x: 0, y: 0, z: 0 },
+            beacon_locations: beacons[start..start + 35]
+                .iter()
+                .map(|beacon| local.apply(beacon))
+                .collect(),
+        };
+        scanners.push(scanner);
+        truths.push(truth);
+    }
+
+    let registration = register(&scanners, DEFAULT_OVERLAP);
+    let to_first = truths[0].inverse();
+    for (placement, truth) in registration.placements.iter().zip(&truths) {
+        assert_eq!(Some(to_first.then(truth)), *placement);
+    }
+}
@@ -1,8 +1,9 @@
 use itertools::Itertools;
 use nom::{
+    branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, anychar, digit1},
-    combinator::{map, opt, recognize},
+    character::complete::i64 as parse_i64,
+    combinator::{map, value},
     multi::separated_list1,
     sequence::{preceded, separated_pair, tuple},
     IResult,
@@ -10,24 +11,17 @@ use nom::{
 use std::{
     cmp::{max, min},
     collections::HashSet,
+    fmt::{self, Display, Formatter},
     ops::RangeInclusive,
 };
 
 pub fn solve(problem: &str) -> (u64, u64) {
     let (_, instructions) = separated_list1(
         tag("\n"),
-        map(
-            separated_pair(alpha1, tag(" "), Range3::parse),
-            |(on_off, range3)| {
-                let on_off = if on_off == "on" {
-                    true
-                } else if on_off == "off" {
-                    false
-                } else {
-                    panic!("that was unexpected")
-                };
-                (on_off, range3)
-            },
+        separated_pair(
+            alt((value(true, tag("on")), value(false, tag("off")))),
+            tag(" "),
+            Range3::parse,
         ),
     )(problem)
     .unwrap();
@@ -97,41 +91,21 @@ fn remove_from_set(point3: Point3, set: &mut HashSet<Point3>) {
 type Point3 = (i64, i64, i64);
 
 #[derive(PartialEq, Debug, Clone)]
-struct Range3 {
+pub(crate) struct Range3 {
     x: RangeInclusive<i64>,
     y: RangeInclusive<i64>,
     z: RangeInclusive<i64>,
 }
 
 impl Range3 {
-    fn parse(input: &str) -> IResult<&str, Self> {
+    pub(crate) fn parse(input: &str) -> IResult<&str, Self> {
         map(
-            separated_list1(
-                tag(","),
-                preceded(
-                    tuple((anychar, tag("="))),
-                    separated_pair(
-                        recognize(tuple((opt(tag("-")), digit1))),
-                        tag(".."),
-                        recognize(tuple((opt(tag("-")), digit1))),
-                    ),
-                ),
-            ),
-            |ranges: Vec<(&str, &str)>| {
-                let (x_min, x_max) = ranges[0];
-                let (y_min, y_max) = ranges[1];
-                let (z_min, z_max) = ranges[2];
-
-                let (x_min, x_max) = (x_min.parse().unwrap(), x_max.parse().unwrap());
-                let (y_min, y_max) = (y_min.parse().unwrap(), y_max.parse().unwrap());
-                let (z_min, z_max) = (z_min.parse().unwrap(), z_max.parse().unwrap());
-
-                Self {
-                    x: x_min..=x_max,
-                    y: y_min..=y_max,
-                    z: z_min..=z_max,
-                }
-            },
+            tuple((
+                axis_range("x="),
+                preceded(tag(","), axis_range("y=")),
+                preceded(tag(","), axis_range("z=")),
+            )),
+            |(x, y, z)| Self { x, y, z },
         )(input)
     }
 
@@ -221,6 +195,30 @@ impl Range3 {
     }
 }
 
+fn axis_range<'a>(
+    label: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, RangeInclusive<i64>> {
+    map(
+        preceded(tag(label), separated_pair(parse_i64, tag(".."), parse_i64)),
+        |(start, end)| start..=end,
+    )
+}
+
+impl Display for Range3 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "x={}..{},y={}..{},z={}..{}",
+            self.x.start(),
+            self.x.end(),
+            self.y.start(),
+            self.y.end(),
+            self.z.start(),
+            self.z.end()
+        )
+    }
+}
+
 #[test]
 fn test() {
     use crate::utils::basic_test;
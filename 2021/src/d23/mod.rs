@@ -1,281 +1,363 @@
+use advent_common::search::{astar, SearchState};
 use itertools::Itertools;
-use nom::{
-    bytes::complete::take_while, character::complete::anychar, combinator::map,
-    multi::separated_list1, sequence::preceded, IResult,
-};
-use pathfinding::prelude::astar;
+use std::fmt::{self, Display};
 
 pub fn solve(problem: &str) -> (u64, u64) {
-    let (_, game_state_1) = GameState::parse(problem).unwrap();
-
-    let mut problem_2 = problem.to_string();
-    problem_2.insert_str(41, "  #D#C#B#A#\n  #D#B#A#C#");
-    let (_, game_state_2) = GameState::parse(&problem_2).unwrap();
+    (least_energy(problem), least_energy(&unfold(problem)))
+}
 
-    (solve1(&game_state_1), solve2(&game_state_2))
+pub fn print_plans(problem: &str) {
+    for map in [problem.to_string(), unfold(problem)] {
+        let (burrow, amphipods) = Burrow::parse(&map);
+        match burrow.organize(amphipods) {
+            Some(plan) => println!("{}\n\ntotal: {} energy\n", plan.render(), plan.energy()),
+            None => println!("no way to organize the amphipods\n"),
+        }
+    }
 }
 
-fn solve1(game_state: &GameState<2>) -> u64 {
-    let winning_state = GameState {
-        corridor: [None; 7],
-        burrows: [
-            [Some(Amphipod::Amber); 2],
-            [Some(Amphipod::Bronze); 2],
-            [Some(Amphipod::Copper); 2],
-            [Some(Amphipod::Desert); 2],
-        ],
-    };
-    let (_, dist) = astar(
-        game_state,
-        GameState::successors,
-        |_| 0,
-        |s| s == &winning_state,
-    )
-    .expect("there should be some winning path");
-    dist
+fn least_energy(map: &str) -> u64 {
+    let (burrow, amphipods) = Burrow::parse(map);
+    burrow
+        .organize(amphipods)
+        .expect("there should be some winning path")
+        .energy()
 }
 
-fn solve2(game_state: &GameState<4>) -> u64 {
-    let winning_state = GameState {
-        corridor: [None; 7],
-        burrows: [
-            [Some(Amphipod::Amber); 4],
-            [Some(Amphipod::Bronze); 4],
-            [Some(Amphipod::Copper); 4],
-            [Some(Amphipod::Desert); 4],
-        ],
-    };
-    let (_, dist) = astar(
-        game_state,
-        GameState::successors,
-        GameState::heuristic,
-        |s| s == &winning_state,
-    )
-    .expect("there should be some winning path");
-    dist
+/// Adds the two lines of the diagram that were folded away, under the top row of rooms.
+fn unfold(map: &str) -> String {
+    let mut lines = map.lines().collect_vec();
+    lines.splice(3..3, ["  #D#C#B#A#", "  #D#B#A#C#"]);
+    lines.join("\n")
 }
 
-#[derive(PartialEq, Debug, Clone, Eq, Hash)]
-struct GameState<const N: usize> {
-    corridor: [Option<Amphipod>; 7],
-    burrows: [[Option<Amphipod>; N]; 4],
+/// The layout of a burrow: a hallway with rooms of equal depth hanging off it, where the `i`th
+/// room from the left belongs to the `i`th amphipod type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Burrow {
+    /// The map with every amphipod replaced by open floor.
+    template: Vec<Vec<char>>,
+    /// The map position of the leftmost hallway cell.
+    hallway_start: (usize, usize),
+    hallway_len: usize,
+    /// The hallway cell above each room, left to right.
+    entrances: Vec<usize>,
+    depth: usize,
 }
 
-impl<const N: usize> GameState<N> {
-    fn parse(input: &str) -> IResult<&str, Self> {
-        map(
-            preceded(
-                take_while(|c: char| !c.is_alphabetic()),
-                separated_list1(take_while(|c: char| !c.is_alphabetic()), anychar),
-            ),
-            |amphipods: Vec<char>| {
-                let amphipods: Vec<Amphipod> =
-                    amphipods.into_iter().map(Amphipod::from).collect_vec();
-
-                let mut burrows = [[None; N]; 4];
-
-                for (burrow_idx, burrow) in burrows.iter_mut().enumerate() {
-                    for (spot_idx, burrow_spot) in burrow.iter_mut().enumerate() {
-                        let amphipod_idx = burrow_idx + (4 * spot_idx);
-                        *burrow_spot = Some(amphipods[amphipod_idx])
-                    }
-                }
+/// What is in each cell of a burrow: first the hallway from left to right, then each room from
+/// top to bottom. Empty cells are `.`, amphipods are their letter.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Amphipods(Vec<u8>);
 
-                GameState {
-                    corridor: [None; 7],
-                    burrows,
-                }
-            },
-        )(input)
-    }
-    fn successors(&self) -> Vec<(Self, u64)> {
-        for (idx, maybe_amphipod) in self.corridor.iter().enumerate() {
-            if let Some(amphipod) = maybe_amphipod {
-                let maybe_distance = self.distance_to_own_burrow(amphipod, idx);
-
-                if let Some((blocks_traveled, dest_idx)) = maybe_distance {
-                    let mut next_game = self.clone();
-                    next_game.corridor[idx] = None;
-                    next_game.burrows[amphipod.burrow_idx()][dest_idx] = Some(*amphipod);
-                    let energy_consumed = blocks_traveled * amphipod.unit_energy_consumption();
-                    return vec![(next_game, energy_consumed)];
-                }
-            }
-        }
+const EMPTY: u8 = b'.';
 
-        let mut eligible_burrowed_amphipods = vec![];
-        for (idx, burrow) in self.burrows.iter().enumerate() {
-            // don't evaluate if all amphipods are already in position
-            if burrow.iter().all(|a| match a {
-                None => true,
-                Some(a) => a.burrow_idx() == idx,
-            }) {
-                continue;
-            }
+/// One amphipod moving from one cell to another, as map positions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Move {
+    amphipod: char,
+    from: (usize, usize),
+    to: (usize, usize),
+    energy: u64,
+}
 
-            let (a_idx, amphipod) = burrow
-                .iter()
-                .enumerate()
-                .find_or_first(|(_, a)| a.is_some())
-                .unwrap();
+/// The cheapest way to get every amphipod home, as the state after each move.
+struct Plan<'a> {
+    burrow: &'a Burrow,
+    states: Vec<Amphipods>,
+    energies: Vec<u64>,
+}
+
+impl Burrow {
+    /// Reads the layout and the amphipods from a map. The hallway is the first row with open
+    /// cells, and a room is any open column below it.
+    fn parse(map: &str) -> (Self, Amphipods) {
+        let grid = map
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.chars().collect_vec())
+            .collect_vec();
+        let is_open = |row: usize, col: usize| {
+            grid.get(row)
+                .and_then(|line| line.get(col))
+                .is_some_and(|c| *c == '.' || c.is_ascii_uppercase())
+        };
 
-            if let Some(amphipod) = amphipod {
-                eligible_burrowed_amphipods.push((idx, a_idx, amphipod))
+        let row = (0..grid.len())
+            .find(|row| (0..grid[*row].len()).any(|col| is_open(*row, col)))
+            .expect("there should be a hallway");
+        let first_col = (0..grid[row].len()).find(|col| is_open(row, *col)).unwrap();
+        let hallway_len = (first_col..grid[row].len())
+            .take_while(|col| is_open(row, *col))
+            .count();
+        let entrances = (0..hallway_len)
+            .filter(|idx| is_open(row + 1, first_col + idx))
+            .collect_vec();
+        let depth = (row + 1..grid.len())
+            .take_while(|r| is_open(*r, first_col + entrances[0]))
+            .count();
+
+        let hallway = (0..hallway_len).map(|idx| grid[row][first_col + idx]);
+        let rooms = entrances.iter().flat_map(|entrance| {
+            (0..depth)
+                .map(|slot| grid[row + 1 + slot][first_col + *entrance])
+                .collect_vec()
+        });
+        let amphipods = Amphipods(hallway.chain(rooms).map(|c| c as u8).collect());
+
+        let mut template = grid.clone();
+        for cell in template.iter_mut().flatten() {
+            if cell.is_ascii_uppercase() {
+                *cell = '.';
             }
         }
+        let burrow = Self {
+            template,
+            hallway_start: (row, first_col),
+            hallway_len,
+            entrances,
+            depth,
+        };
+        (burrow, amphipods)
+    }
 
-        let mut next_games = vec![];
-
-        for (burrow_idx, amphipod_idx, amphipod) in eligible_burrowed_amphipods {
-            let mut next_game_template = self.clone();
-            next_game_template.burrows[burrow_idx][amphipod_idx] = None;
-
-            let mut corridor_idx = burrow_idx + 1; // to the left
-            let mut blocks_travelled = 2 + amphipod_idx as u64;
-            while self.corridor[corridor_idx].is_none() {
-                let mut next_game = next_game_template.clone();
-                next_game.corridor[corridor_idx] = Some(*amphipod);
-                let energy_consumed = blocks_travelled * amphipod.unit_energy_consumption();
-                next_games.push((next_game, energy_consumed));
+    /// The cheapest way to get every amphipod into its own room.
+    fn organize(&self, amphipods: Amphipods) -> Option<Plan> {
+        let path = astar(amphipods, self)?;
+        let energies = path
+            .states
+            .iter()
+            .tuple_windows()
+            .map(|(before, after)| {
+                self.moves(before)
+                    .into_iter()
+                    .filter(|(state, _)| state == after)
+                    .map(|(_, energy)| energy)
+                    .min()
+                    .unwrap()
+            })
+            .collect();
+        Some(Plan {
+            burrow: self,
+            states: path.states,
+            energies,
+        })
+    }
 
-                if corridor_idx == 0 {
-                    break;
-                }
+    fn render(&self, amphipods: &Amphipods) -> String {
+        let mut map = self.template.clone();
+        for (cell, content) in amphipods.0.iter().enumerate() {
+            let (row, col) = self.position(cell);
+            map[row][col] = *content as char;
+        }
+        map.iter()
+            .map(|line| line.iter().collect::<String>())
+            .join("\n")
+    }
 
-                corridor_idx -= 1;
-                blocks_travelled += 2;
-                if corridor_idx == 0 {
-                    blocks_travelled -= 1;
-                }
+    /// Every state one move away, with the energy it takes. An amphipod that can go straight to
+    /// its own room from the hallway always should, so if one can that is the only move.
+    fn moves(&self, amphipods: &Amphipods) -> Vec<(Amphipods, u64)> {
+        let cells = &amphipods.0;
+        for (idx, amphipod) in cells[..self.hallway_len].iter().enumerate() {
+            if *amphipod == EMPTY {
+                continue;
             }
+            if let Some((slot, steps)) = self.way_home(amphipods, *amphipod, idx) {
+                let home = self.room_cell(kind(*amphipod), slot);
+                return vec![(amphipods.moved(idx, home), steps * energy(*amphipod))];
+            }
+        }
 
-            corridor_idx = burrow_idx + 2; // to the right
-            blocks_travelled = 2 + amphipod_idx as u64;
-            while let Some(None) = self.corridor.get(corridor_idx) {
-                let mut next_game = next_game_template.clone();
-                next_game.corridor[corridor_idx] = Some(*amphipod);
-                let energy_consumed = blocks_travelled * amphipod.unit_energy_consumption();
-                next_games.push((next_game, energy_consumed));
-
-                corridor_idx += 1;
-                blocks_travelled += 2;
-                if corridor_idx == self.corridor.len() - 1 {
-                    blocks_travelled -= 1;
+        let mut moves = vec![];
+        for room in 0..self.entrances.len() {
+            if self.is_settled(amphipods, room) {
+                continue;
+            }
+            let (slot, amphipod) = (0..self.depth)
+                .map(|slot| (slot, cells[self.room_cell(room, slot)]))
+                .find(|(_, amphipod)| *amphipod != EMPTY)
+                .unwrap();
+            let from = self.room_cell(room, slot);
+            let entrance = self.entrances[room];
+            let steps_out = slot as u64 + 1;
+
+            if let Some((home_slot, steps)) = self.way_home(amphipods, amphipod, entrance) {
+                let home = self.room_cell(kind(amphipod), home_slot);
+                let energy = (steps_out + steps) * energy(amphipod);
+                moves.push((amphipods.moved(from, home), energy));
+            }
+            let left = (0..entrance).rev();
+            let right = entrance + 1..self.hallway_len;
+            for stops in [left.collect_vec(), right.collect_vec()] {
+                for stop in stops.into_iter().take_while(|idx| cells[*idx] == EMPTY) {
+                    if self.entrances.contains(&stop) {
+                        continue;
+                    }
+                    let steps = steps_out + stop.abs_diff(entrance) as u64;
+                    moves.push((amphipods.moved(from, stop), steps * energy(amphipod)));
                 }
             }
         }
-        next_games
+        moves
     }
 
-    fn distance_to_own_burrow(
-        &self,
-        amphipod: &Amphipod,
-        starting_corridor_idx: usize,
-    ) -> Option<(u64, usize)> {
-        let burrow_idx = amphipod.burrow_idx();
-
-        // verify that there are no foreign amphipods in burrow
-        if !self.burrows[burrow_idx].iter().all(|maybe_a| {
-            if let Some(a) = maybe_a {
-                a.burrow_idx() == burrow_idx
-            } else {
-                true
-            }
-        }) {
+    /// The slot an amphipod at hallway cell `from` would settle into in its own room and how many
+    /// steps away it is, if the room holds no strangers and the hallway is clear.
+    fn way_home(&self, amphipods: &Amphipods, amphipod: u8, from: usize) -> Option<(usize, u64)> {
+        let room = kind(amphipod);
+        let entrance = self.entrances[room];
+        let cells = &amphipods.0;
+        let hallway_clear =
+            (from.min(entrance)..=from.max(entrance)).all(|idx| idx == from || cells[idx] == EMPTY);
+        if !hallway_clear || !self.is_settled(amphipods, room) {
             return None;
         }
-
-        let (a_idx, maybe_amphipod) = self.burrows[burrow_idx]
-            .iter()
-            .enumerate()
+        let slot = (0..self.depth)
             .rev()
-            .find(|(_, a)| a.is_none())
-            .unwrap();
+            .find(|slot| cells[self.room_cell(room, *slot)] == EMPTY)?;
+        Some((slot, (from.abs_diff(entrance) + slot + 1) as u64))
+    }
+
+    /// Whether a room holds only its own amphipods, if any.
+    fn is_settled(&self, amphipods: &Amphipods, room: usize) -> bool {
+        (0..self.depth).all(|slot| {
+            let amphipod = amphipods.0[self.room_cell(room, slot)];
+            amphipod == EMPTY || kind(amphipod) == room
+        })
+    }
+
+    fn room_cell(&self, room: usize, slot: usize) -> usize {
+        self.hallway_len + room * self.depth + slot
+    }
 
-        if maybe_amphipod.is_some() {
-            panic!("burrow should have at least one empty spot")
+    fn position(&self, cell: usize) -> (usize, usize) {
+        let (row, first_col) = self.hallway_start;
+        if cell < self.hallway_len {
+            return (row, first_col + cell);
         }
+        let (room, slot) = (
+            (cell - self.hallway_len) / self.depth,
+            (cell - self.hallway_len) % self.depth,
+        );
+        (row + 1 + slot, first_col + self.entrances[room])
+    }
+}
 
-        let starting_idx_is_left_of_burrow = starting_corridor_idx < burrow_idx + 2;
+impl Amphipods {
+    fn moved(&self, from: usize, to: usize) -> Self {
+        let mut cells = self.0.clone();
+        cells.swap(from, to);
+        Self(cells)
+    }
+}
 
-        let mut corridor_idx = if starting_idx_is_left_of_burrow {
-            // starting corridor position is to the left of burrow
-            burrow_idx + 1
-        } else {
-            // its to the right
-            burrow_idx + 2
-        };
+fn kind(amphipod: u8) -> usize {
+    (amphipod - b'A') as usize
+}
 
-        let mut blocks_travelled = 2 + a_idx as u64;
-        while self.corridor[corridor_idx].is_none() {
-            if starting_idx_is_left_of_burrow {
-                corridor_idx -= 1;
-            } else {
-                corridor_idx += 1;
-            }
+fn energy(amphipod: u8) -> u64 {
+    10_u64.pow(kind(amphipod) as u32)
+}
 
-            blocks_travelled += 2;
-            if corridor_idx == 0 || corridor_idx == self.corridor.len() - 1 {
-                blocks_travelled -= 1;
-            }
-        }
-        if corridor_idx == starting_corridor_idx {
-            return Some((blocks_travelled, a_idx));
-        }
-        None
+impl SearchState<Burrow> for Amphipods {
+    type Cost = u64;
+
+    fn successors(&self, burrow: &Burrow) -> Vec<(Self, u64)> {
+        burrow.moves(self)
     }
 
-    fn heuristic(&self) -> u64 {
-        /*
-        I tried heuristics like:
-         - minimum cost to put all amphipods in their correct room
-         - number of amphipods out of place
+    /// Every amphipod still to move walks to its room's entrance through no one, and then the
+    /// ones arriving at each room fill it from the top slot down.
+    fn heuristic(&self, burrow: &Burrow) -> u64 {
+        let mut arriving = vec![0; burrow.entrances.len()];
+        let mut steps = 0;
+        for (cell, amphipod) in self.0.iter().enumerate().filter(|(_, a)| **a != EMPTY) {
+            let home = kind(*amphipod);
+            let to_entrance = if cell < burrow.hallway_len {
+                cell.abs_diff(burrow.entrances[home])
+            } else {
+                let room = (cell - burrow.hallway_len) / burrow.depth;
+                let slot = (cell - burrow.hallway_len) % burrow.depth;
+                let mut below =
+                    (slot + 1..burrow.depth).map(|below| self.0[burrow.room_cell(room, below)]);
+                if room == home && below.all(|a| a == *amphipod) {
+                    continue;
+                }
+                // leaving a room and coming back takes at least a step out into the hallway
+                let across = burrow.entrances[room]
+                    .abs_diff(burrow.entrances[home])
+                    .max(2);
+                slot + 1 + across
+            };
+            arriving[home] += 1;
+            steps += to_entrance as u64 * energy(*amphipod);
+        }
+        let filling = arriving
+            .iter()
+            .enumerate()
+            .map(|(room, count)| (count * (count + 1) / 2) as u64 * energy(b'A' + room as u8));
+        steps + filling.sum::<u64>()
+    }
 
-        But both resulted in slower execution times
-        */
-        0
+    fn is_goal(&self, burrow: &Burrow) -> bool {
+        (0..burrow.entrances.len()).all(|room| {
+            (0..burrow.depth).all(|slot| {
+                let amphipod = self.0[burrow.room_cell(room, slot)];
+                amphipod != EMPTY && kind(amphipod) == room
+            })
+        })
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
-enum Amphipod {
-    Amber,
-    Bronze,
-    Copper,
-    Desert,
-}
+impl Plan<'_> {
+    fn energy(&self) -> u64 {
+        self.energies.iter().sum()
+    }
 
-impl Amphipod {
-    fn unit_energy_consumption(&self) -> u64 {
-        match self {
-            Amphipod::Amber => 1,
-            Amphipod::Bronze => 10,
-            Amphipod::Copper => 100,
-            Amphipod::Desert => 1000,
-        }
+    fn moves(&self) -> Vec<Move> {
+        self.states
+            .iter()
+            .tuple_windows()
+            .zip(&self.energies)
+            .map(|((before, after), energy)| {
+                let changed = |predicate: fn(u8, u8) -> bool| {
+                    (0..before.0.len())
+                        .find(|cell| predicate(before.0[*cell], after.0[*cell]))
+                        .unwrap()
+                };
+                let from = changed(|b, a| b != EMPTY && a == EMPTY);
+                let to = changed(|b, a| b == EMPTY && a != EMPTY);
+                Move {
+                    amphipod: before.0[from] as char,
+                    from: self.burrow.position(from),
+                    to: self.burrow.position(to),
+                    energy: *energy,
+                }
+            })
+            .collect()
     }
 
-    fn burrow_idx(&self) -> usize {
-        match self {
-            Amphipod::Amber => 0,
-            Amphipod::Bronze => 1,
-            Amphipod::Copper => 2,
-            Amphipod::Desert => 3,
-        }
+    /// The starting map, then each move and the map after it.
+    fn render(&self) -> String {
+        let maps = self.states.iter().map(|state| self.burrow.render(state));
+        let captions = ["start".to_string()]
+            .into_iter()
+            .chain(self.moves().iter().map(Move::to_string).collect_vec());
+        captions
+            .zip(maps)
+            .map(|(caption, map)| format!("{caption}\n{map}"))
+            .join("\n\n")
     }
 }
 
-impl From<char> for Amphipod {
-    fn from(value: char) -> Self {
-        match value {
-            'A' => Self::Amber,
-            'B' => Self::Bronze,
-            'C' => Self::Copper,
-            'D' => Self::Desert,
-            _ => {
-                panic!("{value} is not valid")
-            }
-        }
+impl Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} from {:?} to {:?} for {} energy",
+            self.amphipod, self.from, self.to, self.energy
+        )
     }
 }
 
@@ -286,157 +368,126 @@ fn test() {
     basic_test(input, solve);
 }
 
+#[cfg(test)]
+fn moves_from(map: &str) -> Vec<(String, u64)> {
+    let (burrow, amphipods) = Burrow::parse(map);
+    burrow
+        .moves(&amphipods)
+        .into_iter()
+        .map(|(state, energy)| (burrow.render(&state), energy))
+        .collect()
+}
+
 #[test]
-fn test_parse_game_state() {
+fn test_parse_burrow() {
     let input = r#"#############
 #...........#
 ###B#C#B#D###
   #A#D#C#A#
   #########
 "#;
-    let (_, game_state) = GameState::parse(input).unwrap();
+    let (burrow, amphipods) = Burrow::parse(input);
 
-    assert_eq!(
-        GameState {
-            corridor: [None; 7],
-            burrows: [
-                [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-                [Some(Amphipod::Copper), Some(Amphipod::Desert)],
-                [Some(Amphipod::Bronze), Some(Amphipod::Copper)],
-                [Some(Amphipod::Desert), Some(Amphipod::Amber)]
-            ],
-        },
-        game_state
-    )
+    assert_eq!((1, 1), burrow.hallway_start);
+    assert_eq!(11, burrow.hallway_len);
+    assert_eq!(vec![2, 4, 6, 8], burrow.entrances);
+    assert_eq!(2, burrow.depth);
+    assert_eq!(b"...........BACDBCDA".to_vec(), amphipods.0);
+    assert_eq!(input.trim_end(), burrow.render(&amphipods));
+
+    let unfolded = Burrow::parse(&unfold(input)).0;
+    assert_eq!(4, unfolded.depth);
 }
 
 #[test]
-fn test_successors_amphipod_to_burrow() {
-    let game_state = GameState {
-        corridor: [None, Some(Amphipod::Amber), None, None, None, None, None],
-        burrows: [[None; 2]; 4],
-    };
+fn test_parse_other_layouts() {
+    // three rooms of depth 3, with the hallway starting further in and an amphipod already in it
+    let input = "
+ #########
+ #......A#
+ ##B#A#.##
+  #C#C#B#
+  #A#B#C#
+  #######";
+    let (burrow, amphipods) = Burrow::parse(input);
+    assert_eq!((1, 2), burrow.hallway_start);
+    assert_eq!(7, burrow.hallway_len);
+    assert_eq!(vec![1, 3, 5], burrow.entrances);
+    assert_eq!(3, burrow.depth);
+    assert_eq!(input.trim_start_matches('\n'), burrow.render(&amphipods));
+
+    let plan = burrow.organize(amphipods).unwrap();
+    assert!(plan.states.last().unwrap().is_goal(&burrow));
+    assert_eq!(plan.energy(), plan.moves().iter().map(|m| m.energy).sum());
+}
 
-    let actual = game_state.successors();
+#[test]
+fn test_successors_amphipod_to_burrow() {
+    let game_state = "#############
+#.A.........#
+###.#.#.#.###
+  #.#.#.#.#
+  #########";
 
-    assert_eq!(
-        vec![(
-            GameState {
-                corridor: [None, None, None, None, None, None, None],
-                burrows: [
-                    [None, Some(Amphipod::Amber)],
-                    [None; 2],
-                    [None; 2],
-                    [None; 2]
-                ],
-            },
-            3
-        )],
-        actual
-    );
+    let expected = "#############
+#...........#
+###.#.#.#.###
+  #A#.#.#.#
+  #########";
+    assert_eq!(vec![(expected.to_string(), 3)], moves_from(game_state));
 }
 
 #[test]
 fn test_successors_amphipod_to_burrow_with_obstacle() {
-    let game_state = GameState {
-        corridor: [
-            Some(Amphipod::Amber),
-            Some(Amphipod::Bronze),
-            None,
-            None,
-            None,
-            None,
-            None,
-        ],
-        burrows: [[None; 2]; 4],
-    };
-
-    let actual = game_state.successors();
-
-    assert_eq!(
-        vec![(
-            GameState {
-                corridor: [Some(Amphipod::Amber), None, None, None, None, None, None],
-                burrows: [
-                    [None; 2],
-                    [None, Some(Amphipod::Bronze)],
-                    [None; 2],
-                    [None; 2]
-                ],
-            },
-            50
-        )],
-        actual
-    );
+    let game_state = "#############
+#AB.........#
+###.#.#.#.###
+  #.#.#.#.#
+  #########";
+
+    let expected = "#############
+#A..........#
+###.#.#.#.###
+  #.#B#.#.#
+  #########";
+    assert_eq!(vec![(expected.to_string(), 50)], moves_from(game_state));
 }
 
 #[test]
 fn test_successors_burrow_to_corridor() {
     use std::collections::HashSet;
 
-    let game_state = GameState {
-        corridor: [None; 7],
-        burrows: [
-            [None, Some(Amphipod::Bronze)],
-            [None; 2],
-            [None; 2],
-            [None; 2],
-        ],
+    let game_state = "#############
+#...........#
+###.#.#.#.###
+  #B#.#.#.#
+  #########";
+
+    let in_hallway = |col: usize| {
+        let mut hallway = ['.'; 11];
+        hallway[col] = 'B';
+        format!(
+            "#############\n#{}#\n###.#.#.#.###\n  #.#.#.#.#\n  #########",
+            hallway.iter().collect::<String>()
+        )
     };
-
-    let actual: HashSet<_> = game_state.successors().into_iter().collect();
+    let straight_home = "#############
+#...........#
+###.#.#.#.###
+  #.#B#.#.#
+  #########";
+    let actual: HashSet<_> = moves_from(game_state).into_iter().collect();
 
     assert_eq!(
         HashSet::from([
-            (
-                GameState {
-                    corridor: [Some(Amphipod::Bronze), None, None, None, None, None, None],
-                    burrows: [[None; 2]; 4],
-                },
-                40
-            ),
-            (
-                GameState {
-                    corridor: [None, Some(Amphipod::Bronze), None, None, None, None, None],
-                    burrows: [[None; 2]; 4],
-                },
-                30
-            ),
-            (
-                GameState {
-                    corridor: [None, None, Some(Amphipod::Bronze), None, None, None, None],
-                    burrows: [[None; 2]; 4],
-                },
-                30
-            ),
-            (
-                GameState {
-                    corridor: [None, None, None, Some(Amphipod::Bronze), None, None, None],
-                    burrows: [[None; 2]; 4],
-                },
-                50
-            ),
-            (
-                GameState {
-                    corridor: [None, None, None, None, Some(Amphipod::Bronze), None, None],
-                    burrows: [[None; 2]; 4],
-                },
-                70
-            ),
-            (
-                GameState {
-                    corridor: [None, None, None, None, None, Some(Amphipod::Bronze), None],
-                    burrows: [[None; 2]; 4],
-                },
-                90
-            ),
-            (
-                GameState {
-                    corridor: [None, None, None, None, None, None, Some(Amphipod::Bronze)],
-                    burrows: [[None; 2]; 4],
-                },
-                100
-            ),
+            (in_hallway(0), 40),
+            (in_hallway(1), 30),
+            (in_hallway(3), 30),
+            (in_hallway(5), 50),
+            (in_hallway(7), 70),
+            (in_hallway(9), 90),
+            (in_hallway(10), 100),
+            (straight_home.to_string(), 60),
         ]),
         actual
     );
@@ -444,152 +495,84 @@ fn test_successors_burrow_to_corridor() {
 
 #[test]
 fn test_winning_strategy_depth_2() {
-    let game_state = GameState {
-        corridor: [None; 7],
-        burrows: [
-            [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-            [Some(Amphipod::Copper), Some(Amphipod::Desert)],
-            [Some(Amphipod::Bronze), Some(Amphipod::Copper)],
-            [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-        ],
-    };
-
-    let actual = game_state.successors();
-
-    let expected = (
-        GameState {
-            corridor: [None, None, Some(Amphipod::Bronze), None, None, None, None],
-            burrows: [
-                [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-                [Some(Amphipod::Copper), Some(Amphipod::Desert)],
-                [None, Some(Amphipod::Copper)],
-                [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-            ],
-        },
-        40,
-    );
-
-    assert!(actual.contains(&expected));
-
-    let (game_state, _) = expected;
-
-    let actual = game_state.successors();
-
-    let expected = (
-        GameState {
-            corridor: [
-                None,
-                None,
-                Some(Amphipod::Bronze),
-                Some(Amphipod::Copper),
-                None,
-                None,
-                None,
-            ],
-            burrows: [
-                [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-                [None, Some(Amphipod::Desert)],
-                [None, Some(Amphipod::Copper)],
-                [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-            ],
-        },
-        200,
-    );
-
-    assert!(actual.contains(&expected));
-
-    let (game_state, _) = expected;
+    let game_state = "#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########";
 
-    let actual = game_state.successors();
+    let expected = "#############
+#...B.......#
+###B#C#.#D###
+  #A#D#C#A#
+  #########";
+    assert!(moves_from(game_state).contains(&(expected.to_string(), 40)));
 
-    let expected = (
-        GameState {
-            corridor: [None, None, Some(Amphipod::Bronze), None, None, None, None],
-            burrows: [
-                [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-                [None, Some(Amphipod::Desert)],
-                [Some(Amphipod::Copper), Some(Amphipod::Copper)],
-                [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-            ],
-        },
-        200,
-    );
+    let game_state = expected;
+    let expected = "#############
+#...B.C.....#
+###B#.#.#D###
+  #A#D#C#A#
+  #########";
+    assert!(moves_from(game_state).contains(&(expected.to_string(), 200)));
 
-    assert!(actual.contains(&expected));
+    let game_state = expected;
+    let expected = "#############
+#...B.......#
+###B#.#C#D###
+  #A#D#C#A#
+  #########";
+    assert_eq!(vec![(expected.to_string(), 200)], moves_from(game_state));
 }
 
 #[test]
 fn test_winning_strategy_depth_4() {
-    let game_state = GameState {
-        corridor: [None; 7],
-        burrows: [
-            [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-            [Some(Amphipod::Copper), Some(Amphipod::Desert)],
-            [Some(Amphipod::Bronze), Some(Amphipod::Copper)],
-            [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-        ],
-    };
-
-    let actual = game_state.successors();
-
-    let expected = (
-        GameState {
-            corridor: [None, None, Some(Amphipod::Bronze), None, None, None, None],
-            burrows: [
-                [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-                [Some(Amphipod::Copper), Some(Amphipod::Desert)],
-                [None, Some(Amphipod::Copper)],
-                [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-            ],
-        },
-        40,
-    );
-
-    assert!(actual.contains(&expected));
-
-    let (game_state, _) = expected;
-
-    let actual = game_state.successors();
-
-    let expected = (
-        GameState {
-            corridor: [
-                None,
-                None,
-                Some(Amphipod::Bronze),
-                Some(Amphipod::Copper),
-                None,
-                None,
-                None,
-            ],
-            burrows: [
-                [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-                [None, Some(Amphipod::Desert)],
-                [None, Some(Amphipod::Copper)],
-                [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-            ],
-        },
-        200,
-    );
-
-    assert!(actual.contains(&expected));
-
-    let (game_state, _) = expected;
+    let game_state = "#############
+#...........#
+###B#C#B#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########";
 
-    let actual = game_state.successors();
+    let expected = "#############
+#...B.......#
+###B#C#.#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########";
+    assert!(moves_from(game_state).contains(&(expected.to_string(), 40)));
+
+    let game_state = expected;
+    let expected = "#############
+#...B.C.....#
+###B#.#.#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########";
+    assert!(moves_from(game_state).contains(&(expected.to_string(), 200)));
+}
 
-    let expected = (
-        GameState {
-            corridor: [None, None, Some(Amphipod::Bronze), None, None, None, None],
-            burrows: [
-                [Some(Amphipod::Bronze), Some(Amphipod::Amber)],
-                [None, Some(Amphipod::Desert)],
-                [Some(Amphipod::Copper), Some(Amphipod::Copper)],
-                [Some(Amphipod::Desert), Some(Amphipod::Amber)],
-            ],
-        },
-        200,
-    );
+#[test]
+fn test_heuristic_is_admissible() {
+    let example = &crate::utils::parse_example_testcases(include_str!("example.txt"))[0].problem;
+    for map in [example.clone(), unfold(example)] {
+        let (burrow, amphipods) = Burrow::parse(&map);
+        let plan = burrow.organize(amphipods).unwrap();
+        for (idx, state) in plan.states.iter().enumerate() {
+            let energy_left: u64 = plan.energies[idx..].iter().sum();
+            assert!(state.heuristic(&burrow) <= energy_left);
+        }
+    }
+}
 
-    assert!(actual.contains(&expected));
+#[test]
+fn test_plan_snapshot() {
+    let example = &crate::utils::parse_example_testcases(include_str!("example.txt"))[0].problem;
+    let (burrow, amphipods) = Burrow::parse(example);
+    let plan = burrow.organize(amphipods).unwrap();
+    assert_eq!(12521, plan.energy());
+    insta::assert_snapshot!(plan.render());
 }
@@ -0,0 +1,538 @@
+use super::{Instruction, InstructionInput, Register};
+use itertools::Itertools;
+use std::fmt::{self, Display, Formatter};
+
+/// What an `inp` can read: a single non-zero digit.
+const DIGITS: Interval = Interval { min: 1, max: 9 };
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Interval {
+    pub(crate) min: i64,
+    pub(crate) max: i64,
+}
+
+impl Interval {
+    fn union(self, other: Interval) -> Interval {
+        Interval {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    fn is_disjoint(self, other: Interval) -> bool {
+        self.max < other.min || other.max < self.min
+    }
+
+    fn is_within(self, other: Interval) -> bool {
+        other.min <= self.min && self.max <= other.max
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.min, self.max)
+    }
+}
+
+/// A register value in terms of the input digits.
+///
+/// Programs like MONAD use z as a stack of base `m` numbers, pushing with `mul z m; add z y` and
+/// popping with `div z m`, and only ever look at the top with `mod z m`. Every stack entry, and
+/// every other register, is a constant or a single digit plus a constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Const(i64),
+    Digit {
+        index: usize,
+        offset: i64,
+    },
+    /// At least two entries, bottom first. Shorter stacks are stored as their only entry, or
+    /// `Const(0)` when empty.
+    Stack(Vec<Value>),
+}
+
+/// A relation between two digit expressions that a path through the program relies on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Constraint {
+    lhs: Value,
+    rhs: Value,
+    equal: bool,
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Const(value) => write!(f, "{value}"),
+            Value::Digit { index, offset: 0 } => write!(f, "d{index}"),
+            Value::Digit { index, offset } if *offset < 0 => write!(f, "d{index} - {}", -offset),
+            Value::Digit { index, offset } => write!(f, "d{index} + {offset}"),
+            Value::Stack(entries) => write!(f, "[{}]", entries.iter().join(", ")),
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let relation = if self.equal { "==" } else { "!=" };
+        write!(f, "{} {relation} {}", self.lhs, self.rhs)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum AnalysisError {
+    /// The instruction at this index does something the stack model can't describe.
+    Unsupported(usize),
+    NoInputs,
+}
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::Unsupported(index) => write!(f, "unsupported instruction at {index}"),
+            AnalysisError::NoInputs => write!(f, "program reads no digits"),
+        }
+    }
+}
+
+/// The result of symbolically running a whole program over every possible model number.
+#[derive(Debug)]
+pub(crate) struct Analysis {
+    pub(crate) digits: usize,
+    /// For each digit, the range each register (w, x, y, z) can hold once that digit's
+    /// instructions have run.
+    pub(crate) intervals: Vec<[Interval; 4]>,
+    /// The constraints of every path through the program that ends with z at 0.
+    pub(crate) accepted: Vec<Vec<Constraint>>,
+}
+
+#[derive(Clone)]
+struct Path {
+    registers: [Value; 4],
+    constraints: Vec<Constraint>,
+}
+
+struct Executor {
+    base: Option<i64>,
+    digits: usize,
+}
+
+/// Runs the program with every `inp` reading an unknown digit, splitting into two paths whenever
+/// an `eql` could go either way.
+pub(crate) fn analyze(instructions: &[Instruction]) -> Result<Analysis, AnalysisError> {
+    let mut executor = Executor {
+        base: None,
+        digits: 0,
+    };
+    let mut paths = vec![Path {
+        registers: [(); 4].map(|_| Value::Const(0)),
+        constraints: vec![],
+    }];
+    let mut intervals = vec![];
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        if matches!(instruction, Instruction::Inp(_)) && executor.digits > 0 {
+            intervals.push(executor.register_intervals(&paths));
+        }
+        let mut next_paths = vec![];
+        for path in paths {
+            next_paths.extend(
+                executor
+                    .execute(path, instruction)
+                    .ok_or(AnalysisError::Unsupported(index))?,
+            );
+        }
+        paths = next_paths;
+        if matches!(instruction, Instruction::Inp(_)) {
+            executor.digits += 1;
+        }
+    }
+    if executor.digits == 0 {
+        return Err(AnalysisError::NoInputs);
+    }
+    intervals.push(executor.register_intervals(&paths));
+
+    let base = executor.base.unwrap_or(i64::MAX);
+    let accepted = paths
+        .into_iter()
+        .filter_map(|mut path| {
+            // every stack entry is in [0, base), so z is 0 exactly when all of them are
+            let z = path.registers[Register::Z as usize].clone();
+            for entry in entries(z, base)? {
+                match entry {
+                    Value::Const(0) => {}
+                    Value::Const(_) => return None,
+                    entry => {
+                        if interval(&entry).is_disjoint(Interval { min: 0, max: 0 }) {
+                            return None;
+                        }
+                        path.constraints.push(Constraint {
+                            lhs: entry,
+                            rhs: Value::Const(0),
+                            equal: true,
+                        });
+                    }
+                }
+            }
+            Some(path.constraints)
+        })
+        .collect();
+
+    Ok(Analysis {
+        digits: executor.digits,
+        intervals,
+        accepted,
+    })
+}
+
+impl Executor {
+    /// The paths that follow from running `instruction`, or `None` if it can't be modelled.
+    fn execute(&mut self, mut path: Path, instruction: &Instruction) -> Option<Vec<Path>> {
+        let (register, input) = match instruction {
+            Instruction::Inp(register) => {
+                path.registers[*register as usize] = Value::Digit {
+                    index: self.digits,
+                    offset: 0,
+                };
+                return Some(vec![path]);
+            }
+            Instruction::Add(register, input)
+            | Instruction::Mul(register, input)
+            | Instruction::Div(register, input)
+            | Instruction::Mod(register, input)
+            | Instruction::Eql(register, input) => (*register as usize, input),
+        };
+        let lhs = path.registers[register].clone();
+        let rhs = match input {
+            InstructionInput::Register(other) => path.registers[*other as usize].clone(),
+            InstructionInput::Number(number) => Value::Const(*number),
+        };
+
+        let result = match instruction {
+            Instruction::Inp(_) => unreachable!(),
+            Instruction::Add(..) => self.add(lhs, rhs)?,
+            Instruction::Mul(..) => self.mul(lhs, rhs)?,
+            Instruction::Div(..) => self.div(lhs, rhs)?,
+            Instruction::Mod(..) => self.modulo(lhs, rhs)?,
+            Instruction::Eql(..) => {
+                let (lhs_interval, rhs_interval) = (interval(&lhs), interval(&rhs));
+                let always = match (&lhs, &rhs) {
+                    _ if lhs == rhs => Some(true),
+                    (Value::Stack(_), _) | (_, Value::Stack(_)) => return None,
+                    (
+                        Value::Digit { index, offset },
+                        Value::Digit {
+                            index: other_index,
+                            offset: other_offset,
+                        },
+                    ) if index == other_index => Some(offset == other_offset),
+                    _ if lhs_interval.is_disjoint(rhs_interval) => Some(false),
+                    (Value::Const(lhs), Value::Const(rhs)) => Some(lhs == rhs),
+                    _ => None,
+                };
+                match always {
+                    Some(equal) => Value::Const(equal as i64),
+                    None => {
+                        // either outcome is possible, so follow both with what each one requires
+                        let mut unequal = path.clone();
+                        unequal.registers[register] = Value::Const(0);
+                        unequal.constraints.push(Constraint {
+                            lhs: lhs.clone(),
+                            rhs: rhs.clone(),
+                            equal: false,
+                        });
+                        path.registers[register] = Value::Const(1);
+                        path.constraints.push(Constraint {
+                            lhs,
+                            rhs,
+                            equal: true,
+                        });
+                        return Some(vec![path, unequal]);
+                    }
+                }
+            }
+        };
+        path.registers[register] = result;
+        Some(vec![path])
+    }
+
+    fn add(&self, lhs: Value, rhs: Value) -> Option<Value> {
+        match (lhs, rhs) {
+            (Value::Const(0), value) | (value, Value::Const(0)) => Some(value),
+            (Value::Const(lhs), Value::Const(rhs)) => Some(Value::Const(lhs + rhs)),
+            (Value::Digit { index, offset }, Value::Const(constant))
+            | (Value::Const(constant), Value::Digit { index, offset }) => Some(Value::Digit {
+                index,
+                offset: offset + constant,
+            }),
+            // filling in the entry that `mul z m` just pushed
+            (Value::Stack(mut entries), value) if !matches!(value, Value::Stack(_)) => {
+                let base = self.base?;
+                let top = self.add(entries.pop()?, value)?;
+                if !interval(&top).is_within(Interval {
+                    min: 0,
+                    max: base - 1,
+                }) {
+                    return None;
+                }
+                entries.push(top);
+                Some(Value::Stack(entries))
+            }
+            _ => None,
+        }
+    }
+
+    fn mul(&self, lhs: Value, rhs: Value) -> Option<Value> {
+        match (lhs, rhs) {
+            (Value::Const(0), _) | (_, Value::Const(0)) => Some(Value::Const(0)),
+            (value, Value::Const(1)) | (Value::Const(1), value) => Some(value),
+            (Value::Const(lhs), Value::Const(rhs)) => Some(Value::Const(lhs * rhs)),
+            (value, Value::Const(factor)) if Some(factor) == self.base => {
+                let mut entries = entries(value, factor)?;
+                entries.push(Value::Const(0));
+                Some(from_entries(entries))
+            }
+            _ => None,
+        }
+    }
+
+    fn div(&self, lhs: Value, rhs: Value) -> Option<Value> {
+        match (lhs, rhs) {
+            (value, Value::Const(1)) => Some(value),
+            (Value::Const(lhs), Value::Const(rhs)) if rhs != 0 => Some(Value::Const(lhs / rhs)),
+            (value, Value::Const(divisor)) if Some(divisor) == self.base => {
+                let mut entries = entries(value, divisor)?;
+                entries.pop();
+                Some(from_entries(entries))
+            }
+            _ => None,
+        }
+    }
+
+    fn modulo(&mut self, lhs: Value, rhs: Value) -> Option<Value> {
+        let Value::Const(modulus) = rhs else {
+            return None;
+        };
+        if modulus <= 0 {
+            return None;
+        }
+        // the first `mod` tells us which base z is a stack of
+        let base = *self.base.get_or_insert(modulus);
+        match lhs {
+            Value::Const(value) if value >= 0 => Some(Value::Const(value % modulus)),
+            value
+                if interval(&value).is_within(Interval {
+                    min: 0,
+                    max: modulus - 1,
+                }) =>
+            {
+                Some(value)
+            }
+            value if modulus == base => entries(value, base)?.pop(),
+            _ => None,
+        }
+    }
+
+    fn register_intervals(&self, paths: &[Path]) -> [Interval; 4] {
+        let base = self.base.unwrap_or(i64::MAX);
+        [0, 1, 2, 3].map(|register| {
+            paths
+                .iter()
+                .map(|path| match &path.registers[register] {
+                    Value::Stack(entries) => Interval {
+                        min: 0,
+                        max: base.saturating_pow(entries.len() as u32).saturating_sub(1),
+                    },
+                    value => interval(value),
+                })
+                .reduce(Interval::union)
+                .unwrap()
+        })
+    }
+}
+
+fn interval(value: &Value) -> Interval {
+    match value {
+        Value::Const(value) => Interval {
+            min: *value,
+            max: *value,
+        },
+        Value::Digit { offset, .. } => Interval {
+            min: DIGITS.min + offset,
+            max: DIGITS.max + offset,
+        },
+        Value::Stack(_) => Interval {
+            min: 0,
+            max: i64::MAX,
+        },
+    }
+}
+
+/// The stack entries of a value, if it can be read as a stack in `base`.
+fn entries(value: Value, base: i64) -> Option<Vec<Value>> {
+    match value {
+        Value::Stack(entries) => Some(entries),
+        Value::Const(0) => Some(vec![]),
+        value
+            if interval(&value).is_within(Interval {
+                min: 0,
+                max: base - 1,
+            }) =>
+        {
+            Some(vec![value])
+        }
+        _ => None,
+    }
+}
+
+fn from_entries(mut entries: Vec<Value>) -> Value {
+    match entries.len() {
+        0 => Value::Const(0),
+        1 => entries.pop().unwrap(),
+        _ => Value::Stack(entries),
+    }
+}
+
+impl Display for Analysis {
+    /// The register ranges after each digit, then one line per way of being accepted.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (digit, [w, x, y, z]) in self.intervals.iter().enumerate() {
+            writeln!(f, "d{digit}: w {w}, x {x}, y {y}, z {z}")?;
+        }
+        for constraints in &self.accepted {
+            writeln!(f, "accepted when {}", constraints.iter().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Analysis {
+    /// The largest model number accepted by any path, if there is one.
+    pub(crate) fn max_model_number(&self) -> Option<u64> {
+        let largest_first = (1..=9).rev().collect_vec();
+        self.accepted
+            .iter()
+            .filter_map(|constraints| self.first_model_number(constraints, &largest_first))
+            .max()
+    }
+
+    /// The smallest model number accepted by any path, if there is one.
+    pub(crate) fn min_model_number(&self) -> Option<u64> {
+        let smallest_first = (1..=9).collect_vec();
+        self.accepted
+            .iter()
+            .filter_map(|constraints| self.first_model_number(constraints, &smallest_first))
+            .min()
+    }
+
+    /// Picks digits from the most significant down, in `ordered_digits_to_try` order, backtracking
+    /// whenever a constraint over already chosen digits fails.
+    fn first_model_number(
+        &self,
+        constraints: &[Constraint],
+        ordered_digits_to_try: &[i64],
+    ) -> Option<u64> {
+        fn evaluate(value: &Value, digits: &[i64]) -> Option<i64> {
+            match value {
+                Value::Const(value) => Some(*value),
+                Value::Digit { index, offset } => digits.get(*index).map(|digit| digit + offset),
+                Value::Stack(_) => None,
+            }
+        }
+
+        fn search(
+            digits: &mut Vec<i64>,
+            total: usize,
+            constraints: &[Constraint],
+            ordered_digits_to_try: &[i64],
+        ) -> bool {
+            let holds = constraints.iter().all(|constraint| {
+                match (
+                    evaluate(&constraint.lhs, digits),
+                    evaluate(&constraint.rhs, digits),
+                ) {
+                    (Some(lhs), Some(rhs)) => (lhs == rhs) == constraint.equal,
+                    _ => true,
+                }
+            });
+            if !holds {
+                return false;
+            }
+            if digits.len() == total {
+                return true;
+            }
+            for digit in ordered_digits_to_try {
+                digits.push(*digit);
+                if search(digits, total, constraints, ordered_digits_to_try) {
+                    return true;
+                }
+                digits.pop();
+            }
+            false
+        }
+
+        let mut digits = vec![];
+        search(&mut digits, self.digits, constraints, ordered_digits_to_try).then(|| {
+            digits
+                .into_iter()
+                .fold(0, |acc, digit| acc * 10 + digit as u64)
+        })
+    }
+}
+
+#[cfg(test)]
+fn example_analysis() -> Analysis {
+    use crate::utils::parse_example_testcases;
+    use nom::{bytes::complete::tag, multi::separated_list1};
+    let problem = &parse_example_testcases(include_str!("example.txt"))[0].problem;
+    let (_, instructions) = separated_list1(tag("\n"), Instruction::parse)(problem).unwrap();
+    analyze(&instructions).unwrap()
+}
+
+#[test]
+fn test_derives_push_pop_constraints() {
+    let analysis = example_analysis();
+
+    assert_eq!(14, analysis.digits);
+    // only the path where every pop matches the digit pushed earlier leaves z at 0
+    assert_eq!(1, analysis.accepted.len());
+    let constraints = analysis.accepted[0]
+        .iter()
+        .map(ToString::to_string)
+        .collect_vec();
+    assert_eq!(7, constraints.len());
+    assert!(constraints
+        .iter()
+        .all(|constraint| constraint.contains("==")));
+}
+
+#[test]
+fn test_tracks_intervals() {
+    let analysis = example_analysis();
+
+    // the first block always pushes w + 15 onto an empty z
+    assert_eq!(
+        [
+            Interval { min: 1, max: 9 },
+            Interval { min: 1, max: 1 },
+            Interval { min: 16, max: 24 },
+            Interval { min: 16, max: 24 },
+        ],
+        analysis.intervals[0]
+    );
+    assert!(analysis
+        .to_string()
+        .starts_with("d0: w [1, 9], x [1, 1], y [16, 24], z [16, 24]\n"));
+}
+
+#[test]
+fn test_rejects_unsupported_program() {
+    let instructions = [
+        Instruction::Inp(Register::W),
+        Instruction::Inp(Register::X),
+        Instruction::Mul(Register::W, InstructionInput::Register(Register::X)),
+    ];
+    assert!(matches!(
+        analyze(&instructions),
+        Err(AnalysisError::Unsupported(2))
+    ));
+}
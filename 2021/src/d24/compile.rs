@@ -0,0 +1,297 @@
+use super::{Instruction, InstructionInput, Register};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Operand {
+    Register(usize),
+    Immediate(i64),
+}
+
+/// A lowered instruction. Registers are indexed w, x, y, z.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Op {
+    Inp(usize),
+    Set(usize, Operand),
+    Add(usize, Operand),
+    Mul(usize, Operand),
+    Div(usize, Operand),
+    Mod(usize, Operand),
+    Eql(usize, Operand),
+}
+
+impl Op {
+    fn destination(&self) -> usize {
+        match self {
+            Op::Inp(register)
+            | Op::Set(register, _)
+            | Op::Add(register, _)
+            | Op::Mul(register, _)
+            | Op::Div(register, _)
+            | Op::Mod(register, _)
+            | Op::Eql(register, _) => *register,
+        }
+    }
+
+    fn operand(&self) -> Option<Operand> {
+        match self {
+            Op::Inp(_) => None,
+            Op::Set(_, operand)
+            | Op::Add(_, operand)
+            | Op::Mul(_, operand)
+            | Op::Div(_, operand)
+            | Op::Mod(_, operand)
+            | Op::Eql(_, operand) => Some(*operand),
+        }
+    }
+}
+
+/// An ALU program with everything that can be worked out ahead of time already done.
+///
+/// Only z is kept accurate, since it is the only register anyone looks at once the program ends.
+#[derive(Debug)]
+pub(crate) struct CompiledProgram {
+    ops: Vec<Op>,
+}
+
+impl CompiledProgram {
+    pub(crate) fn compile(instructions: &[Instruction]) -> Self {
+        let folded = fold_constants(instructions);
+        Self {
+            ops: eliminate_dead_writes(folded),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Runs the program, reading one input per `inp`, and returns the final value of z.
+    #[cfg(test)]
+    pub(crate) fn run(&self, inputs: &[i64]) -> i64 {
+        let mut registers = [0_i64; 4];
+        let mut inputs = inputs.iter();
+        let mut pointer = 0;
+        while let Some((input_pointer, register)) = self.run_until_input(pointer, &mut registers) {
+            registers[register] = *inputs.next().unwrap();
+            pointer = input_pointer + 1;
+        }
+        registers[Register::Z as usize]
+    }
+
+    /// Runs from `pointer` up to the next `inp`, returning where it is and which register it
+    /// reads into, or `None` once the program has finished.
+    pub(crate) fn run_until_input(
+        &self,
+        pointer: usize,
+        registers: &mut [i64; 4],
+    ) -> Option<(usize, usize)> {
+        for (offset, op) in self.ops[pointer..].iter().enumerate() {
+            let value = |operand| match operand {
+                Operand::Register(register) => registers[register],
+                Operand::Immediate(value) => value,
+            };
+            match *op {
+                Op::Inp(register) => return Some((pointer + offset, register)),
+                Op::Set(register, operand) => registers[register] = value(operand),
+                Op::Add(register, operand) => registers[register] += value(operand),
+                Op::Mul(register, operand) => registers[register] *= value(operand),
+                Op::Div(register, operand) => registers[register] /= value(operand),
+                Op::Mod(register, operand) => registers[register] %= value(operand),
+                Op::Eql(register, operand) => {
+                    registers[register] = (registers[register] == value(operand)) as i64
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Tracks which registers hold a value known at compile time, starting from all zeros. Operations
+/// on known values disappear, and known operands become immediates.
+fn fold_constants(instructions: &[Instruction]) -> Vec<Op> {
+    let mut known = [Some(0_i64); 4];
+    let mut ops = vec![];
+
+    for instruction in instructions {
+        let (register, input) = match instruction {
+            Instruction::Inp(register) => {
+                known[*register as usize] = None;
+                ops.push(Op::Inp(*register as usize));
+                continue;
+            }
+            Instruction::Add(register, input)
+            | Instruction::Mul(register, input)
+            | Instruction::Div(register, input)
+            | Instruction::Mod(register, input)
+            | Instruction::Eql(register, input) => (*register as usize, input),
+        };
+        let operand = match input {
+            InstructionInput::Number(value) => Operand::Immediate(*value),
+            InstructionInput::Register(other) => match known[*other as usize] {
+                Some(value) => Operand::Immediate(value),
+                None => Operand::Register(*other as usize),
+            },
+        };
+
+        let lhs = known[register];
+        let rhs = match operand {
+            Operand::Immediate(value) => Some(value),
+            Operand::Register(_) => None,
+        };
+        let folded = match (instruction, lhs, rhs) {
+            (Instruction::Add(..), Some(lhs), Some(rhs)) => Some(lhs + rhs),
+            (Instruction::Mul(..), Some(lhs), Some(rhs)) => Some(lhs * rhs),
+            (Instruction::Div(..), Some(lhs), Some(rhs)) if rhs != 0 => Some(lhs / rhs),
+            (Instruction::Mod(..), Some(lhs), Some(rhs)) if rhs != 0 => Some(lhs % rhs),
+            (Instruction::Eql(..), Some(lhs), Some(rhs)) => Some((lhs == rhs) as i64),
+            (Instruction::Mul(..), Some(0), _) | (Instruction::Mul(..), _, Some(0)) => Some(0),
+            (Instruction::Div(..) | Instruction::Mod(..), Some(0), _) => Some(0),
+            _ => None,
+        };
+        if let Some(value) = folded {
+            known[register] = Some(value);
+            continue;
+        }
+
+        match (instruction, lhs, rhs) {
+            // identities leave the register as it was
+            (Instruction::Add(..), _, Some(0))
+            | (Instruction::Mul(..) | Instruction::Div(..), _, Some(1)) => continue,
+            (Instruction::Add(..), Some(0), _) | (Instruction::Mul(..), Some(1), _) => {
+                ops.push(Op::Set(register, operand))
+            }
+            _ => {
+                if let Some(value) = lhs {
+                    ops.push(Op::Set(register, Operand::Immediate(value)));
+                }
+                ops.push(match instruction {
+                    Instruction::Add(..) => Op::Add(register, operand),
+                    Instruction::Mul(..) => Op::Mul(register, operand),
+                    Instruction::Div(..) => Op::Div(register, operand),
+                    Instruction::Mod(..) => Op::Mod(register, operand),
+                    Instruction::Eql(..) => Op::Eql(register, operand),
+                    Instruction::Inp(_) => unreachable!(),
+                });
+            }
+        }
+        known[register] = None;
+    }
+
+    // whatever is still known at the end has to actually be in the register
+    for (register, value) in known.into_iter().enumerate() {
+        if let Some(value) = value {
+            ops.push(Op::Set(register, Operand::Immediate(value)));
+        }
+    }
+    ops
+}
+
+/// Walks backwards from the end, where only z is read, and drops every write to a register that
+/// is overwritten before anything reads it. `inp` is always kept since it consumes an input.
+fn eliminate_dead_writes(ops: Vec<Op>) -> Vec<Op> {
+    let mut live = [false; 4];
+    live[Register::Z as usize] = true;
+
+    let mut kept = ops
+        .into_iter()
+        .rev()
+        .filter(|op| {
+            let destination = op.destination();
+            if !live[destination] && !matches!(op, Op::Inp(_)) {
+                return false;
+            }
+            // `inp` and `set` replace the register, everything else reads it first
+            live[destination] = !matches!(op, Op::Inp(_) | Op::Set(..));
+            if let Some(Operand::Register(register)) = op.operand() {
+                live[register] = true;
+            }
+            true
+        })
+        .collect::<Vec<_>>();
+    kept.reverse();
+    kept
+}
+
+#[cfg(test)]
+fn example_instructions() -> Vec<Instruction> {
+    use crate::utils::parse_example_testcases;
+    use nom::{bytes::complete::tag, multi::separated_list1};
+    let problem = &parse_example_testcases(include_str!("example.txt"))[0].problem;
+    let (_, instructions) = separated_list1(tag("\n"), Instruction::parse)(problem).unwrap();
+    instructions
+}
+
+/// Seeded random model numbers spread over the whole range.
+#[cfg(test)]
+fn model_numbers(count: usize) -> Vec<Vec<i64>> {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(2024);
+    (0..count)
+        .map(|_| (0..14).map(|_| rng.gen_range(1..=9)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+fn interpret(instructions: &[Instruction], inputs: &[i64]) -> i64 {
+    let mut alu = super::Alu::new(inputs.to_vec());
+    for instruction in instructions {
+        alu.execute(instruction);
+    }
+    alu.z
+}
+
+#[test]
+fn test_compiled_matches_interpreter() {
+    let instructions = example_instructions();
+    let compiled = CompiledProgram::compile(&instructions);
+
+    assert!(compiled.len() < instructions.len());
+    for inputs in model_numbers(1000) {
+        assert_eq!(interpret(&instructions, &inputs), compiled.run(&inputs));
+    }
+}
+
+#[test]
+fn test_folds_constants_and_drops_dead_writes() {
+    use super::Register::{W, X, Z};
+    let instructions = [
+        Instruction::Inp(W),
+        Instruction::Mul(X, InstructionInput::Number(0)),
+        Instruction::Add(X, InstructionInput::Number(3)),
+        Instruction::Add(Z, InstructionInput::Register(X)),
+        Instruction::Add(Z, InstructionInput::Register(W)),
+        Instruction::Mul(W, InstructionInput::Number(7)),
+    ];
+
+    assert_eq!(
+        vec![
+            Op::Inp(W as usize),
+            Op::Set(Z as usize, Operand::Immediate(3)),
+            Op::Add(Z as usize, Operand::Register(W as usize)),
+        ],
+        CompiledProgram::compile(&instructions).ops
+    );
+}
+
+#[bench]
+fn bench_interpreter(bencher: &mut test::Bencher) {
+    let instructions = example_instructions();
+    let inputs = model_numbers(100);
+    bencher.iter(|| {
+        for inputs in &inputs {
+            test::black_box(interpret(&instructions, inputs));
+        }
+    });
+}
+
+#[bench]
+fn bench_compiled(bencher: &mut test::Bencher) {
+    let compiled = CompiledProgram::compile(&example_instructions());
+    let inputs = model_numbers(100);
+    bencher.iter(|| {
+        for inputs in &inputs {
+            test::black_box(compiled.run(inputs));
+        }
+    });
+}
@@ -1,103 +1,126 @@
-use crate::d24::Register::{W, X, Y, Z};
+use crate::d24::{
+    analysis::analyze,
+    compile::CompiledProgram,
+    Register::{W, X, Y, Z},
+};
 use itertools::Itertools;
 use nom::{
-    bytes::complete::{tag, take, take_till},
-    combinator::map,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::i64 as parse_i64,
+    combinator::{map, value},
     multi::separated_list1,
-    sequence::tuple,
+    sequence::{preceded, separated_pair},
     IResult,
 };
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+
+mod analysis;
+mod compile;
 
 pub fn solve(problem: &str) -> (u64, u64) {
     let (_, instructions) = separated_list1(tag("\n"), Instruction::parse)(problem).unwrap();
     (solve1(&instructions), solve2(&instructions))
 }
 
+/// Prints the range of every register after each digit and the constraints the digits must meet.
+pub fn print_analysis(problem: &str) {
+    let (_, instructions) = separated_list1(tag("\n"), Instruction::parse)(problem).unwrap();
+    match analyze(&instructions) {
+        Ok(analysis) => print!("{analysis}"),
+        Err(err) => println!("{err}"),
+    }
+}
+
 fn solve1(instructions: &[Instruction]) -> u64 {
-    // find largest number
-    let ordered_digits_to_try = (1..=9).rev().collect_vec();
-    find_valid_number(instructions, &ordered_digits_to_try)
+    let analysis = analyze(instructions).unwrap_or_else(|err| panic!("{err}"));
+    analysis
+        .max_model_number()
+        .expect("program should accept at least one model number")
 }
 
 fn solve2(instructions: &[Instruction]) -> u64 {
-    // find smallest number
-    let ordered_digits_to_try = (1..=9).collect_vec();
-    find_valid_number(instructions, &ordered_digits_to_try)
+    let analysis = analyze(instructions).unwrap_or_else(|err| panic!("{err}"));
+    analysis
+        .min_model_number()
+        .expect("program should accept at least one model number")
 }
 
-fn find_valid_number(instructions: &[Instruction], ordered_digits_to_try: &[i64]) -> u64 {
-    let instruction_groups = instructions.chunks(18).collect_vec();
-    let mut no_solution_set = HashSet::new();
-    let mut valid_number_le = Vec::new();
+/// Tries every digit at every `inp`, remembering full ALU states that are known dead ends. Makes no
+/// assumptions about how the program is structured, so it is far slower than `solve`.
+pub fn solve_reference(problem: &str) -> (u64, u64) {
+    let (_, instructions) = separated_list1(tag("\n"), Instruction::parse)(problem).unwrap();
+    let largest_first = (1..=9).rev().collect_vec();
+    let smallest_first = (1..=9).collect_vec();
+    (
+        reference_search(&instructions, &largest_first),
+        reference_search(&instructions, &smallest_first),
+    )
+}
 
-    found_valid_num(
-        (0, 0),
-        &instruction_groups,
+fn reference_search(instructions: &[Instruction], ordered_digits_to_try: &[i64]) -> u64 {
+    let program = CompiledProgram::compile(instructions);
+    let mut dead_ends = HashSet::new();
+    let mut number = vec![];
+    let found = search_from_instruction(
+        0,
+        [0; 4],
+        &program,
         ordered_digits_to_try,
-        &mut no_solution_set,
-        &mut valid_number_le,
-        &mut Alu::new(vec![]),
+        &mut dead_ends,
+        &mut number,
     );
+    assert!(found, "program should accept at least one model number");
 
-    valid_number_le
-        .into_iter()
-        .rev()
-        .reduce(|acc, digit| acc * 10 + digit)
-        .unwrap() as u64
+    number.into_iter().fold(0, |acc, digit| acc * 10 + digit) as u64
 }
 
-fn found_valid_num(
-    (depth, z_prev): (usize, i64),
-    instruction_groups: &[&[Instruction]],
+fn search_from_instruction(
+    pointer: usize,
+    mut registers: [i64; 4],
+    program: &CompiledProgram,
     ordered_digits_to_try: &[i64],
-    no_solution_set: &mut HashSet<(usize, i64)>,
+    dead_ends: &mut HashSet<(usize, [i64; 4])>,
     current_number: &mut Vec<i64>,
-    alu: &mut Alu,
 ) -> bool {
-    // requires knowing that the input performs divisions by 26 that eventually need to make it to 0
-    // and experimentally figuring out that this still provides the correct answer
-    if 23_i64.saturating_pow((instruction_groups.len() - depth) as u32) < z_prev {
-        return false;
-    }
+    // run straight through to the next input
+    let Some((pointer, input_register)) = program.run_until_input(pointer, &mut registers) else {
+        return registers[Z as usize] == 0;
+    };
 
-    if z_prev > 1_000_000_000_000 {
-        println!("z_prev: {z_prev}");
-    }
-
-    if depth == 14 {
-        return z_prev == 0;
+    // the register about to be overwritten cannot affect the outcome
+    registers[input_register] = 0;
+    let state = (pointer, registers);
+    if dead_ends.contains(&state) {
+        return false;
     }
 
-    let instructions = instruction_groups[depth];
     for digit in ordered_digits_to_try {
-        alu.z = z_prev;
-        alu.inputs.push(*digit);
-        alu.execute_all(instructions);
+        let mut next_registers = registers;
+        next_registers[input_register] = *digit;
 
-        let new_state = (depth + 1, alu.z);
-        if no_solution_set.contains(&new_state) {
-            continue;
-        }
-
-        let found_solution = found_valid_num(
-            new_state,
-            instruction_groups,
+        current_number.push(*digit);
+        if search_from_instruction(
+            pointer + 1,
+            next_registers,
+            program,
             ordered_digits_to_try,
-            no_solution_set,
+            dead_ends,
             current_number,
-            alu,
-        );
-        if found_solution {
-            current_number.push(*digit);
+        ) {
             return true;
         }
-
-        no_solution_set.insert(new_state);
+        current_number.pop();
     }
+
+    dead_ends.insert(state);
     false
 }
 
+#[cfg(test)]
 #[derive(PartialEq, Debug, Clone)]
 struct Alu {
     w: i64,
@@ -107,6 +130,7 @@ struct Alu {
     inputs: Vec<i64>,
 }
 
+#[cfg(test)]
 impl Alu {
     fn new(inputs: Vec<i64>) -> Self {
         Self {
@@ -118,12 +142,6 @@ impl Alu {
         }
     }
 
-    fn execute_all(&mut self, instructions: &[Instruction]) {
-        for instruction in instructions {
-            self.execute(instruction)
-        }
-    }
-
     fn execute(&mut self, instruction: &Instruction) {
         match instruction {
             Instruction::Inp(num) => {
@@ -180,8 +198,8 @@ impl Alu {
     }
 }
 
-#[derive(Debug, Clone)]
-enum Instruction {
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Instruction {
     Inp(Register),
     Add(Register, InstructionInput),
     Mul(Register, InstructionInput),
@@ -191,60 +209,101 @@ enum Instruction {
 }
 
 impl Instruction {
-    fn parse(input: &str) -> IResult<&str, Self> {
-        map(
-            tuple((
-                take(4_usize),
-                separated_list1(tag(" "), InstructionInput::parse),
-            )),
-            |(ins, args)| {
-                let reg = match args[0] {
-                    InstructionInput::Register(r) => r,
-                    InstructionInput::Number(_) => panic!("this was supposed to be a register"),
-                };
-                match ins {
-                    "inp " => Self::Inp(reg),
-                    "add " => Self::Add(reg, args[1]),
-                    "mul " => Self::Mul(reg, args[1]),
-                    "div " => Self::Div(reg, args[1]),
-                    "mod " => Self::Mod(reg, args[1]),
-                    "eql " => Self::Eql(reg, args[1]),
-                    _ => panic!("invalid instruction: {ins:?}"),
-                }
-            },
-        )(input)
+    pub(crate) fn parse(input: &str) -> IResult<&str, Self> {
+        alt((
+            map(preceded(tag("inp "), Register::parse), Self::Inp),
+            map(preceded(tag("add "), Self::parse_args), |(r, i)| {
+                Self::Add(r, i)
+            }),
+            map(preceded(tag("mul "), Self::parse_args), |(r, i)| {
+                Self::Mul(r, i)
+            }),
+            map(preceded(tag("div "), Self::parse_args), |(r, i)| {
+                Self::Div(r, i)
+            }),
+            map(preceded(tag("mod "), Self::parse_args), |(r, i)| {
+                Self::Mod(r, i)
+            }),
+            map(preceded(tag("eql "), Self::parse_args), |(r, i)| {
+                Self::Eql(r, i)
+            }),
+        ))(input)
+    }
+
+    fn parse_args(input: &str) -> IResult<&str, (Register, InstructionInput)> {
+        separated_pair(Register::parse, tag(" "), InstructionInput::parse)(input)
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (name, register, input) = match self {
+            Instruction::Inp(register) => return write!(f, "inp {register}"),
+            Instruction::Add(register, input) => ("add", register, input),
+            Instruction::Mul(register, input) => ("mul", register, input),
+            Instruction::Div(register, input) => ("div", register, input),
+            Instruction::Mod(register, input) => ("mod", register, input),
+            Instruction::Eql(register, input) => ("eql", register, input),
+        };
+        write!(f, "{name} {register} {input}")
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum InstructionInput {
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum InstructionInput {
     Register(Register),
     Number(i64),
 }
 
 impl InstructionInput {
     fn parse(input: &str) -> IResult<&str, Self> {
-        map(
-            take_till(|c: char| !c.is_alphanumeric() && c != '-'),
-            |num| match num {
-                "w" => Self::Register(W),
-                "x" => Self::Register(X),
-                "y" => Self::Register(Y),
-                "z" => Self::Register(Z),
-                literal => Self::Number(literal.parse().unwrap()),
-            },
-        )(input)
+        alt((
+            map(Register::parse, Self::Register),
+            map(parse_i64, Self::Number),
+        ))(input)
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Register {
+impl Display for InstructionInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InstructionInput::Register(register) => write!(f, "{register}"),
+            InstructionInput::Number(num) => write!(f, "{num}"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Register {
     W,
     X,
     Y,
     Z,
 }
 
+impl Register {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        alt((
+            value(W, tag("w")),
+            value(X, tag("x")),
+            value(Y, tag("y")),
+            value(Z, tag("z")),
+        ))(input)
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            W => "w",
+            X => "x",
+            Y => "y",
+            Z => "z",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[test]
 fn test() {
     use crate::utils::basic_test;
@@ -252,6 +311,22 @@ fn test() {
     basic_test(input, solve);
 }
 
+#[test]
+fn test_reference_agrees() {
+    use crate::utils::parse_example_testcases;
+    let input = include_str!("example.txt");
+    let lines = parse_example_testcases(input)[0]
+        .problem
+        .lines()
+        .map(str::to_string)
+        .collect_vec();
+    // the first 8 and the last 6 digit blocks of the example each push and pop z the same number
+    // of times, so on their own they still form programs that accept some model numbers
+    for problem in [lines[..8 * 18].join("\n"), lines[8 * 18..].join("\n")] {
+        assert_eq!(solve(&problem), solve_reference(&problem));
+    }
+}
+
 #[test]
 fn test_execute_instructions() {
     let instructions = vec![
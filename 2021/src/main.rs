@@ -1,4 +1,7 @@
+#![feature(test)]
+
 use crate::utils::SolverFn;
+use advent_common::timing;
 use clap::Parser;
 use core::panic;
 use itertools::Itertools;
@@ -32,11 +35,22 @@ mod d24;
 mod d25;
 mod utils;
 
+#[cfg(test)]
+extern crate test;
+
 fn main() {
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
 
     let cli = Cli::parse();
 
+    if let Some(source) = &cli.compile {
+        match cli.day {
+            16 => d16::print_compiled(source),
+            _ => panic!("only day 16 compiles expressions"),
+        }
+        return;
+    }
+
     let file = match cli.example {
         true => "example.txt",
         false => "input.txt",
@@ -49,12 +63,57 @@ fn main() {
         false => get_real_testcases(cli.day, &input),
     };
 
-    let solver = get_solver(cli.day);
+    if cli.plan {
+        match cli.day {
+            23 => test_cases
+                .iter()
+                .for_each(|test_case| d23::print_plans(&test_case.problem)),
+            _ => panic!("only day 23 prints plans"),
+        }
+        return;
+    }
+
+    if cli.trace {
+        match cli.day {
+            18 => test_cases
+                .iter()
+                .for_each(|test_case| d18::print_trace(&test_case.problem)),
+            _ => panic!("only day 18 prints a trace"),
+        }
+        return;
+    }
+
+    if cli.analyze {
+        match cli.day {
+            24 => test_cases
+                .iter()
+                .for_each(|test_case| d24::print_analysis(&test_case.problem)),
+            _ => panic!("only day 24 prints an analysis"),
+        }
+        return;
+    }
+
+    if cli.registration {
+        match cli.day {
+            19 => test_cases
+                .iter()
+                .for_each(|test_case| d19::print_registration(&test_case.problem)),
+            _ => panic!("only day 19 prints registrations"),
+        }
+        return;
+    }
+
+    let solver = match cli.reference {
+        true => get_reference_solver(cli.day),
+        false => get_solver(cli.day),
+    };
 
     let mut printable_results = test_cases.iter().map(|test_case| {
         let start = time::Instant::now();
         let res = solver(&test_case.problem);
         let elapsed = start.elapsed();
+        let run_name = format!("d{:02}::{}", cli.day, file.trim_end_matches(".txt"));
+        timing::check(&run_name, elapsed, utils::TIME_BUDGETS);
 
         let (answer1, res0) = format_to_longest(test_case.answer1, res.0);
         let (answer2, res1) = format_to_longest(test_case.answer2, res.1);
@@ -122,6 +181,15 @@ fn get_solver(day: u8) -> SolverFn {
     }
 }
 
+fn get_reference_solver(day: u8) -> SolverFn {
+    match day {
+        24 => d24::solve_reference,
+        _ => {
+            panic!("no reference solver for this day");
+        }
+    }
+}
+
 fn get_real_testcases(day: u8, problem: &str) -> Vec<TestCase> {
     let problem = problem.to_string();
     match day {
@@ -261,4 +329,22 @@ struct Cli {
     day: u8,
     #[arg(long)]
     example: bool,
+    /// use the slow but general reference solver instead of the optimized one
+    #[arg(long)]
+    reference: bool,
+    /// day 16 only: print the BITS transmission for an expression such as "1 + max(2, 3)"
+    #[arg(long)]
+    compile: Option<String>,
+    /// day 23 only: print the cheapest sequence of moves, with the map after each one
+    #[arg(long)]
+    plan: bool,
+    /// day 18 only: print every explode and split while adding up the homework
+    #[arg(long)]
+    trace: bool,
+    /// day 19 only: print each pair of overlapping scanners and how one maps onto the other
+    #[arg(long)]
+    registration: bool,
+    /// day 24 only: print each register's range after every digit and the constraints on the digits
+    #[arg(long)]
+    analyze: bool,
 }
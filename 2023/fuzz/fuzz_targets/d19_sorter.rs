@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/d19/mod.rs"]
+mod d19;
+
+use d19::Sorter;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(sorter) = Sorter::parse(input) else {
+        return;
+    };
+
+    let printed = sorter.to_string();
+    assert_eq!(Ok(sorter), Sorter::parse(&printed));
+});
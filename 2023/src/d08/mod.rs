@@ -1,6 +1,9 @@
+use advent_common::{
+    cycle::find_cycle,
+    graph::{Edges, Graph},
+    number::{first_common_step, Schedule},
+};
 use itertools::Itertools;
-use num::Integer;
-use std::collections::HashMap;
 
 pub fn solve(problem: &str) -> (usize, usize) {
     let steps = problem
@@ -15,70 +18,58 @@ pub fn solve(problem: &str) -> (usize, usize) {
         })
         .collect_vec();
 
-    let node_map = problem
-        .lines()
-        .skip(2)
-        .map(|line| {
-            // ZZZ = (ZZZ, ZZZ)
-            (&line[..3], (&line[7..10], &line[12..15]))
-        })
-        .collect::<HashMap<_, _>>();
+    // every node leads left then right, so its neighbours are in that order
+    let mut node_map = Graph::new(Edges::Directed);
+    for line in problem.lines().skip(2) {
+        // ZZZ = (ZZZ, ZZZ)
+        node_map.add_edge(&line[..3], &line[7..10]);
+        node_map.add_edge(&line[..3], &line[12..15]);
+    }
 
     (solve1(&steps, &node_map), solve2(&steps, &node_map))
 }
 
-fn solve1(steps: &[Step], node_map: &HashMap<&str, (&str, &str)>) -> usize {
-    let mut node = "AAA";
+fn solve1(steps: &[Step], node_map: &Graph) -> usize {
+    let mut node = node_map.id("AAA").unwrap();
     let mut total_steps = 0;
     for step in steps.iter().cycle() {
-        node = match step {
-            Step::Left => node_map[node].0,
-            Step::Right => node_map[node].1,
-        };
+        node = step.take(node_map, node);
         total_steps += 1;
-        if node == "ZZZ" {
+        if node_map.label(node) == "ZZZ" {
             break;
         }
     }
     total_steps
 }
 
-fn solve2(steps: &[Step], node_map: &HashMap<&str, (&str, &str)>) -> usize {
-    let nodes = node_map
-        .keys()
-        .cloned()
-        .filter(|key| key.ends_with('A'))
-        .collect_vec();
-
-    let loop_sizes = nodes
-        .iter()
-        .map(|node| steps_to_see_z(node, steps, node_map))
+fn solve2(steps: &[Step], node_map: &Graph) -> usize {
+    let schedules = node_map
+        .nodes()
+        .filter(|node| node_map.label(*node).ends_with('A'))
+        .map(|node| arrivals_at_z(node, steps, node_map))
         .collect_vec();
-
-    loop_sizes
-        .into_iter()
-        .reduce(|prev_loop, curr_loop| prev_loop.lcm(&curr_loop))
-        .unwrap()
+    first_common_step(&schedules).expect("the ghosts should all reach a Z node on the same step")
 }
 
-fn steps_to_see_z(
-    start_node: &str,
-    steps: &[Step],
-    node_map: &HashMap<&str, (&str, &str)>,
-) -> usize {
-    let mut node = start_node;
-    for (steps_taken, step) in steps.iter().cycle().enumerate() {
-        if node.ends_with('Z') {
-            // assume the next node loops back to the start, as this problem is to be solved
-            // using LCM
-            return steps_taken;
+/// The steps on which a ghost starting at `start_node` stands on a node ending in Z. Where it is
+/// and how far through the steps it is repeat together, so these repeat too, though not
+/// necessarily from step zero or only once per loop.
+fn arrivals_at_z(start_node: usize, steps: &[Step], node_map: &Graph) -> Schedule {
+    let walk = |(node, step): &mut (usize, usize)| {
+        *node = steps[*step].take(node_map, *node);
+        *step = (*step + 1) % steps.len();
+    };
+    let period = find_cycle(&(start_node, 0), walk);
+
+    let mut ghost = (start_node, 0);
+    let mut arrivals = vec![];
+    for steps_taken in 0..period.start + period.length {
+        if node_map.label(ghost.0).ends_with('Z') {
+            arrivals.push(steps_taken);
         }
-        node = match step {
-            Step::Left => node_map[node].0,
-            Step::Right => node_map[node].1,
-        };
+        walk(&mut ghost);
     }
-    unreachable!()
+    Schedule::new(arrivals, period.start, period.length)
 }
 
 enum Step {
@@ -86,9 +77,39 @@ enum Step {
     Right,
 }
 
+impl Step {
+    fn take(&self, node_map: &Graph, node: usize) -> usize {
+        match self {
+            Step::Left => node_map.neighbours(node)[0],
+            Step::Right => node_map.neighbours(node)[1],
+        }
+    }
+}
+
 #[test]
 fn test() {
     use crate::utils::basic_test;
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_offset_cycles() {
+    // one ghost is on a Z at steps 2, 5, 8, ... and the other at 1, 5, 9, ...
+    let problem = "L
+
+11A = (11B, XXX)
+11B = (11Z, XXX)
+11Z = (11C, XXX)
+11C = (11D, XXX)
+11D = (11Z, XXX)
+22A = (22Z, XXX)
+22Z = (22B, XXX)
+22B = (22C, XXX)
+22C = (22D, XXX)
+22D = (22Z, XXX)
+AAA = (ZZZ, XXX)
+ZZZ = (ZZZ, XXX)
+XXX = (XXX, XXX)";
+    assert_eq!((1, 5), solve(problem));
+}
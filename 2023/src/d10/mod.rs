@@ -1,6 +1,10 @@
 use crate::utils::get_cross_neighbors;
+use advent_common::{
+    differential::differential_test,
+    search::{bfs, SearchState},
+};
 use itertools::Itertools;
-use pathfinding::prelude::astar;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use std::collections::{HashMap, HashSet, VecDeque};
 
 pub fn solve(problem: &str) -> (usize, usize) {
@@ -15,20 +19,13 @@ fn solve1(start_coord: &CoordI64, mut pipe_map: HashMap<CoordI64, Vec<CoordI64>>
     // break loop at start to make path / length finding easier
     let coords = pipe_map.get_mut(start_coord).unwrap();
     let end = coords.pop().unwrap();
-    let (_, dist) = astar(
-        start_coord,
-        |coord| {
-            pipe_map
-                .get(coord)
-                .expect("successors should already be in the loop")
-                .iter()
-                .map(|n| (*n, 1))
-                .collect_vec()
-        },
-        |_| 0,
-        |coord| coord == &end,
-    )
-    .expect("should find path to end of loop");
+    let pipe_loop = PipeLoop {
+        pipe_map: &pipe_map,
+        end,
+    };
+    let dist = bfs(*start_coord, &pipe_loop)
+        .expect("should find path to end of loop")
+        .cost;
     (dist + 1) / 2
 }
 
@@ -163,6 +160,233 @@ fn parse_input(input: &str) -> (CoordI64, HashMap<CoordI64, Vec<CoordI64>>) {
 
 type CoordI64 = (i64, i64);
 
+/// The loop of pipes, cut open just after the start so that it has an end.
+struct PipeLoop<'a> {
+    pipe_map: &'a HashMap<CoordI64, Vec<CoordI64>>,
+    end: CoordI64,
+}
+
+impl SearchState<PipeLoop<'_>> for CoordI64 {
+    type Cost = usize;
+
+    fn successors(&self, pipe_loop: &PipeLoop) -> Vec<(Self, usize)> {
+        pipe_loop
+            .pipe_map
+            .get(self)
+            .expect("successors should already be in the loop")
+            .iter()
+            .map(|n| (*n, 1))
+            .collect_vec()
+    }
+
+    fn is_goal(&self, pipe_loop: &PipeLoop) -> bool {
+        *self == pipe_loop.end
+    }
+}
+
+type Grid = Vec<Vec<char>>;
+
+/// Walks the loop tile by tile and counts enclosed tiles with a scanline, without any of the
+/// tricks `solve` relies on.
+pub fn solve_reference(problem: &str) -> (usize, usize) {
+    let grid = problem
+        .lines()
+        .map(|line| line.chars().collect_vec())
+        .collect_vec();
+    let height = grid.len() as i64;
+    let width = grid[0].len() as i64;
+    let tile = |(row, col): CoordI64| {
+        if (0..height).contains(&row) && (0..width).contains(&col) {
+            grid[row as usize][col as usize]
+        } else {
+            '.'
+        }
+    };
+    let connections = |coord: CoordI64, c: char| {
+        pipe_directions(c)
+            .iter()
+            .map(|d| (coord.0 + d.0, coord.1 + d.1))
+            .collect_vec()
+    };
+
+    let start = (0..height)
+        .cartesian_product(0..width)
+        .find(|coord| tile(*coord) == 'S')
+        .unwrap();
+    let start_dirs = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+        .into_iter()
+        .filter(|d| {
+            let neighbor = (start.0 + d.0, start.1 + d.1);
+            connections(neighbor, tile(neighbor)).contains(&start)
+        })
+        .collect_vec();
+    let start_pipe = "-|JLF7"
+        .chars()
+        .find(|c| pipe_directions(*c).iter().all(|d| start_dirs.contains(d)))
+        .unwrap();
+    let pipe_at = |coord: CoordI64| match tile(coord) {
+        'S' => start_pipe,
+        c => c,
+    };
+
+    let mut loop_tiles = HashSet::from([start]);
+    let mut prev = start;
+    let mut curr = connections(start, start_pipe)[0];
+    while curr != start {
+        loop_tiles.insert(curr);
+        let next = connections(curr, pipe_at(curr))
+            .into_iter()
+            .find(|n| n != &prev)
+            .unwrap();
+        prev = curr;
+        curr = next;
+    }
+
+    let mut enclosed = 0;
+    for row in 0..height {
+        let mut inside = false;
+        for col in 0..width {
+            if loop_tiles.contains(&(row, col)) {
+                // crossing any pipe that reaches upward flips the parity of the scanline
+                if pipe_directions(pipe_at((row, col))).contains(&(-1, 0)) {
+                    inside = !inside;
+                }
+            } else if inside {
+                enclosed += 1;
+            }
+        }
+    }
+
+    (loop_tiles.len() / 2, enclosed)
+}
+
+fn pipe_directions(c: char) -> Vec<CoordI64> {
+    match c {
+        '-' => vec![(0, -1), (0, 1)],
+        '|' => vec![(-1, 0), (1, 0)],
+        'J' => vec![(0, -1), (-1, 0)],
+        'L' => vec![(-1, 0), (0, 1)],
+        'F' => vec![(0, 1), (1, 0)],
+        '7' => vec![(1, 0), (0, -1)],
+        _ => vec![],
+    }
+}
+
+/// Generates a `size` x `size` maze containing a single random loop, with the remaining tiles
+/// filled by junk pipes that never connect to `S`.
+fn generate_input(rng: &mut StdRng, size: usize) -> Grid {
+    let size = size.max(2) as i64;
+    let row = rng.gen_range(0..size - 1);
+    let col = rng.gen_range(0..size - 1);
+    let mut loop_coords = vec![
+        (row, col),
+        (row, col + 1),
+        (row + 1, col + 1),
+        (row + 1, col),
+    ];
+    let mut on_loop = loop_coords.iter().cloned().collect::<HashSet<_>>();
+
+    // grow the loop with random local moves, each of which keeps it a simple cycle
+    let in_bounds = |(r, c): CoordI64| (0..size).contains(&r) && (0..size).contains(&c);
+    for _ in 0..size * size * 4 {
+        let len = loop_coords.len();
+        let idx = rng.gen_range(0..len);
+        let a = loop_coords[idx];
+        let b = loop_coords[(idx + 1) % len];
+        let c = loop_coords[(idx + 2) % len];
+
+        if rng.gen_bool(0.5) {
+            // detour the a-b edge around two free tiles to one side of it
+            let (dr, dc) = (b.1 - a.1, a.0 - b.0);
+            let sign = if rng.gen_bool(0.5) { 1 } else { -1 };
+            let a_new = (a.0 + sign * dr, a.1 + sign * dc);
+            let b_new = (b.0 + sign * dr, b.1 + sign * dc);
+            if in_bounds(a_new)
+                && in_bounds(b_new)
+                && !on_loop.contains(&a_new)
+                && !on_loop.contains(&b_new)
+            {
+                loop_coords.splice(idx + 1..idx + 1, [a_new, b_new]);
+                on_loop.extend([a_new, b_new]);
+            }
+        } else if a.0 != c.0 && a.1 != c.1 {
+            // flip the corner at b across the a-c diagonal, growing or shrinking the interior
+            let flipped = (a.0 + c.0 - b.0, a.1 + c.1 - b.1);
+            if in_bounds(flipped) && !on_loop.contains(&flipped) {
+                loop_coords[(idx + 1) % len] = flipped;
+                on_loop.remove(&b);
+                on_loop.insert(flipped);
+            }
+        }
+    }
+
+    let junk = ['-', '|', 'J', 'L', 'F', '7', '.'];
+    let mut grid = (0..size)
+        .map(|_| (0..size).map(|_| *junk.choose(rng).unwrap()).collect_vec())
+        .collect_vec();
+    for (idx, curr) in loop_coords.iter().enumerate() {
+        let prev = loop_coords[(idx + loop_coords.len() - 1) % loop_coords.len()];
+        let next = loop_coords[(idx + 1) % loop_coords.len()];
+        let dirs = [
+            (prev.0 - curr.0, prev.1 - curr.1),
+            (next.0 - curr.0, next.1 - curr.1),
+        ];
+        grid[curr.0 as usize][curr.1 as usize] = "-|JLF7"
+            .chars()
+            .find(|c| pipe_directions(*c).iter().all(|d| dirs.contains(d)))
+            .unwrap();
+    }
+
+    let start = *loop_coords.choose(rng).unwrap();
+    grid[start.0 as usize][start.1 as usize] = 'S';
+    for neighbor in get_cross_neighbors(&start) {
+        let (r, c) = (neighbor.0 as usize, neighbor.1 as usize);
+        let Some(c_ref) = grid.get_mut(r).and_then(|line| line.get_mut(c)) else {
+            continue;
+        };
+        let points_to_start = pipe_directions(*c_ref)
+            .iter()
+            .any(|d| (neighbor.0 + d.0, neighbor.1 + d.1) == start);
+        if !on_loop.contains(&neighbor) && points_to_start {
+            *c_ref = '.';
+        }
+    }
+    grid
+}
+
+/// Candidate smaller failures: every junk pipe individually replaced by ground.
+fn shrink_input(grid: &Grid) -> Vec<Grid> {
+    let (s_coord, pipe_map) = parse_input(&grid_to_string(grid));
+    let mut loop_tiles = doubled_state_space_keys(&s_coord, &pipe_map)
+        .into_iter()
+        .filter(|(r, c)| r % 2 == 0 && c % 2 == 0)
+        .map(|(r, c)| ((r / 2) as usize, (c / 2) as usize))
+        .collect::<HashSet<_>>();
+    loop_tiles.insert((s_coord.0 as usize, s_coord.1 as usize));
+
+    (0..grid.len())
+        .cartesian_product(0..grid[0].len())
+        .filter(|(r, c)| grid[*r][*c] != '.' && !loop_tiles.contains(&(*r, *c)))
+        .map(|(r, c)| {
+            let mut smaller = grid.clone();
+            smaller[r][c] = '.';
+            smaller
+        })
+        .collect_vec()
+}
+
+fn grid_to_string(grid: &[Vec<char>]) -> String {
+    grid.iter().map(|line| line.iter().join("")).join("\n")
+}
+
+pub fn differential(iterations: usize, size: usize) -> Result<(), String> {
+    differential_test(iterations, size, generate_input, shrink_input, |grid| {
+        let problem = grid_to_string(grid);
+        solve(&problem) == solve_reference(&problem)
+    })
+    .map_err(|grid| grid_to_string(&grid))
+}
+
 #[test]
 fn test() {
     use crate::utils::basic_test;
@@ -170,6 +394,18 @@ fn test() {
     basic_test(input, solve);
 }
 
+#[test]
+fn test_reference() {
+    use crate::utils::basic_test;
+    let input = include_str!("example.txt");
+    basic_test(input, solve_reference);
+}
+
+#[test]
+fn test_differential() {
+    assert_eq!(Ok(()), differential(200, 12));
+}
+
 #[test]
 fn test_double_state_space() {
     //   0 1
@@ -1,5 +1,5 @@
+use advent_common::cycle::state_after_by_key;
 use itertools::Itertools;
-use std::collections::HashMap;
 
 pub fn solve(problem: &str) -> (usize, usize) {
     let problem = problem
@@ -34,43 +34,38 @@ fn solve1(mut problem: Vec<Vec<char>>) -> usize {
         .sum()
 }
 
-fn solve2(mut problem: Vec<Vec<char>>) -> usize {
-    let cycles_req = 1_000_000_000;
-    let mut memory = HashMap::new();
-    let mut cycles_taken = 0;
-    while !memory.contains_key(&problem) {
-        memory.insert(problem.clone(), cycles_taken);
-
-        tilt_north(&mut problem);
-        tilt_west(&mut problem);
-        tilt_south(&mut problem);
-        tilt_east(&mut problem);
+fn solve2(problem: Vec<Vec<char>>) -> usize {
+    let spun = state_after_by_key(
+        &problem,
+        1_000_000_000,
+        |grid| spin_cycle(grid),
+        |grid| round_rocks(grid),
+    );
+    let height = spun.len();
+    round_rocks(&spun).iter().map(|(row, _)| height - row).sum()
+}
 
-        cycles_taken += 1;
-    }
+fn round_rocks(problem: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let width = problem[0].len();
+    (0..problem.len())
+        .cartesian_product(0..width)
+        .filter(|(row, col)| problem[*row][*col] == 'O')
+        .collect()
+}
 
-    let meta_cycle_repeat_start = memory[&problem];
-    let meta_cycle_len = cycles_taken - meta_cycle_repeat_start;
-    let required_cycle_pos_within_meta_cycle =
-        (cycles_req - meta_cycle_repeat_start) % meta_cycle_len;
+fn spin_cycle(problem: &mut [Vec<char>]) {
+    tilt_north(problem);
+    tilt_west(problem);
+    tilt_south(problem);
+    tilt_east(problem);
+}
 
-    let problem_at_req_cycle = memory
+#[cfg(test)]
+fn render(problem: &[Vec<char>]) -> String {
+    problem
         .iter()
-        .find(|(_, cycle)| {
-            **cycle == required_cycle_pos_within_meta_cycle + meta_cycle_repeat_start
-        })
-        .map(|(problem, _)| problem.clone())
-        .unwrap();
-
-    let height = problem_at_req_cycle.len();
-    let width = problem_at_req_cycle[0].len();
-    (0..height)
-        .cartesian_product(0..width)
-        .map(|(row, col)| match problem_at_req_cycle[row][col] {
-            'O' => height - row,
-            _ => 0,
-        })
-        .sum()
+        .map(|line| line.iter().collect::<String>())
+        .join("\n")
 }
 
 fn tilt_north(problem: &mut [Vec<char>]) {
@@ -173,3 +168,18 @@ fn test() {
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_spin_cycle_snapshots() {
+    use crate::utils::parse_example_testcases;
+    let example = &parse_example_testcases(include_str!("example.txt"))[0];
+    let mut problem = example
+        .problem
+        .lines()
+        .map(|line| line.chars().collect_vec())
+        .collect_vec();
+    for cycle in 1..=3 {
+        spin_cycle(&mut problem);
+        insta::assert_snapshot!(format!("cycle_{cycle}"), render(&problem));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::utils::{get_cross_neighbors, manhattan_distance};
+use advent_common::search::{astar, Path, SearchState};
 use itertools::Itertools;
-use pathfinding::prelude::astar;
 use std::{ops::Range, thread};
 
 pub fn solve(problem: &str) -> (usize, usize) {
@@ -32,34 +32,38 @@ fn solve2(heat_loss_map: &[Vec<usize>]) -> usize {
 }
 
 fn min_heat_loss(heat_loss_map: &[Vec<usize>], ultra_crucible: bool) -> usize {
+    min_heat_loss_path(heat_loss_map, ultra_crucible).cost
+}
+
+fn min_heat_loss_path(heat_loss_map: &[Vec<usize>], ultra_crucible: bool) -> Path<Crucible, usize> {
     let height = heat_loss_map.len();
     let width = heat_loss_map[0].len();
-    let dest = (height - 1, width - 1);
-    let row_bounds = 0..height;
-    let col_bounds = 0..height;
-    let (_, distance) = astar(
-        &Crucible {
+    let city = City {
+        heat_loss_map,
+        row_bounds: 0..height,
+        col_bounds: 0..width,
+        dest: (height - 1, width - 1),
+        ultra_crucible,
+    };
+    astar(
+        Crucible {
             position: (0, 0),
             prev_position: (0, 0),
             moves_without_turning: 0,
         },
-        |crucible| crucible.neighbors(&row_bounds, &col_bounds, heat_loss_map, ultra_crucible),
-        |crucible| manhattan_distance(&crucible.position, &dest),
-        |crucible| crucible.position == dest,
+        &city,
     )
-    .expect("should find path to destination");
-
-    distance
+    .expect("should find path to destination")
 }
 
-#[allow(dead_code)]
-fn display(path: &Vec<Crucible>, mut heat_loss_map: Vec<Vec<usize>>) {
+#[cfg(test)]
+fn render(path: &[Crucible], mut heat_loss_map: Vec<Vec<usize>>) -> String {
     for crucible in path {
         let (r, c) = crucible.position;
         heat_loss_map[r][c] = 0;
     }
 
-    let s = heat_loss_map
+    heat_loss_map
         .iter()
         .map(|line| {
             line.iter().fold(String::new(), |mut acc, n| {
@@ -72,26 +76,35 @@ fn display(path: &Vec<Crucible>, mut heat_loss_map: Vec<Vec<usize>>) {
                 acc
             })
         })
-        .join("\n");
-    println!("{s}\n");
+        .join("\n")
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+struct City<'a> {
+    heat_loss_map: &'a [Vec<usize>],
+    row_bounds: Range<usize>,
+    col_bounds: Range<usize>,
+    dest: (usize, usize),
+    ultra_crucible: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 struct Crucible {
     position: (usize, usize),
     prev_position: (usize, usize),
     moves_without_turning: usize,
 }
 
-impl Crucible {
-    fn neighbors(
-        &self,
-        row_bounds: &Range<usize>,
-        col_bounds: &Range<usize>,
-        heat_loss_map: &[Vec<usize>],
-        ultra: bool,
-    ) -> Vec<(Self, usize)> {
-        let (min_in_a_row, max_in_a_row) = if ultra { (3, 9) } else { (0, 2) };
+impl SearchState<City<'_>> for Crucible {
+    type Cost = usize;
+
+    fn successors(&self, city: &City) -> Vec<(Self, usize)> {
+        let City {
+            heat_loss_map,
+            row_bounds,
+            col_bounds,
+            ..
+        } = city;
+        let (min_in_a_row, max_in_a_row) = if city.ultra_crucible { (3, 9) } else { (0, 2) };
 
         get_cross_neighbors(&self.position)
             .into_iter()
@@ -142,6 +155,14 @@ impl Crucible {
             })
             .collect()
     }
+
+    fn heuristic(&self, city: &City) -> usize {
+        manhattan_distance(&self.position, &city.dest)
+    }
+
+    fn is_goal(&self, city: &City) -> bool {
+        self.position == city.dest
+    }
 }
 
 #[test]
@@ -150,3 +171,22 @@ fn test() {
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_render_snapshots() {
+    use crate::utils::parse_example_testcases;
+    let example = &parse_example_testcases(include_str!("example.txt"))[0];
+    let heat_loss_map = example
+        .problem
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| c.to_digit(10).unwrap() as usize)
+                .collect_vec()
+        })
+        .collect_vec();
+    for (name, ultra_crucible) in [("crucible", false), ("ultra_crucible", true)] {
+        let path = min_heat_loss_path(&heat_loss_map, ultra_crucible);
+        insta::assert_snapshot!(name, render(&path.states, heat_loss_map.clone()));
+    }
+}
@@ -4,19 +4,19 @@ use crate::d19::{
     XmasProperty::{A, M, S, X},
 };
 use itertools::Itertools;
-use std::{collections::HashMap, ops::RangeInclusive};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+};
+use workflows::Workflows;
+
+mod workflows;
 
 pub fn solve(problem: &str) -> (usize, usize) {
     let (sorters_input, xmas_input) = problem.split("\n\n").collect_tuple().unwrap();
-    let sorters = sorters_input
-        .lines()
-        .map(|process| {
-            let (label, criteria) = process.split("{").collect_tuple().unwrap();
-            let criteria = &criteria[0..criteria.len() - 1];
-            let sorter = Sorter::parse(criteria);
-            (label, sorter)
-        })
-        .collect::<HashMap<_, _>>();
+    // every part is rated 1 to 4000, so rules and workflows no such part reaches can go
+    let ratings = XmasRange::new(1..=4000, 1..=4000, 1..=4000, 1..=4000);
+    let workflows = Workflows::parse(sorters_input).unwrap().simplify(&ratings);
 
     let xmas_structs = xmas_input
         .lines()
@@ -26,96 +26,49 @@ pub fn solve(problem: &str) -> (usize, usize) {
         })
         .collect_vec();
 
-    (solve1(&sorters, &xmas_structs), solve2(&sorters))
+    (
+        solve1(&workflows, &xmas_structs),
+        solve2(&workflows, &ratings),
+    )
 }
 
-fn solve1(sorters: &HashMap<&str, Sorter>, xmas_structs: &[Xmas]) -> usize {
-    let first_sorter = &sorters["in"];
+fn solve1(workflows: &Workflows, xmas_structs: &[Xmas]) -> usize {
     xmas_structs
         .iter()
-        .filter_map(|xmas| match first_sorter.sort(xmas, sorters) {
-            Accepted => Some(xmas.sum()),
-            Rejected => None,
-            ReSort(_) => unreachable!(),
-        })
+        .filter(|xmas| workflows.accepts(xmas))
+        .map(Xmas::sum)
         .sum()
 }
 
-fn solve2(sorters: &HashMap<&str, Sorter>) -> usize {
-    let first_sorter = &sorters["in"];
-    let start_range = XmasRange {
-        x: 1..=4000,
-        m: 1..=4000,
-        a: 1..=4000,
-        s: 1..=4000,
-    };
-
-    first_sorter
-        .sort_range(start_range, sorters)
-        .iter()
-        .filter_map(|(range, result)| match result {
-            Accepted => Some(range.num_combinations()),
-            Rejected => None,
-            ReSort(_) => unreachable!(),
-        })
-        .sum()
+fn solve2(workflows: &Workflows, ratings: &XmasRange) -> usize {
+    workflows.count_accepted(ratings)
 }
 
-struct Sorter<'a> {
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Sorter<'a> {
     checkers: Vec<Checker<'a>>,
 }
 
 impl<'a> Sorter<'a> {
-    fn parse(input: &'a str) -> Self {
-        let checkers = input.split(",").map(Checker::parse).collect_vec();
-        Self { checkers }
+    pub(crate) fn parse(input: &'a str) -> Result<Self, ParseError<'a>> {
+        let checkers = input.split(",").map(Checker::parse).try_collect()?;
+        Ok(Self { checkers })
     }
+}
 
-    fn sort(&'a self, xmas: &Xmas, sorters: &'a HashMap<&'a str, Sorter>) -> SortResult {
-        let sort_result = self
-            .checkers
-            .iter()
-            .filter_map(|checker| checker.check(xmas))
-            .next()
-            .unwrap();
-        if let ReSort(label) = &sort_result {
-            let new_sorter = &sorters[label];
-            new_sorter.sort(xmas, sorters)
-        } else {
-            sort_result
-        }
-    }
-
-    fn sort_range(
-        &'a self,
-        xmas_range: XmasRange,
-        sorters: &'a HashMap<&'a str, Sorter>,
-    ) -> Vec<(XmasRange, SortResult)> {
-        let mut prev_range = xmas_range;
-        let mut range_check_results = Vec::new();
-        for checker in &self.checkers {
-            let (maybe_pass_check, maybe_skip_check) = checker.check_range(&prev_range);
-
-            if let Some((range, sort_result)) = maybe_pass_check {
-                if let ReSort(label) = &sort_result {
-                    let new_sorter = &sorters[label];
-                    let mut sub_results = new_sorter.sort_range(range, sorters);
-                    range_check_results.append(&mut sub_results);
-                } else {
-                    range_check_results.push((range, sort_result));
-                }
-            }
-
-            match maybe_skip_check {
-                None => break,
-                Some(range) => prev_range = range,
-            }
-        }
-
-        range_check_results
-    }
+#[derive(Debug, PartialEq)]
+pub(crate) enum ParseError<'a> {
+    UnknownProperty(&'a str),
+    UnknownComparison(&'a str),
+    InvalidValue(&'a str),
+    InvalidLabel(&'a str),
+    /// A line of the workflow list that isn't `label{rules}`.
+    InvalidWorkflow(&'a str),
+    /// A rule sends parts to a workflow that isn't defined.
+    UndefinedWorkflow(&'a str),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum XmasProperty {
     X,
     M,
@@ -124,17 +77,18 @@ enum XmasProperty {
 }
 
 impl XmasProperty {
-    fn parse(input: &str) -> Self {
+    fn parse(input: &str) -> Result<Self, ParseError> {
         match input {
-            "x" => X,
-            "m" => M,
-            "a" => A,
-            "s" => S,
-            _ => unreachable!(),
+            "x" => Ok(X),
+            "m" => Ok(M),
+            "a" => Ok(A),
+            "s" => Ok(S),
+            _ => Err(ParseError::UnknownProperty(input)),
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 enum Checker<'a> {
     LessThan {
         prop: XmasProperty,
@@ -150,31 +104,32 @@ enum Checker<'a> {
 }
 
 impl<'a> Checker<'a> {
-    fn parse(input: &'a str) -> Self {
-        if !input.contains(":") {
-            return Pass(SortResult::parse(input));
-        } else {
-            let (cmp, sort_result) = input.split(":").collect_tuple().unwrap();
-            let prop = XmasProperty::parse(&cmp[0..1]);
-            let value = cmp[2..].parse().unwrap();
-            let sort_result = SortResult::parse(sort_result);
-            match &cmp[1..2] {
-                "<" => LessThan {
-                    prop,
-                    value,
-                    sort_result,
-                },
-                ">" => GreaterThan {
-                    prop,
-                    value,
-                    sort_result,
-                },
-                _ => unreachable!(),
-            }
+    fn parse(input: &'a str) -> Result<Self, ParseError<'a>> {
+        let Some((cmp, sort_result)) = input.split_once(":") else {
+            return Ok(Pass(SortResult::parse(input)?));
+        };
+        let sort_result = SortResult::parse(sort_result)?;
+        let prop = XmasProperty::parse(cmp.get(0..1).ok_or(ParseError::UnknownProperty(cmp))?)?;
+        let comparison = cmp.get(1..2).ok_or(ParseError::UnknownComparison(cmp))?;
+        let value = cmp[2..]
+            .parse()
+            .map_err(|_| ParseError::InvalidValue(cmp))?;
+        match comparison {
+            "<" => Ok(LessThan {
+                prop,
+                value,
+                sort_result,
+            }),
+            ">" => Ok(GreaterThan {
+                prop,
+                value,
+                sort_result,
+            }),
+            _ => Err(ParseError::UnknownComparison(cmp)),
         }
     }
 
-    fn check(&self, xmas: &Xmas) -> Option<SortResult> {
+    fn check(&self, xmas: &Xmas) -> Option<SortResult<'a>> {
         match self {
             LessThan {
                 prop,
@@ -205,7 +160,7 @@ impl<'a> Checker<'a> {
     fn check_range(
         &self,
         xmas_range: &XmasRange,
-    ) -> (Option<(XmasRange, SortResult)>, Option<XmasRange>) {
+    ) -> (Option<(XmasRange, SortResult<'a>)>, Option<XmasRange>) {
         match self {
             LessThan {
                 prop,
@@ -222,7 +177,7 @@ impl<'a> Checker<'a> {
                     let matched = Some((xmas_range.clone(), sort_result.clone()));
                     let leftover = None;
                     (matched, leftover)
-                } else if start > value {
+                } else if start >= value {
                     let matched = None;
                     let leftover = Some(xmas_range.clone());
                     (matched, leftover)
@@ -253,7 +208,7 @@ impl<'a> Checker<'a> {
                     let matched = Some((xmas_range.clone(), sort_result.clone()));
                     let leftover = None;
                     (matched, leftover)
-                } else if end < value {
+                } else if end <= value {
                     let matched = None;
                     let leftover = Some(xmas_range.clone());
                     (matched, leftover)
@@ -274,8 +229,9 @@ impl<'a> Checker<'a> {
     }
 }
 
-#[derive(Clone)]
-struct XmasRange {
+/// Every part whose ratings all fall within the given ranges.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct XmasRange {
     x: RangeInclusive<usize>,
     m: RangeInclusive<usize>,
     a: RangeInclusive<usize>,
@@ -283,6 +239,21 @@ struct XmasRange {
 }
 
 impl XmasRange {
+    pub(crate) fn new(
+        x: RangeInclusive<usize>,
+        m: RangeInclusive<usize>,
+        a: RangeInclusive<usize>,
+        s: RangeInclusive<usize>,
+    ) -> Self {
+        Self { x, m, a, s }
+    }
+
+    fn is_empty(&self) -> bool {
+        [&self.x, &self.m, &self.a, &self.s]
+            .iter()
+            .any(|range| range.is_empty())
+    }
+
     fn num_combinations(&self) -> usize {
         range_len(&self.x) * range_len(&self.m) * range_len(&self.a) * range_len(&self.s)
     }
@@ -307,10 +278,10 @@ impl XmasRange {
 }
 
 fn range_len(range: &RangeInclusive<usize>) -> usize {
-    range.end() - range.start() + 1
+    (range.end() + 1).saturating_sub(*range.start())
 }
 
-struct Xmas {
+pub(crate) struct Xmas {
     x: usize,
     m: usize,
     a: usize,
@@ -341,7 +312,7 @@ impl Xmas {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 enum SortResult<'a> {
     Accepted,
     Rejected,
@@ -349,11 +320,60 @@ enum SortResult<'a> {
 }
 
 impl<'a> SortResult<'a> {
-    fn parse(input: &'a str) -> Self {
+    fn parse(input: &'a str) -> Result<Self, ParseError<'a>> {
         match input {
-            "A" => Accepted,
-            "R" => Rejected,
-            _ => ReSort(input),
+            "A" => Ok(Accepted),
+            "R" => Ok(Rejected),
+            _ if !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                Ok(ReSort(input))
+            }
+            _ => Err(ParseError::InvalidLabel(input)),
+        }
+    }
+}
+
+impl Display for Sorter<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.checkers.iter().join(","))
+    }
+}
+
+impl Display for Checker<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LessThan {
+                prop,
+                value,
+                sort_result,
+            } => write!(f, "{prop}<{value}:{sort_result}"),
+            GreaterThan {
+                prop,
+                value,
+                sort_result,
+            } => write!(f, "{prop}>{value}:{sort_result}"),
+            Pass(sort_result) => write!(f, "{sort_result}"),
+        }
+    }
+}
+
+impl Display for XmasProperty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            X => "x",
+            M => "m",
+            A => "a",
+            S => "s",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Display for SortResult<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Accepted => write!(f, "A"),
+            Rejected => write!(f, "R"),
+            ReSort(label) => write!(f, "{label}"),
         }
     }
 }
@@ -364,3 +384,21 @@ fn test() {
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_sorter_round_trip() {
+    let input = "a<2006:qkq,m>2090:A,rfg";
+    let sorter = Sorter::parse(input).unwrap();
+    assert_eq!(input, sorter.to_string());
+
+    assert_eq!(
+        Err(ParseError::UnknownProperty("q")),
+        Sorter::parse("q<5:A,R")
+    );
+    assert_eq!(
+        Err(ParseError::UnknownComparison("a=5")),
+        Sorter::parse("a=5:A,R")
+    );
+    assert_eq!(Err(ParseError::InvalidValue("a<")), Sorter::parse("a<:A,R"));
+    assert_eq!(Err(ParseError::InvalidLabel("")), Sorter::parse("a<5:A,"));
+}
@@ -0,0 +1,381 @@
+use super::{
+    Checker::{self, GreaterThan, LessThan, Pass},
+    ParseError,
+    SortResult::{self, Accepted, ReSort, Rejected},
+    Sorter, Xmas, XmasRange,
+};
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+};
+
+/// A rule, by the workflow it is in and its position there.
+pub(crate) type RuleId<'a> = (&'a str, usize);
+
+/// Every workflow by name. Parts start at `in` and follow rules until accepted or rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Workflows<'a> {
+    /// Workflow names in the order they were listed.
+    order: Vec<&'a str>,
+    sorters: HashMap<&'a str, Sorter<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Report<'a> {
+    /// Rules in live workflows that no part in the queried range is ever sorted by.
+    pub(crate) unreachable_rules: Vec<RuleId<'a>>,
+    /// Workflows that no part in the queried range is ever sent to.
+    pub(crate) dead_workflows: Vec<&'a str>,
+}
+
+fn outcome<'a, 'b>(checker: &'b Checker<'a>) -> &'b SortResult<'a> {
+    match checker {
+        LessThan { sort_result, .. } | GreaterThan { sort_result, .. } | Pass(sort_result) => {
+            sort_result
+        }
+    }
+}
+
+fn outcome_mut<'a, 'b>(checker: &'b mut Checker<'a>) -> &'b mut SortResult<'a> {
+    match checker {
+        LessThan { sort_result, .. } | GreaterThan { sort_result, .. } | Pass(sort_result) => {
+            sort_result
+        }
+    }
+}
+
+impl<'a> Workflows<'a> {
+    /// Parses lines like `px{a<2006:qkq,m>2090:A,rfg}`. Every workflow has to end with a rule
+    /// that always applies, and only send parts to workflows that exist.
+    pub(crate) fn parse(input: &'a str) -> Result<Self, ParseError<'a>> {
+        let mut order = vec![];
+        let mut sorters = HashMap::new();
+        for line in input.lines() {
+            let Some((label, rules)) = line.strip_suffix('}').and_then(|line| line.split_once('{'))
+            else {
+                return Err(ParseError::InvalidWorkflow(line));
+            };
+            let sorter = Sorter::parse(rules)?;
+            if !matches!(sorter.checkers.last(), Some(Pass(_))) {
+                return Err(ParseError::InvalidWorkflow(line));
+            }
+            order.push(label);
+            sorters.insert(label, sorter);
+        }
+
+        let targets = sorters
+            .values()
+            .flat_map(|sorter| &sorter.checkers)
+            .filter_map(|checker| match outcome(checker) {
+                ReSort(label) => Some(*label),
+                _ => None,
+            });
+        if let Some(undefined) = ["in"]
+            .into_iter()
+            .chain(targets)
+            .find(|label| !sorters.contains_key(label))
+        {
+            return Err(ParseError::UndefinedWorkflow(undefined));
+        }
+
+        Ok(Self { order, sorters })
+    }
+
+    pub(crate) fn accepts(&self, xmas: &Xmas) -> bool {
+        let mut label = "in";
+        loop {
+            let result = self.sorters[label]
+                .checkers
+                .iter()
+                .find_map(|checker| checker.check(xmas))
+                .unwrap();
+            match result {
+                Accepted => return true,
+                Rejected => return false,
+                ReSort(next) => label = next,
+            }
+        }
+    }
+
+    /// Splits `range` into pieces that each end up accepted or rejected as a whole, calling `fired`
+    /// with every rule and the piece it sorts along the way. The pieces never overlap.
+    fn propagate(
+        &self,
+        range: &XmasRange,
+        mut fired: impl FnMut(RuleId<'a>, &XmasRange),
+    ) -> Vec<(XmasRange, bool)> {
+        let mut outcomes = vec![];
+        let mut stack = vec![("in", range.clone())];
+        if range.is_empty() {
+            stack.clear();
+        }
+
+        while let Some((label, mut range)) = stack.pop() {
+            for (idx, checker) in self.sorters[label].checkers.iter().enumerate() {
+                let (matched, leftover) = checker.check_range(&range);
+                if let Some((piece, result)) = matched {
+                    fired((label, idx), &piece);
+                    match result {
+                        Accepted => outcomes.push((piece, true)),
+                        Rejected => outcomes.push((piece, false)),
+                        ReSort(next) => stack.push((next, piece)),
+                    }
+                }
+                match leftover {
+                    Some(rest) => range = rest,
+                    None => break,
+                }
+            }
+        }
+        outcomes
+    }
+
+    /// How many parts with ratings inside `range` are accepted.
+    pub(crate) fn count_accepted(&self, range: &XmasRange) -> usize {
+        self.accepted_boxes(range)
+            .iter()
+            .map(XmasRange::num_combinations)
+            .sum()
+    }
+
+    /// The accepted parts of `range`, as boxes that don't overlap.
+    pub(crate) fn accepted_boxes(&self, range: &XmasRange) -> Vec<XmasRange> {
+        self.propagate(range, |_, _| {})
+            .into_iter()
+            .filter_map(|(piece, accepted)| accepted.then_some(piece))
+            .collect()
+    }
+
+    /// Which rules and workflows never see a part from `range`.
+    pub(crate) fn analyze(&self, range: &XmasRange) -> Report<'a> {
+        let mut fired = HashSet::new();
+        self.propagate(range, |rule, _| {
+            fired.insert(rule);
+        });
+
+        // anything entering a workflow is sorted by one of its rules
+        let (live, dead_workflows): (Vec<_>, Vec<_>) = self
+            .order
+            .iter()
+            .partition(|label| fired.iter().any(|(fired_label, _)| fired_label == *label));
+        let unreachable_rules = live
+            .into_iter()
+            .flat_map(|label| (0..self.sorters[label].checkers.len()).map(move |idx| (label, idx)))
+            .filter(|rule| !fired.contains(rule))
+            .collect();
+
+        Report {
+            unreachable_rules,
+            dead_workflows,
+        }
+    }
+
+    /// An equivalent set of workflows, as far as parts in `range` are concerned, with dead
+    /// workflows and unreachable rules removed, conditions that end up where the fallback would
+    /// anyway dropped, and workflows that always give the same result inlined.
+    pub(crate) fn simplify(&self, range: &XmasRange) -> Self {
+        let mut workflows = self.clone();
+        loop {
+            let report = workflows.analyze(range);
+            let mut changed = false;
+
+            for (label, idx) in report.unreachable_rules.iter().rev() {
+                let checkers = &mut workflows.sorters.get_mut(label).unwrap().checkers;
+                checkers.remove(*idx);
+                changed = true;
+            }
+            for sorter in workflows.sorters.values_mut() {
+                // the fallback never firing means nothing gets past the last rule left
+                if let Some(last) = sorter.checkers.pop() {
+                    sorter.checkers.push(Pass(outcome(&last).clone()));
+                }
+            }
+            for label in report.dead_workflows {
+                if label != "in" {
+                    workflows.order.retain(|other| *other != label);
+                    workflows.sorters.remove(label);
+                    changed = true;
+                }
+            }
+
+            for sorter in workflows.sorters.values_mut() {
+                let checkers = &mut sorter.checkers;
+                while checkers.len() >= 2
+                    && outcome(&checkers[checkers.len() - 2])
+                        == outcome(&checkers[checkers.len() - 1])
+                {
+                    checkers.remove(checkers.len() - 2);
+                    changed = true;
+                }
+            }
+
+            let constant = workflows
+                .sorters
+                .iter()
+                .filter(|(label, sorter)| **label != "in" && sorter.checkers.len() == 1)
+                .map(|(label, sorter)| (*label, outcome(&sorter.checkers[0]).clone()))
+                .collect::<HashMap<_, _>>();
+            for checker in workflows
+                .sorters
+                .values_mut()
+                .flat_map(|sorter| &mut sorter.checkers)
+            {
+                let result = outcome_mut(checker);
+                if let ReSort(label) = result {
+                    if let Some(constant) = constant.get(label) {
+                        *result = constant.clone();
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                return workflows;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn num_rules(&self) -> usize {
+        self.sorters
+            .values()
+            .map(|sorter| sorter.checkers.len())
+            .sum()
+    }
+}
+
+impl Display for Workflows<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let lines = self
+            .order
+            .iter()
+            .map(|label| format!("{label}{{{}}}", self.sorters[label]));
+        write!(f, "{}", lines.format("\n"))
+    }
+}
+
+#[cfg(test)]
+fn example_workflows() -> String {
+    let example = &crate::utils::parse_example_testcases(include_str!("example.txt"))[0];
+    example.problem.split("\n\n").next().unwrap().to_string()
+}
+
+#[cfg(test)]
+fn full_range() -> XmasRange {
+    XmasRange::new(1..=4000, 1..=4000, 1..=4000, 1..=4000)
+}
+
+#[test]
+fn test_count_arbitrary_ranges() {
+    let input = example_workflows();
+    let workflows = Workflows::parse(&input).unwrap();
+    assert_eq!(167409079868000, workflows.count_accepted(&full_range()));
+
+    // straddles a threshold of nearly every rule in the example
+    let range = XmasRange::new(1410..=1420, 1545..=1552, 2000..=2010, 1345..=1355);
+    let brute_force = range
+        .x
+        .clone()
+        .cartesian_product(range.m.clone())
+        .cartesian_product(range.a.clone())
+        .cartesian_product(range.s.clone())
+        .filter(|(((x, m), a), s)| {
+            workflows.accepts(&Xmas {
+                x: *x,
+                m: *m,
+                a: *a,
+                s: *s,
+            })
+        })
+        .count();
+    assert_eq!(brute_force, workflows.count_accepted(&range));
+
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = XmasRange::new(1..=4000, 5..=4, 1..=4000, 1..=4000);
+    assert_eq!(0, workflows.count_accepted(&empty));
+}
+
+#[test]
+fn test_accepted_boxes_are_disjoint() {
+    let input = example_workflows();
+    let workflows = Workflows::parse(&input).unwrap();
+    let boxes = workflows.accepted_boxes(&full_range());
+
+    let overlaps = |lhs: &XmasRange, rhs: &XmasRange| {
+        [
+            (&lhs.x, &rhs.x),
+            (&lhs.m, &rhs.m),
+            (&lhs.a, &rhs.a),
+            (&lhs.s, &rhs.s),
+        ]
+        .iter()
+        .all(|(lhs, rhs)| lhs.start() <= rhs.end() && rhs.start() <= lhs.end())
+    };
+    for (lhs, rhs) in boxes.iter().tuple_combinations() {
+        assert!(!overlaps(lhs, rhs), "{lhs:?} overlaps {rhs:?}");
+    }
+    assert_eq!(
+        167409079868000,
+        boxes.iter().map(XmasRange::num_combinations).sum::<usize>()
+    );
+}
+
+#[test]
+fn test_report_and_simplify() {
+    let workflows = Workflows::parse(
+        "in{x<10:a,x<5:R,c}\n\
+         a{m>3:A,m>5:R,R}\n\
+         b{A}\n\
+         c{s<5:R,R}",
+    )
+    .unwrap();
+
+    assert_eq!(
+        Report {
+            unreachable_rules: vec![("in", 1), ("a", 1)],
+            dead_workflows: vec!["b"],
+        },
+        workflows.analyze(&full_range())
+    );
+    let simplified = workflows.simplify(&full_range());
+    assert_eq!("in{x<10:a,R}\na{m>3:A,R}", simplified.to_string());
+
+    // with x always below 10, everything goes through a
+    let range = XmasRange::new(1..=9, 1..=4000, 1..=4000, 1..=4000);
+    assert_eq!("in{a}\na{m>3:A,R}", workflows.simplify(&range).to_string());
+}
+
+#[test]
+fn test_simplify_example() {
+    let input = example_workflows();
+    let workflows = Workflows::parse(&input).unwrap();
+    let simplified = workflows.simplify(&full_range());
+
+    assert!(simplified.num_rules() < workflows.num_rules());
+    assert_eq!(
+        workflows.count_accepted(&full_range()),
+        simplified.count_accepted(&full_range())
+    );
+    insta::assert_snapshot!(simplified.to_string());
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        Err(ParseError::UndefinedWorkflow("zz")),
+        Workflows::parse("in{x<5:zz,A}")
+    );
+    assert_eq!(
+        Err(ParseError::UndefinedWorkflow("in")),
+        Workflows::parse("px{A}")
+    );
+    assert_eq!(
+        Err(ParseError::InvalidWorkflow("in{x<5:A}")),
+        Workflows::parse("in{x<5:A}")
+    );
+    assert_eq!(
+        Err(ParseError::InvalidWorkflow("in x<5:A")),
+        Workflows::parse("in x<5:A")
+    );
+}
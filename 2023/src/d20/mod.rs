@@ -1,176 +1,72 @@
-use crate::d20::ModuleType::{Broadcaster, Conjunction, FlipFlop};
-use itertools::Itertools;
-use num::integer::lcm;
-use std::collections::{HashMap, VecDeque};
+use network::{Network, Pulse};
 
-pub fn solve(problem: &str) -> (usize, usize) {
-    let mut modules = problem
-        .lines()
-        .map(|line| {
-            let (parent, children) = line.split(" -> ").collect_tuple().unwrap();
-            let children = children.split(", ").collect_vec();
-
-            let (label, module_type) = match &parent[0..1] {
-                "%" => (&parent[1..], FlipFlop { state: false }),
-                "&" => (
-                    &parent[1..],
-                    Conjunction {
-                        state: HashMap::new(),
-                    },
-                ),
-                _ => (parent, Broadcaster),
-            };
+mod network;
 
-            (
-                label,
-                Module {
-                    module_type,
-                    children,
-                },
-            )
-        })
-        .collect::<HashMap<_, _>>();
+pub fn solve(problem: &str) -> (usize, usize) {
+    let network = Network::parse(problem);
+    (solve1(network.clone()), solve2(&network))
+}
 
-    let keys = modules.keys().cloned().collect_vec();
+/// Prints the module network as a Graphviz digraph.
+pub fn print_dot(problem: &str) {
+    print!("{}", Network::parse(problem).to_dot());
+}
 
-    for parent in keys {
-        let children = modules[parent].children.clone();
-        for child in children {
-            if let Some(Module {
-                module_type: Conjunction { state },
-                ..
-            }) = modules.get_mut(child)
-            {
-                state.insert(parent, false);
-            }
+/// Prints every pulse delivered on each of the first `presses` presses of the button.
+pub fn print_trace(problem: &str, presses: usize) {
+    let mut network = Network::parse(problem);
+    for press in 1..=presses {
+        println!("press {press}:");
+        for signal in network.press() {
+            println!("{signal}");
         }
     }
-    (solve1(modules.clone()), solve2(&modules))
 }
 
-fn solve1(mut modules: HashMap<&str, Module>) -> usize {
-    let mut pulses_count = HashMap::from([(false, 0_usize), (true, 0_usize)]);
-
-    for _ in 0..1000 {
-        let mut queue = VecDeque::from([("button", "broadcaster", false)]);
-        while let Some((source, target, signal)) = queue.pop_front() {
-            if let Some(count) = pulses_count.get_mut(&signal) {
-                *count += 1;
-            }
-
-            let Some(target_module) = modules.get_mut(target) else {
-                continue;
-            };
-
-            let new_value = match &mut target_module.module_type {
-                Broadcaster => signal,
-                FlipFlop { state } if !signal => {
-                    *state = !*state;
-                    *state
-                }
-                Conjunction { state } => {
-                    let old_signal = state.get_mut(source).unwrap();
-                    *old_signal = signal;
-                    !state.values().all(|signal| *signal)
-                }
-                _ => continue,
-            };
-
-            let new_source = target;
-            for new_target in &target_module.children {
-                queue.push_back((new_source, *new_target, new_value));
-            }
+/// Prints when the whole network starts repeating, then the same for each group of modules that
+/// only the broadcaster feeds.
+pub fn print_periods(problem: &str) {
+    let network = Network::parse(problem);
+    let describe = |modules: &[&str]| match network.period(modules) {
+        Ok(Some(period)) => format!("period {} from press {}", period.length, period.start),
+        Ok(None) => String::from("doesn't repeat within the simulation budget"),
+        Err(error) => error.to_string(),
+    };
+    println!("network: {}", describe(network.modules()));
+    for modules in network.independent_subgraphs() {
+        // a destination that others also reach has no modules to itself
+        if modules.is_empty() {
+            continue;
         }
+        println!("{}: {}", modules.join(", "), describe(&modules));
     }
-
-    pulses_count.values().product()
 }
 
-fn solve2(modules: &HashMap<&str, Module>) -> usize {
-    let rx_parent = modules
-        .iter()
-        .find_map(|(id, module)| {
-            if module.children.contains(&"rx") {
-                Some(id)
-            } else {
-                None
-            }
-        })
-        .unwrap();
-
-    let rx_parent_inputs = modules
-        .iter()
-        .filter_map(|(id, module)| {
-            if module.children.contains(rx_parent) {
-                Some(id)
-            } else {
-                None
-            }
-        })
-        .collect_vec();
-
-    rx_parent_inputs
-        .into_iter()
-        .map(|input| least_steps_to_reach(input, rx_parent, true, modules.clone()) + 1)
-        .reduce(lcm)
-        .unwrap()
-}
-
-fn least_steps_to_reach(
-    des_source: &str,
-    des_target: &str,
-    des_signal: bool,
-    mut modules: HashMap<&str, Module>,
-) -> usize {
-    for i in 0_usize.. {
-        let mut queue = vec![("button", "broadcaster", false)];
-        while !queue.is_empty() {
-            let mut next_queue = vec![];
-            for (source, target, signal) in queue {
-                if source == des_source && target == des_target && signal == des_signal {
-                    return i;
-                }
-
-                let Some(target_module) = modules.get_mut(target) else {
-                    continue;
-                };
-
-                let new_value = match &mut target_module.module_type {
-                    Broadcaster => signal,
-                    FlipFlop { state } if !signal => {
-                        *state = !*state;
-                        *state
-                    }
-                    Conjunction { state } => {
-                        let old_signal = state.get_mut(source).unwrap();
-                        *old_signal = signal;
-                        !state.values().all(|signal| *signal)
-                    }
-                    _ => continue,
-                };
-
-                let new_source = target;
-                for new_target in &target_module.children {
-                    next_queue.push((new_source, *new_target, new_value));
-                }
-            }
-            queue = next_queue;
-        }
+fn solve1(mut network: Network) -> usize {
+    let (mut low, mut high) = (0, 0);
+    for _ in 0..1000 {
+        network.press_with(|_, pulse, _| match pulse {
+            Pulse::Low => low += 1,
+            Pulse::High => high += 1,
+        });
     }
-    unreachable!()
+    low * high
 }
 
-#[derive(Debug, Clone)]
-struct Module<'a> {
-    module_type: ModuleType<'a>,
-    children: Vec<&'a str>,
+fn solve2(network: &Network) -> usize {
+    // the examples have no rx, and give 0 as their part 2 answer
+    match network.first_press_receiving("rx", Pulse::Low) {
+        Ok(press) => press.unwrap(),
+        Err(network::NetworkError::UnknownModule(_)) => 0,
+        Err(error) => panic!("{error}"),
+    }
 }
 
-#[derive(Debug, Clone)]
-enum ModuleType<'a> {
-    Broadcaster,
-    FlipFlop { state: bool },
-    Conjunction { state: HashMap<&'a str, bool> },
+#[test]
+fn test() {
+    use crate::utils::basic_test;
+    let input = include_str!("example.txt");
+    basic_test(input, solve);
 }
 
 /*
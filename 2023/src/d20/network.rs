@@ -0,0 +1,558 @@
+use advent_common::{
+    cycle::{find_cycle_within, Period},
+    graph::{Edges, Graph},
+    number::Schedule,
+};
+use itertools::Itertools;
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    fmt::Write,
+};
+
+/// How many presses to simulate while waiting for part of the network to repeat a state.
+const SIMULATION_BUDGET: usize = 1 << 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pulse {
+    Low,
+    High,
+}
+
+impl fmt::Display for Pulse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pulse::Low => write!(f, "low"),
+            Pulse::High => write!(f, "high"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Button,
+    Broadcaster,
+    FlipFlop,
+    Conjunction,
+    /// Anything that is only ever sent to, like `rx`.
+    Output,
+}
+
+/// One pulse delivered during a press, written the way the puzzle does, e.g. `a -high-> b`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal<'a> {
+    pub from: &'a str,
+    pub pulse: Pulse,
+    pub to: &'a str,
+}
+
+impl fmt::Display for Signal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -{}-> {}", self.from, self.pulse, self.to)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    UnknownModule(String),
+    /// The module neither repeats within the simulation budget nor splits into parts that do.
+    NoPeriod(String),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::UnknownModule(name) => write!(f, "no module named {name}"),
+            NetworkError::NoPeriod(name) => {
+                write!(f, "could not find a period for what {name} sends")
+            }
+        }
+    }
+}
+
+/// A module network that can be pressed, traced, and analysed for periodic behaviour.
+#[derive(Debug, Clone)]
+pub struct Network<'a> {
+    /// Which modules each module sends to.
+    children: Graph<'a>,
+    /// Which modules each module hears from, with the same ids.
+    parents: Graph<'a>,
+    kinds: Vec<Kind>,
+    /// Whether each flip-flop is on.
+    on: Vec<bool>,
+    /// What each conjunction last heard from each of its parents, in the same order.
+    memory: Vec<Vec<Pulse>>,
+}
+
+impl<'a> Network<'a> {
+    pub fn parse(input: &'a str) -> Self {
+        let mut children = Graph::new(Edges::Directed);
+        let button = children.intern("button");
+        let mut declared = vec![(button, Kind::Button)];
+
+        for line in input.lines() {
+            let (parent, targets) = line.split(" -> ").collect_tuple().unwrap();
+            let (name, kind) = match &parent[0..1] {
+                "%" => (&parent[1..], Kind::FlipFlop),
+                "&" => (&parent[1..], Kind::Conjunction),
+                _ => (parent, Kind::Broadcaster),
+            };
+            declared.push((children.intern(name), kind));
+            for child in targets.split(", ") {
+                children.add_edge(name, child);
+            }
+        }
+        children.add_edge("button", "broadcaster");
+
+        let mut kinds = vec![Kind::Output; children.len()];
+        for (id, kind) in declared {
+            kinds[id] = kind;
+        }
+        let mut network = Network {
+            parents: children.reversed(),
+            children,
+            kinds,
+            on: vec![],
+            memory: vec![],
+        };
+        network.reset();
+        network
+    }
+
+    fn id(&self, name: &str) -> Result<usize, NetworkError> {
+        self.children
+            .id(name)
+            .ok_or_else(|| NetworkError::UnknownModule(name.to_string()))
+    }
+
+    /// Turns every flip-flop off and makes every conjunction remember low pulses.
+    pub fn reset(&mut self) {
+        self.on = vec![false; self.children.len()];
+        self.memory = self
+            .parents
+            .nodes()
+            .map(|module| vec![Pulse::Low; self.parents.neighbours(module).len()])
+            .collect();
+    }
+
+    /// Presses the button and handles pulses until none are left, calling `observe` with the
+    /// sender, pulse and receiver of each one as it is delivered.
+    pub fn press_with(&mut self, mut observe: impl FnMut(usize, Pulse, usize)) {
+        let button = self.children.id("button").unwrap();
+        let mut queue = VecDeque::from([(button, Pulse::Low, self.children.neighbours(button)[0])]);
+        while let Some((from, pulse, to)) = queue.pop_front() {
+            observe(from, pulse, to);
+            let sent = match (self.kinds[to], pulse) {
+                (Kind::Broadcaster, _) => pulse,
+                (Kind::FlipFlop, Pulse::Low) => {
+                    self.on[to] = !self.on[to];
+                    if self.on[to] {
+                        Pulse::High
+                    } else {
+                        Pulse::Low
+                    }
+                }
+                (Kind::Conjunction, _) => {
+                    let input = self
+                        .parents
+                        .neighbours(to)
+                        .iter()
+                        .position(|p| *p == from)
+                        .unwrap();
+                    self.memory[to][input] = pulse;
+                    if self.memory[to].iter().all(|pulse| *pulse == Pulse::High) {
+                        Pulse::Low
+                    } else {
+                        Pulse::High
+                    }
+                }
+                _ => continue,
+            };
+            for child in self.children.neighbours(to) {
+                queue.push_back((to, sent, *child));
+            }
+        }
+    }
+
+    /// Every module's name, starting with the button.
+    pub fn modules(&self) -> &[&'a str] {
+        self.children.labels()
+    }
+
+    /// Presses the button and returns every pulse delivered, in order.
+    pub fn press(&mut self) -> Vec<Signal<'a>> {
+        let mut trace = vec![];
+        let names = self.children.labels().to_vec();
+        self.press_with(|from, pulse, to| {
+            trace.push(Signal {
+                from: names[from],
+                pulse,
+                to: names[to],
+            })
+        });
+        trace
+    }
+
+    /// Everything that can change what the given modules see: the modules themselves and every
+    /// module with a path to them.
+    fn upstream(&self, modules: &[usize]) -> Vec<usize> {
+        let mut seen = modules.iter().copied().collect::<HashSet<_>>();
+        let mut stack = modules.to_vec();
+        while let Some(module) = stack.pop() {
+            for parent in self.parents.neighbours(module) {
+                if seen.insert(*parent) {
+                    stack.push(*parent);
+                }
+            }
+        }
+        seen.into_iter().sorted().collect()
+    }
+
+    fn state(&self, modules: &[usize]) -> Vec<bool> {
+        modules
+            .iter()
+            .flat_map(|module| match self.kinds[*module] {
+                Kind::FlipFlop => vec![self.on[*module]],
+                Kind::Conjunction => self.memory[*module]
+                    .iter()
+                    .map(|pulse| *pulse == Pulse::High)
+                    .collect(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// Finds when the state of `watched` starts repeating, then presses a copy of the network
+    /// through the first time round, noting the presses on which `hit` matches a delivered pulse.
+    fn simulate(
+        &self,
+        watched: &[usize],
+        hit: impl Fn(usize, Pulse, usize) -> bool,
+        budget: usize,
+    ) -> Option<Schedule> {
+        let period = self.period_of(watched, budget)?;
+        let mut network = self.clone();
+        let mut hits = vec![];
+        for press in 1..=period.start + period.length {
+            let mut was_hit = false;
+            network.press_with(|from, pulse, to| was_hit |= hit(from, pulse, to));
+            if was_hit {
+                hits.push(press);
+            }
+        }
+        // presses are counted from 1, so the state after `period.start` presses first repeats
+        // after the next one
+        Some(Schedule::new(hits, period.start + 1, period.length))
+    }
+
+    fn period_of(&self, watched: &[usize], budget: usize) -> Option<Period> {
+        find_cycle_within(
+            self,
+            |network| network.press_with(|_, _, _| ()),
+            |network| network.state(watched),
+            budget,
+        )
+    }
+
+    /// When the flip-flops and conjunction memories of `modules` start repeating, if they do
+    /// within the simulation budget.
+    pub fn period(&self, modules: &[&str]) -> Result<Option<Period>, NetworkError> {
+        let modules = modules
+            .iter()
+            .map(|name| self.id(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.period_of(&modules, SIMULATION_BUDGET))
+    }
+
+    /// For each of the broadcaster's destinations, the modules it can reach that none of the
+    /// others can. Only the broadcaster feeds into these, so each group runs on its own.
+    pub fn independent_subgraphs(&self) -> Vec<Vec<&'a str>> {
+        let broadcaster = self.children.id("broadcaster").unwrap();
+        let reachable = self
+            .children
+            .neighbours(broadcaster)
+            .iter()
+            .map(|start| {
+                let mut seen = HashSet::from([*start]);
+                let mut stack = vec![*start];
+                while let Some(module) = stack.pop() {
+                    for child in self.children.neighbours(module) {
+                        if seen.insert(*child) {
+                            stack.push(*child);
+                        }
+                    }
+                }
+                seen
+            })
+            .collect_vec();
+
+        reachable
+            .iter()
+            .enumerate()
+            .map(|(idx, modules)| {
+                modules
+                    .iter()
+                    .filter(|module| {
+                        reachable
+                            .iter()
+                            .enumerate()
+                            .all(|(other, seen)| other == idx || !seen.contains(module))
+                    })
+                    .map(|module| self.children.label(*module))
+                    .sorted()
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The first press, counting from the current state, on which `target` is sent `pulse`, or
+    /// `None` if that never happens.
+    ///
+    /// If everything upstream of `target` repeats within the simulation budget this is found
+    /// directly. Otherwise each sender is worked out separately, and a conjunction sending low is
+    /// split into the presses on which all of its inputs send high. That assumes those high
+    /// pulses all arrive before any of the inputs goes low again within the press.
+    pub fn first_press_receiving(
+        &self,
+        target: &str,
+        pulse: Pulse,
+    ) -> Result<Option<usize>, NetworkError> {
+        self.first_press_receiving_within(target, pulse, SIMULATION_BUDGET)
+    }
+
+    fn first_press_receiving_within(
+        &self,
+        target: &str,
+        pulse: Pulse,
+        budget: usize,
+    ) -> Result<Option<usize>, NetworkError> {
+        let target = self.id(target)?;
+        let upstream = self.upstream(self.parents.neighbours(target));
+        let receives = |_, sent, to| to == target && sent == pulse;
+        if let Some(schedule) = self.simulate(&upstream, receives, budget) {
+            return Ok(schedule.first());
+        }
+
+        let mut first = None;
+        for parent in self.parents.neighbours(target) {
+            let schedule = self.sending_schedule(*parent, pulse, budget)?;
+            first = first.into_iter().chain(schedule.first()).min();
+        }
+        Ok(first)
+    }
+
+    fn sending_schedule(
+        &self,
+        module: usize,
+        pulse: Pulse,
+        budget: usize,
+    ) -> Result<Schedule, NetworkError> {
+        let upstream = self.upstream(&[module]);
+        let sends = |from, sent, _| from == module && sent == pulse;
+        if let Some(schedule) = self.simulate(&upstream, sends, budget) {
+            return Ok(schedule);
+        }
+
+        match (self.kinds[module], pulse) {
+            (Kind::Conjunction, Pulse::Low) => self
+                .parents
+                .neighbours(module)
+                .iter()
+                .map(|parent| self.sending_schedule(*parent, Pulse::High, budget))
+                .reduce(|lhs, rhs| Ok(lhs?.intersect(&rhs?)))
+                .unwrap(),
+            _ => Err(NetworkError::NoPeriod(
+                self.children.label(module).to_string(),
+            )),
+        }
+    }
+
+    /// A Graphviz digraph of the network, with flip-flops and conjunctions drawn differently.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph network {\n");
+        for (id, name) in self.children.labels().iter().enumerate() {
+            let (label, shape) = match self.kinds[id] {
+                Kind::Button | Kind::Broadcaster => (name.to_string(), "box"),
+                Kind::FlipFlop => (format!("%{name}"), "ellipse"),
+                Kind::Conjunction => (format!("&{name}"), "diamond"),
+                Kind::Output => (name.to_string(), "doublecircle"),
+            };
+            writeln!(dot, "    {name} [label=\"{label}\", shape={shape}];").unwrap();
+        }
+        for (from, to) in self.children.edges() {
+            let (from, to) = (self.children.label(from), self.children.label(to));
+            writeln!(dot, "    {from} -> {to};").unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+fn examples() -> Vec<String> {
+    crate::utils::parse_example_testcases(include_str!("example.txt"))
+        .into_iter()
+        .map(|example| example.problem)
+        .collect()
+}
+
+/// Brute force for [`Network::first_press_receiving`].
+#[cfg(test)]
+fn first_press_by_pressing(
+    network: &Network,
+    target: &str,
+    pulse: Pulse,
+    presses: usize,
+) -> Option<usize> {
+    let mut network = network.clone();
+    (1..=presses).find(|_| {
+        network
+            .press()
+            .iter()
+            .any(|signal| signal.to == target && signal.pulse == pulse)
+    })
+}
+
+/// Counters like the ones in the puzzle input: flip-flops counting button presses in binary, and a
+/// hub that fires and resets them when they reach `target`.
+#[cfg(test)]
+fn counters(targets: &[usize]) -> String {
+    let mut lines = vec![format!(
+        "broadcaster -> {}",
+        (0..targets.len()).map(|c| format!("c{c}b0")).join(", ")
+    )];
+    for (c, target) in targets.iter().enumerate() {
+        let bits = usize::BITS - target.leading_zeros();
+        for bit in 0..bits {
+            let mut children = vec![];
+            if bit + 1 < bits {
+                children.push(format!("c{c}b{}", bit + 1));
+            }
+            if target >> bit & 1 == 1 {
+                children.push(format!("hub{c}"));
+            }
+            lines.push(format!("%c{c}b{bit} -> {}", children.join(", ")));
+        }
+        let resets = (0..bits)
+            .filter(|bit| *bit == 0 || target >> bit & 1 == 0)
+            .map(|bit| format!("c{c}b{bit}"));
+        lines.push(format!(
+            "&hub{c} -> {}",
+            [format!("inv{c}")].into_iter().chain(resets).join(", ")
+        ));
+        lines.push(format!("&inv{c} -> all"));
+    }
+    lines.push(String::from("&all -> rx"));
+    lines.join("\n")
+}
+
+#[test]
+fn test_press_trace() {
+    let examples = examples();
+    let mut network = Network::parse(&examples[0]);
+    assert_eq!(
+        "button -low-> broadcaster\n\
+         broadcaster -low-> a\n\
+         broadcaster -low-> b\n\
+         broadcaster -low-> c\n\
+         a -high-> b\n\
+         b -high-> c\n\
+         c -high-> inv\n\
+         inv -low-> a\n\
+         a -low-> b\n\
+         b -low-> c\n\
+         c -low-> inv\n\
+         inv -high-> a",
+        network.press().iter().join("\n")
+    );
+}
+
+#[test]
+fn test_periods() {
+    let examples = examples();
+    let network = Network::parse(&examples[0]);
+    assert_eq!(
+        Ok(Some(Period {
+            start: 0,
+            length: 1
+        })),
+        network.period(&["a", "b", "c", "inv"])
+    );
+
+    let network = Network::parse(&examples[1]);
+    assert_eq!(
+        Ok(Some(Period {
+            start: 0,
+            length: 4
+        })),
+        network.period(&["a", "b", "inv", "con"])
+    );
+
+    let network = Network::parse(&examples[1]);
+    assert_eq!(
+        Err(NetworkError::UnknownModule(String::from("d"))),
+        network.period(&["d"])
+    );
+}
+
+#[test]
+fn test_independent_subgraph_periods() {
+    let input = counters(&[11, 13]);
+    let network = Network::parse(&input);
+    let subgraphs = network.independent_subgraphs();
+
+    assert_eq!(
+        vec![
+            vec!["c0b0", "c0b1", "c0b2", "c0b3", "hub0", "inv0"],
+            vec!["c1b0", "c1b1", "c1b2", "c1b3", "hub1", "inv1"],
+        ],
+        subgraphs
+    );
+    let lengths = subgraphs
+        .iter()
+        .map(|modules| network.period(modules).unwrap().unwrap().length)
+        .collect_vec();
+    assert_eq!(vec![11, 13], lengths);
+}
+
+#[test]
+fn test_first_press_matches_pressing() {
+    for example in examples() {
+        let network = Network::parse(&example);
+        for target in network.children.labels().iter().skip(1) {
+            for pulse in [Pulse::Low, Pulse::High] {
+                assert_eq!(
+                    first_press_by_pressing(&network, target, pulse, 100),
+                    network.first_press_receiving(target, pulse).unwrap(),
+                    "{target} receiving {pulse}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_first_press_through_conjunction() {
+    let input = counters(&[11, 13, 7]);
+    let network = Network::parse(&input);
+
+    assert_eq!(
+        Some(11 * 13 * 7),
+        first_press_by_pressing(&network, "rx", Pulse::Low, 2000)
+    );
+    // too small a budget to simulate everything at once, which forces the split at `all`
+    assert_eq!(
+        Ok(Some(11 * 13 * 7)),
+        network.first_press_receiving_within("rx", Pulse::Low, 100)
+    );
+    assert_eq!(
+        Ok(Some(13)),
+        network.first_press_receiving_within("inv1", Pulse::Low, 100)
+    );
+}
+
+#[test]
+fn test_dot_snapshot() {
+    let examples = examples();
+    insta::assert_snapshot!(Network::parse(&examples[1]).to_dot());
+}
@@ -80,6 +80,18 @@ fn solve_base(
     height: usize,
     max_steps: usize,
 ) -> usize {
+    reachable(rock_coords, start, width, height, max_steps).len()
+}
+
+/// Every garden plot, including those in repeated copies of the map, that can be ended on after
+/// exactly `max_steps` steps.
+fn reachable(
+    rock_coords: &HashSet<(usize, usize)>,
+    start: (usize, usize),
+    width: usize,
+    height: usize,
+    max_steps: usize,
+) -> HashSet<(isize, isize)> {
     let rock_coords = rock_coords
         .iter()
         .map(|(row, col)| (*row as isize, *col as isize))
@@ -126,14 +138,14 @@ fn solve_base(
     }
 
     if max_steps.is_even() {
-        visited_even.len()
+        visited_even
     } else {
-        visited_odd.len()
+        visited_odd
     }
 }
 
-#[allow(dead_code)]
-fn display(hashtags: &HashSet<(isize, isize)>, os: &HashSet<(isize, isize)>) {
+#[cfg(test)]
+fn render(hashtags: &HashSet<(isize, isize)>, os: &HashSet<(isize, isize)>) -> String {
     let rows = hashtags.iter().chain(os).map(|p| p.0).collect_vec();
     let cols = hashtags.iter().chain(os).map(|p| p.1).collect_vec();
     let min_row = rows.iter().min().unwrap();
@@ -149,11 +161,9 @@ fn display(hashtags: &HashSet<(isize, isize)>, os: &HashSet<(isize, isize)>) {
     for (row, col) in os.iter().map(|(r, c)| (r - min_row, c - min_col)) {
         buf[row as usize][col as usize] = 'O';
     }
-    let graph = buf
-        .into_iter()
+    buf.into_iter()
         .map(|line| line.iter().collect::<String>())
-        .join("\n");
-    println!("{graph}");
+        .join("\n")
 }
 
 #[test]
@@ -162,3 +172,50 @@ fn test() {
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_render_snapshots() {
+    use crate::utils::parse_example_testcases;
+    let example = &parse_example_testcases(include_str!("example.txt"))[0];
+    let problem = example
+        .problem
+        .lines()
+        .map(|line| line.chars().collect_vec())
+        .collect_vec();
+    let height = problem.len();
+    let width = problem[0].len();
+    let rock_coords = (0..height)
+        .cartesian_product(0..width)
+        .filter(|&(row, col)| problem[row][col] == '#')
+        .collect::<HashSet<_>>();
+    let start = ((height - 1) / 2, (width - 1) / 2);
+
+    // tile the rocks over every copy of the map the reachable plots spill into
+    let tiled_rocks = |reachable: &HashSet<(isize, isize)>| {
+        let (height, width) = (height as isize, width as isize);
+        let min_tile = |coord: isize, len: isize| coord - coord.rem_euclid(len);
+        let rows = reachable.iter().map(|p| p.0);
+        let cols = reachable.iter().map(|p| p.1);
+        let row_range = min_tile(rows.clone().min().unwrap(), height)
+            ..min_tile(rows.max().unwrap(), height) + height;
+        let col_range = min_tile(cols.clone().min().unwrap(), width)
+            ..min_tile(cols.max().unwrap(), width) + width;
+        row_range
+            .cartesian_product(col_range)
+            .filter(|&(row, col)| {
+                rock_coords.contains(&(
+                    row.rem_euclid(height) as usize,
+                    col.rem_euclid(width) as usize,
+                ))
+            })
+            .collect::<HashSet<_>>()
+    };
+
+    for steps in [6, 10, 50] {
+        let reachable = reachable(&rock_coords, start, width, height, steps);
+        insta::assert_snapshot!(
+            format!("steps_{steps}"),
+            render(&tiled_rocks(&reachable), &reachable)
+        );
+    }
+}
@@ -1,109 +1,170 @@
-use std::collections::HashMap;
-
+use advent_common::graph::{Edges, Graph};
 use itertools::Itertools;
-use rand::{thread_rng, Rng};
+use std::collections::HashSet;
 
 pub fn solve(problem: &str) -> (usize, usize) {
-    let mut node_map = HashMap::new();
-    let mut edge_list = vec![];
-    let mut initial_node_id_map = HashMap::new();
-
-
-    for (id, name) in problem.lines().flat_map(|line| line.replace(':', "").split_whitespace().map(String::from).collect_vec()).unique().enumerate() {
-        initial_node_id_map.insert(name.clone(), id);
-        node_map.insert(id, Node {
-            contains: vec![name],
-            adjacent: vec![],
-        });
-    }
-
-    for line in problem.lines() {
-        let name= &line[0..3];
-        let id = initial_node_id_map[name];
-        for adj_name in line[5..].split(' ') {
-            let adj_id = initial_node_id_map[adj_name];
-            node_map.get_mut(&id).unwrap().adjacent.push(adj_id);
-            node_map.get_mut(&adj_id).unwrap().adjacent.push(id);
-            edge_list.push((id, adj_id));
-        }
-    }
-
-    (solve1(&node_map, &edge_list), solve2())
+    let graph = Graph::parse_lists(problem, Edges::Undirected);
+    (solve1(&graph), solve2())
 }
 
-fn solve1(initial_node_map: &HashMap<usize, Node>, initial_edge_list: &[(usize, usize)]) -> usize {
-    let mut rng = thread_rng();
-
-    let (left, right) = loop {
-        let mut node_map = initial_node_map.clone();
-        let mut edge_list = initial_edge_list.to_vec();
-
-        while node_map.len() > 2 {
-            let random_edge = rng.gen_range(0..edge_list.len());
-            let edge = edge_list[random_edge];
-            // remove all instances of the chosen edge
-            edge_list.retain(|e| e != &edge && e != &(edge.1, edge.0));
-
-            let (keep_id, merge_id) = edge;
-            let mut merge = node_map.remove(&merge_id).unwrap();
-
-            // make all nodes on the other side of "merge" point instead to "keep"
-            for merge_adj_id in &merge.adjacent {
-                if merge_adj_id == &keep_id {
-                    continue;
-                }
+fn solve1(graph: &Graph) -> usize {
+    let cut = graph
+        .min_cut()
+        .expect("there should be components to separate");
+    debug_assert_eq!(3, cut.edges.len(), "expected to cut three wires");
+    cut.sides.iter().map(Vec::len).product()
+}
 
-                let merge_adj = node_map.get_mut(merge_adj_id).unwrap();
-                merge_adj.adjacent.retain(|id| id != &merge_id);
-                if !merge_adj.adjacent.contains(&keep_id) {
-                    merge_adj.adjacent.push(keep_id);
+/// The most wires [`solve_reference`] will take on, which is tens of thousands of triples to try.
+const MAX_REFERENCE_EDGES: usize = 64;
+
+/// Removes every combination of three edges and checks whether the graph falls apart, so it is
+/// deterministic but only practical for small graphs. Larger ones are refused rather than left to
+/// run for hours.
+pub fn solve_reference(problem: &str) -> (usize, usize) {
+    let graph = Graph::parse_lists(problem, Edges::Undirected);
+    let edges = graph.edges().collect_vec();
+    assert!(
+        edges.len() <= MAX_REFERENCE_EDGES,
+        "the reference only handles up to {MAX_REFERENCE_EDGES} wires, not {}",
+        edges.len()
+    );
+
+    let group_size = edges
+        .iter()
+        .combinations(3)
+        .find_map(|cut| {
+            let mut seen = HashSet::from([0]);
+            let mut to_visit = vec![0];
+            while let Some(id) = to_visit.pop() {
+                for adj_id in graph.neighbours(id) {
+                    let edge = (id.min(*adj_id), id.max(*adj_id));
+                    if !cut.contains(&&edge) && seen.insert(*adj_id) {
+                        to_visit.push(*adj_id);
+                    }
                 }
             }
 
-            // anything that used to be connected to "merge"
-            for edge in edge_list.iter_mut() {
-                if edge.0 == merge_id {
-                    edge.0 = keep_id
-                } else if edge.1 == merge_id {
-                    edge.1 = keep_id
-                }
-            }
+            (seen.len() < graph.len()).then_some(seen.len())
+        })
+        .expect("should be able to split the graph by cutting three edges");
 
-            let keep = node_map.get_mut(&keep_id).unwrap();
-
-            // absorb "merge" into "keep"
-            keep.contains.append(&mut merge.contains);
-            keep.adjacent.append(&mut merge.adjacent);
-            keep.adjacent.retain(|id| id != &merge_id && id != &keep_id);
-            keep.adjacent.sort();
-            keep.adjacent.dedup();
-        }
-
-        if edge_list.len() == 3 {
-            break node_map.values().cloned().collect_tuple().unwrap();
-        }
-    };
-    // repeat while the partition is greater than 3 edges
-    // repeat while there are more than two nodes
-    // pick a random edge
-    // delete it
-    // merge the nodes
-    left.contains.len() * right.contains.len()
+    (group_size * (graph.len() - group_size), solve2())
 }
 
 fn solve2() -> usize {
     0
 }
 
-#[derive(Hash, Clone, Debug)]
-struct Node {
-    contains: Vec<String>,
-    adjacent: Vec<usize>,
-}
-
 #[test]
 fn test() {
     use crate::utils::basic_test;
     let input = include_str!("example.txt");
     basic_test(input, solve);
 }
+
+#[test]
+fn test_reference() {
+    use crate::utils::basic_test;
+    let input = include_str!("example.txt");
+    basic_test(input, solve_reference);
+}
+
+#[test]
+fn test_reference_agrees() {
+    use advent_common::differential::differential_test;
+    use rand::Rng;
+
+    // two complete groups of wires, which take more than three cuts to split, joined by three
+    let result = differential_test(
+        20,
+        7,
+        |rng, size| {
+            let groups = [rng.gen_range(5..=size), rng.gen_range(5..=size)];
+            let name = |group: usize, idx: usize| format!("{}{idx:02}", ["l", "r"][group]);
+            let mut wires = (0..2)
+                .flat_map(|group| {
+                    (0..groups[group])
+                        .tuple_combinations()
+                        .map(move |(a, b)| (name(group, a), name(group, b)))
+                })
+                .collect_vec();
+            while wires.len() < groups.iter().map(|n| n * (n - 1) / 2).sum::<usize>() + 3 {
+                let wire = (
+                    name(0, rng.gen_range(0..groups[0])),
+                    name(1, rng.gen_range(0..groups[1])),
+                );
+                if !wires.contains(&wire) {
+                    wires.push(wire);
+                }
+            }
+            wires
+                .iter()
+                .map(|(from, to)| format!("{from}: {to}"))
+                .join("\n")
+        },
+        |_| vec![],
+        |input| {
+            let edges = input.lines().count();
+            edges <= MAX_REFERENCE_EDGES && solve(input) == solve_reference(input)
+        },
+    );
+    assert_eq!(Ok(()), result);
+}
+
+#[test]
+fn test_example_cut() {
+    use crate::utils::parse_example_testcases;
+
+    let example = &parse_example_testcases(include_str!("example.txt"))[0].problem;
+    let graph = Graph::parse_lists(example, Edges::Undirected);
+    let cut = graph.min_cut().unwrap();
+
+    let cut_edges = cut
+        .edges
+        .iter()
+        .map(|(from, to)| {
+            [graph.label(*from), graph.label(*to)]
+                .iter()
+                .sorted()
+                .join("/")
+        })
+        .sorted()
+        .collect_vec();
+    assert_eq!(vec!["bvb/cmg", "hfx/pzl", "jqt/nvd"], cut_edges);
+    assert_eq!([6, 9], cut.sides.clone().map(|side| side.len()));
+    assert!(cut.sides[0].contains(&0));
+    assert_eq!(Some(cut), graph.min_cut());
+}
+
+#[test]
+fn test_example_components() {
+    use crate::utils::parse_example_testcases;
+
+    let example = &parse_example_testcases(include_str!("example.txt"))[0].problem;
+    let graph = Graph::parse_lists(example, Edges::Undirected);
+    let mut network = graph.flow_network(1);
+
+    let (source, sink) = (graph.id("jqt").unwrap(), graph.id("cmg").unwrap());
+    assert_eq!(3, network.max_flow(source, sink));
+    let cut = network.min_cut(source);
+    let cut_edges = cut
+        .edges
+        .iter()
+        .map(|(from, to)| {
+            [graph.label(*from), graph.label(*to)]
+                .iter()
+                .sorted()
+                .join("/")
+        })
+        .sorted()
+        .collect_vec();
+    assert_eq!(vec!["bvb/cmg", "hfx/pzl", "jqt/nvd"], cut_edges);
+    assert_eq!([6, 9], cut.sides.clone().map(|side| side.len()));
+    assert_eq!(graph.min_cut().unwrap().sides, cut.sides);
+    assert_eq!(3, network.decompose(source, sink).len());
+
+    // components on the same side are joined by more than three wires
+    network.reset();
+    assert!(network.max_flow(source, graph.id("ntq").unwrap()) > 3);
+}
@@ -2,7 +2,8 @@
 
 extern crate core;
 
-use crate::utils::SolverFn;
+use crate::utils::{DifferentialFn, SolverFn};
+use advent_common::timing;
 use clap::Parser;
 use core::panic;
 use itertools::Itertools;
@@ -43,6 +44,15 @@ fn main() {
     let cli = Cli::parse();
     // let cli = Cli::parse_from(["", "20"]);
 
+    if let Some(iterations) = cli.differential {
+        let differential = get_differential(cli.day);
+        match differential(iterations, cli.size) {
+            Ok(()) => println!("{iterations} generated inputs agree with the reference"),
+            Err(input) => println!("Smallest disagreeing input:\n{input}"),
+        }
+        return;
+    }
+
     let file = match cli.example {
         true => "example.txt",
         false => "input.txt",
@@ -55,12 +65,47 @@ fn main() {
         false => get_real_testcases(cli.day, &input),
     };
 
-    let solver = get_solver(cli.day);
+    if let Some(presses) = cli.trace {
+        match cli.day {
+            20 => test_cases
+                .iter()
+                .for_each(|test_case| d20::print_trace(&test_case.problem, presses)),
+            _ => panic!("only day 20 prints a trace"),
+        }
+        return;
+    }
+
+    if cli.periods {
+        match cli.day {
+            20 => test_cases
+                .iter()
+                .for_each(|test_case| d20::print_periods(&test_case.problem)),
+            _ => panic!("only day 20 prints periods"),
+        }
+        return;
+    }
+
+    if cli.dot {
+        match cli.day {
+            20 => test_cases
+                .iter()
+                .for_each(|test_case| d20::print_dot(&test_case.problem)),
+            _ => panic!("only day 20 prints a graph"),
+        }
+        return;
+    }
+
+    let solver = match cli.reference {
+        true => get_reference_solver(cli.day),
+        false => get_solver(cli.day),
+    };
 
     let mut printable_results = test_cases.iter().map(|test_case| {
         let start = time::Instant::now();
         let res = solver(&test_case.problem);
         let elapsed = start.elapsed();
+        let run_name = format!("d{:02}::{}", cli.day, file.trim_end_matches(".txt"));
+        timing::check(&run_name, elapsed, utils::TIME_BUDGETS);
 
         let (answer1, res0) = format_to_longest(test_case.answer1, res.0);
         let (answer2, res1) = format_to_longest(test_case.answer2, res.1);
@@ -128,6 +173,25 @@ fn get_solver(day: u8) -> SolverFn {
     }
 }
 
+fn get_differential(day: u8) -> DifferentialFn {
+    match day {
+        10 => d10::differential,
+        _ => {
+            panic!("no input generator for this day");
+        }
+    }
+}
+
+fn get_reference_solver(day: u8) -> SolverFn {
+    match day {
+        10 => d10::solve_reference,
+        25 => d25::solve_reference,
+        _ => {
+            panic!("no reference solver for this day");
+        }
+    }
+}
+
 fn get_real_testcases(day: u8, problem: &str) -> Vec<TestCase> {
     let problem = problem.to_string();
     match day {
@@ -267,4 +331,22 @@ struct Cli {
     day: u8,
     #[arg(long)]
     example: bool,
+    /// use the slow but general reference solver instead of the optimized one
+    #[arg(long)]
+    reference: bool,
+    /// cross-check the solver against a brute-force reference on this many generated inputs
+    #[arg(long)]
+    differential: Option<usize>,
+    /// size of each generated input
+    #[arg(long, default_value_t = 10)]
+    size: usize,
+    /// day 20 only: print the module network in Graphviz's DOT format
+    #[arg(long)]
+    dot: bool,
+    /// day 20 only: print every pulse sent during this many button presses
+    #[arg(long)]
+    trace: Option<usize>,
+    /// day 20 only: print when the network, and each part only the broadcaster feeds, repeats
+    #[arg(long)]
+    periods: bool,
 }
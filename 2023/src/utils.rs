@@ -1,20 +1,40 @@
+use advent_common::timing;
 use itertools::Itertools;
 use num::cast::AsPrimitive;
-use std::collections::HashMap;
+use std::{collections::HashMap, env, thread, time};
 
 pub type SolverFn = fn(&str) -> (usize, usize);
+pub type DifferentialFn = fn(usize, usize) -> Result<(), String>;
 
 #[allow(dead_code)]
 pub fn basic_test(input: &str, test: SolverFn) {
     let examples = parse_example_testcases(input);
+    let start = time::Instant::now();
     for (idx, example) in examples.iter().enumerate() {
         println!("Example {}", idx + 1);
         let (answer1, answer2) = test(&example.problem);
         assert_eq!(example.answer1, answer1);
         assert_eq!(example.answer2, answer2);
     }
+    let elapsed = start.elapsed();
+
+    // the test harness names each thread after its test, which tells the solvers apart
+    let test_name = thread::current()
+        .name()
+        .map_or_else(|| "example".to_string(), str::to_string);
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    timing::record(&manifest_dir, test_name, elapsed, TIME_BUDGETS);
 }
 
+/// Time budgets in milliseconds for the days known to be slow, by test name or by day, for
+/// [`timing::record`] and [`timing::check`]. Everything else gets a second.
+pub const TIME_BUDGETS: &[(&str, u64)] = &[
+    // the longest path has to try every route between the junctions
+    ("d23", 10_000),
+    // the hailstone solver searches over candidate rock velocities
+    ("d24", 3_000),
+];
+
 pub fn median_round_down(input: Vec<usize>) -> usize {
     let mut input = input.to_vec();
     input.sort();
@@ -1,4 +1,5 @@
 use crate::common::Direction::{Down, Left, Right, Up};
+use advent_common::timing;
 use itertools::Itertools;
 use num::{integer::gcd, Integer};
 use num_traits::Num;
@@ -10,7 +11,7 @@ use std::{
     fs::read_to_string,
     hash::Hash,
     ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
-    time,
+    thread, time,
 };
 
 pub fn test<T: Debug + Eq, F: Fn(&str) -> T>(
@@ -48,8 +49,21 @@ pub fn test_with_params<P, T: Debug + Eq, F: Fn(&str, P) -> T>(
     };
 
     println!("Elapsed: {time} {units}");
+
+    // the test harness names each thread after its test, which tells p1 and p2 apart
+    let test_name = thread::current()
+        .name()
+        .map_or_else(|| format!("{module_name}::{file_name}"), str::to_string);
+    timing::record(&manifest_dir, test_name, elapsed, TIME_BUDGETS);
 }
 
+/// Time budgets in milliseconds for the days known to be slow, by test name or by day, for
+/// [`timing::record`]. Everything else gets a second.
+const TIME_BUDGETS: &[(&str, u64)] = &[
+    // thousands of secret numbers per buyer, two thousand steps each
+    ("d22", 5_000),
+];
+
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
 pub struct Point2<T> {
     pub(crate) x: T,
@@ -1,9 +1,6 @@
 use crate::common::test;
+use advent_common::graph::{Edges, Graph};
 use itertools::Itertools;
-use std::{
-    collections::{HashMap, HashSet},
-    ops::AddAssign,
-};
 
 const MODULE: &str = module_path!();
 
@@ -32,117 +29,47 @@ fn p1(input: &str) -> usize {
 
     updates
         .iter()
-        .filter_map(|pages| {
-            let mut seen = HashSet::new();
-            for page in pages.iter() {
-                seen.insert(*page);
-
-                let Some(rule) = rules.get(page) else {
-                    continue;
-                };
-
-                if rule.iter().any(|r| seen.contains(r)) {
-                    return None;
-                }
-            }
-
-            Some(pages[pages.len() / 2])
-        })
+        .filter(|pages| is_ordered(&rules, pages))
+        .map(|pages| middle_page(&rules, pages))
         .sum()
 }
 
 fn p2(input: &str) -> usize {
     let (rules, updates) = parse_input(input);
 
-    let mut incorrect_updates = updates
-        .into_iter()
-        .filter(|pages| {
-            let mut seen = HashSet::new();
-            for page in pages.iter() {
-                seen.insert(*page);
-
-                let Some(rule) = rules.get(page) else {
-                    continue;
-                };
-
-                if rule.iter().any(|r| seen.contains(r)) {
-                    return true;
-                }
-            }
-
-            false
-        })
-        .collect_vec();
-
-    for incorrect_update in incorrect_updates.iter_mut() {
-        let rankings = generate_rankings(&rules, incorrect_update);
-
-        incorrect_update.sort_by_key(|n| rankings.get(n).unwrap_or(&0))
-    }
-
-    incorrect_updates
+    updates
         .iter()
-        .map(|pages| pages[pages.len() / 2])
+        .filter(|pages| !is_ordered(&rules, pages))
+        .map(|pages| {
+            let ordered = rules
+                .topo_sort_of(pages)
+                .unwrap_or_else(|cycle| panic!("pages {cycle:?} have no order"));
+            middle_page(&rules, &ordered)
+        })
         .sum()
 }
 
-fn generate_rankings(
-    rules: &HashMap<usize, Vec<usize>>,
-    values: &[usize],
-) -> HashMap<usize, usize> {
-    let mut new_rules = rules.clone();
-    let values: HashSet<_> = values.iter().collect();
-
-    new_rules.retain(|k, _| values.contains(k));
-
-    for rights in new_rules.values_mut() {
-        rights.retain(|v| values.contains(v));
-    }
-
-    let mut rankings = HashMap::<usize, usize>::new();
-
-    for left in new_rules.keys() {
-        recursive_increment(left, &new_rules, &mut rankings);
-    }
-
-    rankings
+/// Whether no page is printed after one that the rules say must come later.
+fn is_ordered(rules: &Graph, pages: &[usize]) -> bool {
+    pages
+        .iter()
+        .tuple_combinations()
+        .all(|(earlier, later)| !rules.has_edge(*later, *earlier))
 }
 
-fn recursive_increment(
-    left: &usize,
-    rules: &HashMap<usize, Vec<usize>>,
-    rankings: &mut HashMap<usize, usize>,
-) {
-    let Some(rights) = rules.get(left) else {
-        return;
-    };
-
-    for right in rights {
-        rankings.entry(*right).or_insert(0).add_assign(1);
-        recursive_increment(right, rules, rankings);
-    }
+fn middle_page(rules: &Graph, pages: &[usize]) -> usize {
+    rules.label(pages[pages.len() / 2]).parse().unwrap()
 }
 
-fn parse_input(input: &str) -> (HashMap<usize, Vec<usize>>, Vec<Vec<usize>>) {
+/// The ordering rules as a graph with an edge from each page to the ones that must come after it,
+/// and each update as the ids of its pages in that graph.
+fn parse_input(input: &str) -> (Graph, Vec<Vec<usize>>) {
     let (rules_input, updates_input) = input.split("\n\n").collect_tuple().unwrap();
 
-    let mut rules = HashMap::new();
-    for line in rules_input.lines() {
-        let (left, right) = line
-            .split('|')
-            .map(|n| n.parse::<usize>().unwrap())
-            .collect_tuple()
-            .unwrap();
-        rules.entry(left).or_insert(vec![]).push(right);
-    }
-
+    let mut rules = Graph::parse_pairs(rules_input, "|", Edges::Directed);
     let updates = updates_input
         .lines()
-        .map(|line| {
-            line.split(',')
-                .map(|n| n.parse::<usize>().unwrap())
-                .collect_vec()
-        })
+        .map(|line| line.split(',').map(|page| rules.intern(page)).collect_vec())
         .collect_vec();
 
     (rules, updates)
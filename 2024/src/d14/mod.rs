@@ -1,7 +1,15 @@
-use crate::common::{test_with_params, visualize_points, AsPoint2, Point2, Vector2};
+use crate::common::{
+    get_cross_neighbors, test_with_params, visualize_points, AsPoint2, Point2, Vector2,
+};
+use advent_common::{
+    cycle::find_cycle_by_key,
+    number::{first_common_step, Schedule},
+};
 use average::Variance;
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use regex::Regex;
+use std::collections::HashSet;
 
 const MODULE: &str = module_path!();
 
@@ -20,6 +28,28 @@ fn p2_input() {
     test_with_params("input", MODULE, p2, (103, 101), 7132);
 }
 
+#[test]
+#[ignore = "slow reference solver, run with --ignored"]
+fn p2_reference_input() {
+    test_with_params("input", MODULE, p2_reference, (103, 101), 7132);
+}
+
+#[test]
+fn p2_reference_agrees() {
+    let dimensions = (23, 31);
+    let input = planted_picture_input(dimensions, 300);
+    assert_eq!(300, p2_reference(&input, dimensions));
+    assert_eq!(300, p2(&input, dimensions));
+}
+
+#[test]
+fn p2_tree_snapshot() {
+    let dimensions = (23, 31);
+    let input = planted_picture_input(dimensions, 300);
+    let steps = p2(&input, dimensions);
+    insta::assert_snapshot!(render_after(&input, dimensions, steps as isize));
+}
+
 fn p1(input: &str, dimensions: (isize, isize)) -> usize {
     let (height, width) = dimensions;
     let mut robots = parse_input(input, width, height);
@@ -55,10 +85,22 @@ fn p2(input: &str, dimensions: (isize, isize)) -> usize {
     let (height, width) = dimensions;
     let mut robots = parse_input(input, width, height);
 
+    // every robot's x comes round again after `width` steps, but all of them together may do so
+    // sooner, and likewise for y
+    let step = |robots: &mut Vec<Robot>| robots.iter_mut().for_each(|robot| robot.step(1));
+    let x_cycle = find_cycle_by_key(&robots, step, |robots| {
+        robots.iter().map(|robot| robot.position.x).collect_vec()
+    });
+    let y_cycle = find_cycle_by_key(&robots, step, |robots| {
+        robots.iter().map(|robot| robot.position.y).collect_vec()
+    });
+    let x_steps = x_cycle.start + x_cycle.length;
+    let y_steps = y_cycle.start + y_cycle.length;
+
     let mut min_var_x = (f64::MAX, 0);
     let mut min_var_y = (f64::MAX, 0);
 
-    for i in 0..width.max(height) {
+    for i in 0..x_steps.max(y_steps) {
         let var_x = robots
             .iter()
             .map(|robot| robot.position.x as f64)
@@ -70,45 +112,109 @@ fn p2(input: &str, dimensions: (isize, isize)) -> usize {
             .collect::<Variance>()
             .population_variance();
 
-        if var_x < min_var_x.0 {
+        if var_x < min_var_x.0 && i < x_steps {
             min_var_x = (var_x, i);
         }
 
-        if var_y < min_var_y.0 {
+        if var_y < min_var_y.0 && i < y_steps {
             min_var_y = (var_y, i);
         }
 
-        for robot in &mut robots {
-            robot.step(1);
-        }
+        step(&mut robots);
     }
 
-    // patterns repeat every {width} in the x, and every {height} in the y
-    // when the pattern in the x with minimum variance overlaps with the pattern in the y, then
-    // we have likely found the Christmas tree, which is more clustered than a rand dist
-    // the state of the graph repeats every width * height
-    let repetition_length = width * height;
-    let min_var_x_idx = min_var_x.1;
-    let min_var_y_idx = min_var_y.1;
-    let steps = (0..repetition_length / width)
-        .cartesian_product(0..repetition_length / height)
-        .find_map(|(x_repetition, y_repetition)| {
-            if min_var_x_idx + width * x_repetition == min_var_y_idx + height * y_repetition {
-                Some(min_var_x_idx + width * x_repetition)
-            } else {
-                None
-            }
-        })
-        .unwrap();
+    // when the x pattern with minimum variance overlaps with the y pattern with minimum
+    // variance, then we have likely found the Christmas tree, which is more clustered than a
+    // random distribution
+    let steps = first_common_step(&[
+        Schedule::new(vec![min_var_x.1], x_cycle.start, x_cycle.length),
+        Schedule::new(vec![min_var_y.1], y_cycle.start, y_cycle.length),
+    ])
+    .expect("the x and y patterns should line up");
+
+    println!("{}", render_after(input, dimensions, steps as isize));
 
+    steps
+}
+
+fn render_after(input: &str, dimensions: (isize, isize), steps: isize) -> String {
+    let (height, width) = dimensions;
     let mut robots = parse_input(input, width, height);
     for robot in &mut robots {
         robot.step(steps);
     }
-    let visualized = visualize_points(&robots, width as usize, height as usize);
-    println!("{visualized}");
+    visualize_points(&robots, width as usize, height as usize)
+}
+
+/// Checks every state before the robots repeat and picks the one where the most robots form a
+/// single connected blob, rather than assuming the x and y variances line up independently.
+fn p2_reference(input: &str, dimensions: (isize, isize)) -> usize {
+    let (height, width) = dimensions;
+    let mut robots = parse_input(input, width, height);
+
+    let mut largest_blob = (0, 0);
+    for i in 0..width * height {
+        let positions = robots
+            .iter()
+            .map(|robot| robot.position)
+            .collect::<HashSet<_>>();
+        let mut unvisited = positions.clone();
+        while let Some(&start) = unvisited.iter().next() {
+            unvisited.remove(&start);
+            let mut to_visit = vec![start];
+            let mut blob_size = 0;
+            while let Some(position) = to_visit.pop() {
+                blob_size += 1;
+                for neighbor in get_cross_neighbors(position) {
+                    if unvisited.remove(&neighbor) {
+                        to_visit.push(neighbor);
+                    }
+                }
+            }
+            if blob_size > largest_blob.0 {
+                largest_blob = (blob_size, i);
+            }
+        }
+
+        for robot in &mut robots {
+            robot.step(1);
+        }
+    }
+
+    largest_blob.1 as usize
+}
+
+/// Places a filled 5x5 square of robots in the middle of the room at step `steps`, plus a few
+/// stray robots, and rewinds everything back to the starting positions.
+fn planted_picture_input(dimensions: (isize, isize), steps: isize) -> String {
+    let (height, width) = dimensions;
+    let mut rng = StdRng::seed_from_u64(14);
 
-    steps as usize
+    let square = (0..5)
+        .cartesian_product(0..5)
+        .map(|(dx, dy)| (width / 2 + dx - 2, height / 2 + dy - 2));
+    let strays = (0..10)
+        .map(|_| (rng.gen_range(0..width), rng.gen_range(0..height)))
+        .collect_vec();
+
+    square
+        .chain(strays)
+        .map(|(x, y)| {
+            let velocity = Vector2 {
+                x: rng.gen_range(-5..=5),
+                y: rng.gen_range(-5..=5),
+            };
+            let mut robot = Robot {
+                position: Point2 { x, y },
+                velocity: -velocity,
+                x_bound: width,
+                y_bound: height,
+            };
+            robot.step(steps);
+            let Point2 { x, y } = robot.position;
+            format!("p={x},{y} v={},{}", velocity.x, velocity.y)
+        })
+        .join("\n")
 }
 
 fn parse_input(input: &str, x_bound: isize, y_bound: isize) -> Vec<Robot> {
@@ -3,8 +3,8 @@ use crate::common::{
     RotationDirection::{Clockwise, Counterclockwise},
     Vector2,
 };
+use advent_common::search::{all_shortest_paths, AllPaths, SearchState};
 use itertools::Itertools;
-use pathfinding::prelude::{astar_bag, AstarSolution};
 use std::collections::HashMap;
 
 const MODULE: &str = module_path!();
@@ -31,42 +31,74 @@ fn p2_input() {
 
 fn p1(input: &str) -> usize {
     let (maze, start_pos, end_pos) = parse_input(input);
-    let (_, cost) = compute_shortest_paths(&maze, start_pos, end_pos);
 
-    cost
+    compute_shortest_paths(maze, start_pos, end_pos).cost
 }
 
 fn p2(input: &str) -> usize {
     let (maze, start_pos, end_pos) = parse_input(input);
-    let (solutions, _) = compute_shortest_paths(&maze, start_pos, end_pos);
+    let paths = compute_shortest_paths(maze, start_pos, end_pos);
 
-    solutions.flatten().map(|(pos, _)| pos).unique().count()
+    paths
+        .states()
+        .iter()
+        .map(|reindeer| reindeer.pos)
+        .unique()
+        .count()
 }
 
 fn compute_shortest_paths(
-    maze: &HashMap<Point2<isize>, char>,
+    tiles: HashMap<Point2<isize>, char>,
     start_pos: Point2<isize>,
     end_pos: Point2<isize>,
-) -> (AstarSolution<AStarPosition>, usize) {
-    astar_bag(
-        &(start_pos, Vector2 { x: 1, y: 0 }),
-        |(curr_pos, curr_dir)| {
-            let clockwise_dir = curr_dir.rotated_90(Clockwise);
-            let counterclockwise_dir = curr_dir.rotated_90(Counterclockwise);
-            let mut successors = vec![
-                ((*curr_pos, clockwise_dir), 1000),
-                ((*curr_pos, counterclockwise_dir), 1000),
-            ];
-            let forward_move = *curr_pos + *curr_dir;
-            if maze[&forward_move] != '#' {
-                successors.push(((forward_move, *curr_dir), 1));
-            }
-            successors
-        },
-        |(curr_pos, _)| curr_pos.x.abs_diff(end_pos.x) + curr_pos.y.abs_diff(end_pos.y),
-        |(curr_pos, _)| curr_pos == &end_pos,
-    )
-    .unwrap()
+) -> AllPaths<Reindeer, usize> {
+    let start = Reindeer {
+        pos: start_pos,
+        dir: Vector2 { x: 1, y: 0 },
+    };
+    all_shortest_paths(start, &Maze { tiles, end_pos }).unwrap()
+}
+
+struct Maze {
+    tiles: HashMap<Point2<isize>, char>,
+    end_pos: Point2<isize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Reindeer {
+    pos: Point2<isize>,
+    dir: Vector2<isize>,
+}
+
+impl SearchState<Maze> for Reindeer {
+    type Cost = usize;
+
+    fn successors(&self, maze: &Maze) -> Vec<(Self, usize)> {
+        let turn = |dir| Self { pos: self.pos, dir };
+        let mut successors = vec![
+            (turn(self.dir.rotated_90(Clockwise)), 1000),
+            (turn(self.dir.rotated_90(Counterclockwise)), 1000),
+        ];
+        let forward_move = self.pos + self.dir;
+        if maze.tiles[&forward_move] != '#' {
+            successors.push((
+                Self {
+                    pos: forward_move,
+                    dir: self.dir,
+                },
+                1,
+            ));
+        }
+        successors
+    }
+
+    fn heuristic(&self, maze: &Maze) -> usize {
+        self.pos.x.abs_diff(maze.end_pos.x) + self.pos.y.abs_diff(maze.end_pos.y)
+    }
+
+    fn is_goal(&self, maze: &Maze) -> bool {
+        self.pos == maze.end_pos
+    }
 }
 
 fn parse_input(input: &str) -> (HashMap<Point2<isize>, char>, Point2<isize>, Point2<isize>) {
@@ -82,5 +114,3 @@ fn parse_input(input: &str) -> (HashMap<Point2<isize>, char>, Point2<isize>, Poi
 
     (maze, start_pos, end_pos)
 }
-
-type AStarPosition = (Point2<isize>, Vector2<isize>);
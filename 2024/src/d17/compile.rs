@@ -0,0 +1,454 @@
+use super::{parse_input, ComboOperand, Computer, Instruction, LiteralOperand};
+
+#[test]
+fn compiled_matches_interpreter() {
+    let realistic =
+        "Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: 2,4,1,1,7,5,1,5,4,5,0,3,5,5,3,0";
+    for input in [
+        include_str!("example.txt"),
+        include_str!("example2.txt"),
+        realistic,
+    ] {
+        let (computer, instructions, _) = parse_input(input);
+        let compiled = CompiledProgram::compile(&instructions, &computer);
+        for register_a in (0..2000).chain([computer.register_a, 117440, 236548287712877]) {
+            assert_eq!(
+                interpret(&instructions, &computer, register_a),
+                compiled.run(register_a)
+            );
+        }
+    }
+}
+
+#[test]
+fn folds_constants_and_drops_dead_writes() {
+    // bst 3; cdv B; out C; adv 3; jnz 0
+    let input = "Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: 2,3,7,5,5,6,0,3,3,0";
+    let (computer, instructions, _) = parse_input(input);
+
+    // B is always 3 and only used as the shift amount, so it never has to be stored
+    assert_eq!(
+        vec![
+            Op::Shr {
+                destination: C,
+                amount: Operand::Immediate(3),
+            },
+            Op::Out(Operand::Register(C)),
+            Op::Shr {
+                destination: A,
+                amount: Operand::Immediate(3),
+            },
+            Op::Jnz(0),
+        ],
+        CompiledProgram::compile(&instructions, &computer).ops
+    );
+}
+
+#[bench]
+fn bench_interpreter_input(bencher: &mut test::Bencher) {
+    let (computer, instructions, _) = parse_input(&bench_input());
+    bencher.iter(|| {
+        for register_a in 0..100 {
+            test::black_box(interpret(&instructions, &computer, register_a << 40));
+        }
+    });
+}
+
+#[bench]
+fn bench_compiled_input(bencher: &mut test::Bencher) {
+    let (computer, instructions, _) = parse_input(&bench_input());
+    let compiled = CompiledProgram::compile(&instructions, &computer);
+    bencher.iter(|| {
+        for register_a in 0..100 {
+            test::black_box(compiled.run(register_a << 40));
+        }
+    });
+}
+
+#[cfg(test)]
+fn bench_input() -> String {
+    use std::{env, fs::read_to_string};
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    read_to_string(format!("{manifest_dir}/src/d17/input.txt")).unwrap()
+}
+
+#[cfg(test)]
+fn interpret(instructions: &[Instruction], computer: &Computer, register_a: usize) -> Vec<usize> {
+    let mut computer = computer.clone();
+    computer.register_a = register_a;
+    while let Some(instruction) = instructions.get(computer.instruction_pointer) {
+        computer.execute(instruction);
+    }
+    computer.output
+}
+
+const A: usize = 0;
+const B: usize = 1;
+const C: usize = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Operand {
+    Register(usize),
+    Immediate(usize),
+}
+
+/// A lowered instruction. Registers are indexed A, B, C.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Op {
+    /// `adv`, `bdv` and `cdv`: the destination becomes A divided by 2 to the power of `amount`.
+    Shr {
+        destination: usize,
+        amount: Operand,
+    },
+    Xor {
+        destination: usize,
+        operand: Operand,
+    },
+    Mod8 {
+        destination: usize,
+        operand: Operand,
+    },
+    Set {
+        destination: usize,
+        value: usize,
+    },
+    Out(Operand),
+    Jnz(usize),
+    Jump(usize),
+}
+
+impl Op {
+    fn destination(&self) -> Option<usize> {
+        match self {
+            Op::Shr { destination, .. }
+            | Op::Xor { destination, .. }
+            | Op::Mod8 { destination, .. }
+            | Op::Set { destination, .. } => Some(*destination),
+            Op::Out(_) | Op::Jnz(_) | Op::Jump(_) => None,
+        }
+    }
+
+    fn reads(&self) -> Vec<usize> {
+        let register = |operand: &Operand| match operand {
+            Operand::Register(register) => Some(*register),
+            Operand::Immediate(_) => None,
+        };
+        match self {
+            Op::Shr { amount, .. } => [Some(A), register(amount)].into_iter().flatten().collect(),
+            Op::Xor {
+                destination,
+                operand,
+            } => [Some(*destination), register(operand)]
+                .into_iter()
+                .flatten()
+                .collect(),
+            Op::Mod8 { operand, .. } | Op::Out(operand) => register(operand).into_iter().collect(),
+            Op::Set { .. } | Op::Jump(_) => vec![],
+            Op::Jnz(_) => vec![A],
+        }
+    }
+
+    fn successors(&self, index: usize) -> Vec<usize> {
+        match self {
+            Op::Jnz(target) => vec![*target, index + 1],
+            Op::Jump(target) => vec![*target],
+            _ => vec![index + 1],
+        }
+    }
+}
+
+/// A 3-bit program lowered to bytecode, specialised for the starting values of B and C so that it
+/// can be run cheaply for many different values of A.
+#[derive(Debug)]
+pub struct CompiledProgram {
+    ops: Vec<Op>,
+    register_b: usize,
+    register_c: usize,
+}
+
+impl CompiledProgram {
+    pub fn compile(instructions: &[Instruction], computer: &Computer) -> Self {
+        let initial = [None, Some(computer.register_b), Some(computer.register_c)];
+        Self {
+            ops: eliminate_dead_writes(fold_constants(instructions, initial)),
+            register_b: computer.register_b,
+            register_c: computer.register_c,
+        }
+    }
+
+    /// Everything the program prints when started with `register_a` in A.
+    pub fn run(&self, register_a: usize) -> Vec<usize> {
+        let mut registers = [register_a, self.register_b, self.register_c];
+        let mut output = vec![];
+        let mut pointer = 0;
+        while let Some(op) = self.ops.get(pointer) {
+            let value = |operand: Operand| match operand {
+                Operand::Register(register) => registers[register],
+                Operand::Immediate(value) => value,
+            };
+            pointer += 1;
+            match *op {
+                Op::Shr {
+                    destination,
+                    amount,
+                } => {
+                    registers[destination] = shift_right(registers[A], value(amount));
+                }
+                Op::Xor {
+                    destination,
+                    operand,
+                } => registers[destination] ^= value(operand),
+                Op::Mod8 {
+                    destination,
+                    operand,
+                } => registers[destination] = value(operand) % 8,
+                Op::Set { destination, value } => registers[destination] = value,
+                Op::Out(operand) => output.push(value(operand) % 8),
+                Op::Jnz(target) => {
+                    if registers[A] != 0 {
+                        pointer = target;
+                    }
+                }
+                Op::Jump(target) => pointer = target,
+            }
+        }
+        output
+    }
+}
+
+fn shift_right(value: usize, amount: usize) -> usize {
+    u32::try_from(amount)
+        .ok()
+        .and_then(|amount| value.checked_shr(amount))
+        .unwrap_or(0)
+}
+
+type Known = [Option<usize>; 3];
+
+/// Works out which registers hold the same value every time an instruction is reached, then
+/// lowers each instruction with those values filled in. Results that are known ahead of time
+/// become a plain `Set`, which is left for `eliminate_dead_writes` to drop if nothing reads it.
+fn fold_constants(instructions: &[Instruction], initial: Known) -> Vec<Op> {
+    let ops = instructions.iter().map(lower).collect::<Vec<_>>();
+
+    // None means the instruction is never reached
+    let mut entry_states: Vec<Option<Known>> = vec![None; ops.len()];
+    let mut to_visit = vec![(0, initial)];
+    while let Some((index, known)) = to_visit.pop() {
+        let Some(op) = ops.get(index) else {
+            continue;
+        };
+        let merged = match entry_states[index] {
+            None => known,
+            Some(previous) => {
+                let merged = [0, 1, 2].map(|register| {
+                    (previous[register] == known[register])
+                        .then_some(previous[register])
+                        .flatten()
+                });
+                if merged == previous {
+                    continue;
+                }
+                merged
+            }
+        };
+        entry_states[index] = Some(merged);
+        let (_, after) = fold(op, merged);
+        for successor in reachable_successors(op, index, &merged) {
+            to_visit.push((successor, after));
+        }
+    }
+
+    // lower again with the final states, remembering where each instruction starts for jumps
+    let mut folded = vec![];
+    let mut starts = vec![];
+    for (op, known) in ops.iter().zip(&entry_states) {
+        starts.push(folded.len());
+        if let Some(known) = known {
+            folded.extend(fold(op, *known).0);
+        }
+    }
+    starts.push(folded.len());
+    retarget(folded, |target| starts[target.min(starts.len() - 1)])
+}
+
+fn lower(instruction: &Instruction) -> Op {
+    let combo = |operand: &ComboOperand| match operand {
+        ComboOperand::Literal(value) => Operand::Immediate(*value),
+        ComboOperand::RegisterA => Operand::Register(A),
+        ComboOperand::RegisterB => Operand::Register(B),
+        ComboOperand::RegisterC => Operand::Register(C),
+    };
+    match instruction {
+        Instruction::Adv(operand) => Op::Shr {
+            destination: A,
+            amount: combo(operand),
+        },
+        Instruction::Bxl(LiteralOperand(value)) => Op::Xor {
+            destination: B,
+            operand: Operand::Immediate(*value),
+        },
+        Instruction::Bst(operand) => Op::Mod8 {
+            destination: B,
+            operand: combo(operand),
+        },
+        Instruction::Jnz(LiteralOperand(target)) => Op::Jnz(*target),
+        Instruction::Bxc => Op::Xor {
+            destination: B,
+            operand: Operand::Register(C),
+        },
+        Instruction::Out(operand) => Op::Out(combo(operand)),
+        Instruction::Bdv(operand) => Op::Shr {
+            destination: B,
+            amount: combo(operand),
+        },
+        Instruction::Cdv(operand) => Op::Shr {
+            destination: C,
+            amount: combo(operand),
+        },
+    }
+}
+
+/// The ops to run for `op` when the registers are `known`, and what is known afterwards.
+fn fold(op: &Op, mut known: Known) -> (Vec<Op>, Known) {
+    let substitute = |operand: Operand| match operand {
+        Operand::Register(register) => known[register].map_or(operand, Operand::Immediate),
+        Operand::Immediate(_) => operand,
+    };
+    let immediate = |operand: Operand| match substitute(operand) {
+        Operand::Immediate(value) => Some(value),
+        Operand::Register(_) => None,
+    };
+    let (folded, result) = match *op {
+        Op::Shr {
+            destination,
+            amount,
+        } => {
+            let amount = substitute(amount);
+            let result = known[A]
+                .zip(immediate(amount))
+                .map(|(a, amount)| shift_right(a, amount));
+            (
+                Op::Shr {
+                    destination,
+                    amount,
+                },
+                result,
+            )
+        }
+        Op::Xor {
+            destination,
+            operand,
+        } => {
+            let operand = substitute(operand);
+            let result = known[destination]
+                .zip(immediate(operand))
+                .map(|(lhs, rhs)| lhs ^ rhs);
+            (
+                Op::Xor {
+                    destination,
+                    operand,
+                },
+                result,
+            )
+        }
+        Op::Mod8 {
+            destination,
+            operand,
+        } => {
+            let operand = substitute(operand);
+            (
+                Op::Mod8 {
+                    destination,
+                    operand,
+                },
+                immediate(operand).map(|value| value % 8),
+            )
+        }
+        Op::Set { destination, value } => (Op::Set { destination, value }, Some(value)),
+        Op::Out(operand) => return (vec![Op::Out(substitute(operand))], known),
+        Op::Jnz(target) => {
+            return match known[A] {
+                Some(0) => (vec![], known),
+                Some(_) => (vec![Op::Jump(target)], known),
+                None => (vec![Op::Jnz(target)], known),
+            };
+        }
+        Op::Jump(target) => return (vec![Op::Jump(target)], known),
+    };
+
+    let destination = folded.destination().unwrap();
+    known[destination] = result;
+    match result {
+        Some(value) => (vec![Op::Set { destination, value }], known),
+        None => (vec![folded], known),
+    }
+}
+
+fn reachable_successors(op: &Op, index: usize, known: &Known) -> Vec<usize> {
+    match (op, known[A]) {
+        (Op::Jnz(_), Some(0)) => vec![index + 1],
+        (Op::Jnz(target), Some(_)) => vec![*target],
+        _ => op.successors(index),
+    }
+}
+
+/// Repeatedly drops writes to registers that no path reads before they are overwritten, since
+/// dropping one write can make the writes feeding it dead too.
+fn eliminate_dead_writes(mut ops: Vec<Op>) -> Vec<Op> {
+    loop {
+        // live[i] is the set of registers that may be read after op i runs
+        let mut live = vec![[false; 3]; ops.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for index in (0..ops.len()).rev() {
+                let mut live_out = [false; 3];
+                for successor in ops[index].successors(index) {
+                    let Some(successor_op) = ops.get(successor) else {
+                        continue;
+                    };
+                    let mut live_in = live[successor];
+                    if let Some(destination) = successor_op.destination() {
+                        live_in[destination] = false;
+                    }
+                    for register in successor_op.reads() {
+                        live_in[register] = true;
+                    }
+                    for register in 0..3 {
+                        live_out[register] |= live_in[register];
+                    }
+                }
+                if live_out != live[index] {
+                    live[index] = live_out;
+                    changed = true;
+                }
+            }
+        }
+
+        let dead = (0..ops.len())
+            .filter(|&index| matches!(ops[index].destination(), Some(destination) if !live[index][destination]))
+            .collect::<Vec<_>>();
+        if dead.is_empty() {
+            return ops;
+        }
+
+        // every op after a removed one moves back, so jumps past it do too
+        let kept = (0..ops.len())
+            .filter(|index| !dead.contains(index))
+            .collect::<Vec<_>>();
+        let new_index = |target: usize| kept.iter().filter(|&&index| index < target).count();
+        let remaining = kept.iter().map(|&index| ops[index]).collect();
+        ops = retarget(remaining, new_index);
+    }
+}
+
+fn retarget(ops: Vec<Op>, new_target: impl Fn(usize) -> usize) -> Vec<Op> {
+    ops.into_iter()
+        .map(|op| match op {
+            Op::Jnz(target) => Op::Jnz(new_target(target)),
+            Op::Jump(target) => Op::Jump(new_target(target)),
+            op => op,
+        })
+        .collect()
+}
@@ -0,0 +1,424 @@
+use super::{disassemble, parse_input, Computer, Instruction};
+use itertools::Itertools;
+use std::{
+    collections::BTreeSet,
+    fmt,
+    fs::read_to_string,
+    io::{self, BufRead, Write},
+};
+
+#[test]
+fn breakpoint_stops_before_instruction() {
+    let mut debugger = example_debugger();
+    debugger.add_breakpoint(2);
+
+    assert_eq!(StopReason::Breakpoint(2), debugger.resume());
+    assert_eq!(vec![5], debugger.computer().output);
+    assert_eq!(StopReason::Breakpoint(2), debugger.resume());
+    assert_eq!(vec![5, 7], debugger.computer().output);
+}
+
+#[test]
+fn watch_stops_on_register_change() {
+    let mut debugger = example_debugger();
+    debugger.watch(Register::A);
+
+    assert_eq!(
+        StopReason::Watch {
+            register: Register::A,
+            old: 2024,
+            new: 253,
+        },
+        debugger.resume()
+    );
+    assert_eq!(1, debugger.computer().instruction_pointer);
+}
+
+#[test]
+fn resume_runs_to_halt_and_traces() {
+    let mut debugger = example_debugger();
+
+    assert_eq!(StopReason::Halted, debugger.resume());
+    assert_eq!(vec![5, 7, 3, 0], debugger.computer().output);
+    assert_eq!(12, debugger.trace().len());
+    assert_eq!(
+        "0: adv 3    A=2024 B=0 C=0 -> A=253 B=0 C=0",
+        debugger.trace()[0].to_string()
+    );
+    assert_eq!(
+        "1: out A    A=253 B=0 C=0 -> A=253 B=0 C=0    out 5",
+        debugger.trace()[1].to_string()
+    );
+}
+
+#[test]
+fn repl_script() {
+    let script = "break 1\ncontinue\nregs\nstep\nout\nquit\n";
+    let mut output = vec![];
+    repl(example_debugger(), script.as_bytes(), &mut output).unwrap();
+
+    assert_eq!(
+        "\
+(d17) breakpoint at 1
+(d17) stopped at breakpoint 1: out A
+(d17) ip=1 A=253 B=0 C=0
+(d17) stepped to 2: jnz 0
+(d17) 5
+(d17) ",
+        String::from_utf8(output).unwrap()
+    );
+}
+
+#[cfg(test)]
+fn example_debugger() -> Debugger {
+    let (computer, instructions, _) = parse_input(include_str!("example2.txt"));
+    Debugger::new(computer, instructions)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Register {
+    A,
+    B,
+    C,
+}
+
+impl Register {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "a" | "A" => Some(Register::A),
+            "b" | "B" => Some(Register::B),
+            "c" | "C" => Some(Register::C),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register::A => write!(f, "A"),
+            Register::B => write!(f, "B"),
+            Register::C => write!(f, "C"),
+        }
+    }
+}
+
+impl Computer {
+    fn register(&self, register: Register) -> usize {
+        match register {
+            Register::A => self.register_a,
+            Register::B => self.register_b,
+            Register::C => self.register_c,
+        }
+    }
+
+    fn set_register(&mut self, register: Register, value: usize) {
+        match register {
+            Register::A => self.register_a = value,
+            Register::B => self.register_b = value,
+            Register::C => self.register_c = value,
+        }
+    }
+
+    fn registers(&self) -> [usize; 3] {
+        [self.register_a, self.register_b, self.register_c]
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StopReason {
+    /// Execution stopped before the instruction at this pointer.
+    Breakpoint(usize),
+    /// A watched register changed value on the last executed instruction.
+    Watch {
+        register: Register,
+        old: usize,
+        new: usize,
+    },
+    /// A single step finished without anything else to report.
+    Stepped,
+    /// The instruction pointer ran off the end of the program.
+    Halted,
+}
+
+/// One executed instruction, with the registers on either side of it.
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub instruction_pointer: usize,
+    pub mnemonic: String,
+    pub before: [usize; 3],
+    pub after: [usize; 3],
+    pub output: Option<usize>,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.before;
+        let [new_a, new_b, new_c] = self.after;
+        write!(
+            f,
+            "{}: {}    A={a} B={b} C={c} -> A={new_a} B={new_b} C={new_c}",
+            self.instruction_pointer, self.mnemonic
+        )?;
+        if let Some(output) = self.output {
+            write!(f, "    out {output}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a `Computer` one instruction at a time, stopping at breakpoints and on changes to watched
+/// registers, and keeping a trace of everything it executed.
+pub struct Debugger {
+    initial: Computer,
+    computer: Computer,
+    instructions: Vec<Instruction>,
+    breakpoints: BTreeSet<usize>,
+    watches: BTreeSet<Register>,
+    trace: Vec<TraceEntry>,
+}
+
+impl Debugger {
+    pub fn new(computer: Computer, instructions: Vec<Instruction>) -> Self {
+        Self {
+            initial: computer.clone(),
+            computer,
+            instructions,
+            breakpoints: BTreeSet::new(),
+            watches: BTreeSet::new(),
+            trace: vec![],
+        }
+    }
+
+    pub fn computer(&self) -> &Computer {
+        &self.computer
+    }
+
+    #[cfg(test)]
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn add_breakpoint(&mut self, instruction_pointer: usize) {
+        self.breakpoints.insert(instruction_pointer);
+    }
+
+    pub fn remove_breakpoint(&mut self, instruction_pointer: usize) -> bool {
+        self.breakpoints.remove(&instruction_pointer)
+    }
+
+    pub fn watch(&mut self, register: Register) {
+        self.watches.insert(register);
+    }
+
+    pub fn unwatch(&mut self, register: Register) -> bool {
+        self.watches.remove(&register)
+    }
+
+    pub fn set_register(&mut self, register: Register, value: usize) {
+        self.computer.set_register(register, value);
+    }
+
+    /// Puts the computer back in its starting state and clears the trace, keeping breakpoints,
+    /// watches and any registers changed with `set_register` before the first step.
+    pub fn reset(&mut self) {
+        self.computer = self.initial.clone();
+        self.trace.clear();
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.current_instruction().is_none()
+    }
+
+    pub fn current_instruction(&self) -> Option<&Instruction> {
+        self.instructions.get(self.computer.instruction_pointer)
+    }
+
+    /// Executes a single instruction, ignoring breakpoints.
+    pub fn step(&mut self) -> StopReason {
+        if self.trace.is_empty() {
+            // registers set before the first step are part of the starting state
+            self.initial = self.computer.clone();
+        }
+        let Some(instruction) = self.instructions.get(self.computer.instruction_pointer) else {
+            return StopReason::Halted;
+        };
+
+        let instruction_pointer = self.computer.instruction_pointer;
+        let before = self.computer.registers();
+        let output_len = self.computer.output.len();
+        self.computer.execute(instruction);
+
+        self.trace.push(TraceEntry {
+            instruction_pointer,
+            mnemonic: instruction.to_string(),
+            before,
+            after: self.computer.registers(),
+            output: self.computer.output.get(output_len).copied(),
+        });
+
+        let changed_watch = self.watches.iter().find_map(|&register| {
+            let old = before[register as usize];
+            let new = self.computer.register(register);
+            (old != new).then_some(StopReason::Watch { register, old, new })
+        });
+        changed_watch.unwrap_or(if self.is_halted() {
+            StopReason::Halted
+        } else {
+            StopReason::Stepped
+        })
+    }
+
+    /// Executes up to `count` instructions, ignoring breakpoints but stopping early on a watch or
+    /// halt.
+    pub fn step_by(&mut self, count: usize) -> StopReason {
+        let mut stop = StopReason::Stepped;
+        for _ in 0..count {
+            stop = self.step();
+            if stop != StopReason::Stepped {
+                break;
+            }
+        }
+        stop
+    }
+
+    /// Executes instructions until the program halts, a watched register changes, or the
+    /// instruction pointer reaches a breakpoint. The breakpoint at the current instruction, if
+    /// any, does not stop it again.
+    pub fn resume(&mut self) -> StopReason {
+        loop {
+            match self.step() {
+                StopReason::Stepped => {}
+                stop => return stop,
+            }
+            let instruction_pointer = self.computer.instruction_pointer;
+            if self.breakpoints.contains(&instruction_pointer) {
+                return StopReason::Breakpoint(instruction_pointer);
+            }
+        }
+    }
+
+    pub fn dump_trace(&self) -> String {
+        self.trace.iter().join("\n")
+    }
+}
+
+/// Loads a program file and runs the debugger REPL on stdin and stdout.
+pub fn run(program_file: &str) -> io::Result<()> {
+    let (computer, instructions, _) = parse_input(&read_to_string(program_file)?);
+    repl(
+        Debugger::new(computer, instructions),
+        io::stdin().lock(),
+        io::stdout(),
+    )
+}
+
+const HELP: &str = "\
+commands:
+  step [n]          execute n instructions (default 1), ignoring breakpoints
+  continue          run until a breakpoint, watch or halt
+  break <ip>        stop before the instruction at <ip>
+  delete <ip>       remove a breakpoint
+  watch <a|b|c>     stop whenever the register changes
+  unwatch <a|b|c>   stop watching a register
+  set <a|b|c> <n>   change a register
+  regs              show the instruction pointer and registers
+  out               show the output so far
+  trace             dump every executed instruction
+  disas             disassemble the program
+  reset             restart from the initial registers
+  quit";
+
+/// Reads debugger commands line by line from `input` until `quit` or the end of input.
+pub fn repl(mut debugger: Debugger, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    write!(output, "(d17) ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let words = line.split_whitespace().collect_vec();
+        let response = match words.as_slice() {
+            [] => None,
+            ["quit" | "q"] => break,
+            ["help" | "h"] => Some(HELP.to_string()),
+            ["step" | "s"] => Some(describe_stop(debugger.step(), &debugger)),
+            ["step" | "s", count] => Some(match count.parse() {
+                Ok(count) => describe_stop(debugger.step_by(count), &debugger),
+                Err(_) => format!("not a step count: {count}"),
+            }),
+            ["continue" | "c"] => Some(describe_stop(debugger.resume(), &debugger)),
+            ["break" | "b", pointer] => Some(match pointer.parse() {
+                Ok(pointer) => {
+                    debugger.add_breakpoint(pointer);
+                    format!("breakpoint at {pointer}")
+                }
+                Err(_) => format!("not an instruction pointer: {pointer}"),
+            }),
+            ["delete" | "d", pointer] => Some(match pointer.parse() {
+                Ok(pointer) if debugger.remove_breakpoint(pointer) => {
+                    format!("removed breakpoint at {pointer}")
+                }
+                _ => format!("no breakpoint at {pointer}"),
+            }),
+            ["watch" | "w", register] => Some(match Register::parse(register) {
+                Some(register) => {
+                    debugger.watch(register);
+                    format!("watching {register}")
+                }
+                None => format!("unknown register: {register}"),
+            }),
+            ["unwatch", register] => Some(match Register::parse(register) {
+                Some(register) if debugger.unwatch(register) => format!("unwatched {register}"),
+                _ => format!("not watching {register}"),
+            }),
+            ["set", register, value] => Some(match (Register::parse(register), value.parse()) {
+                (Some(register), Ok(value)) => {
+                    debugger.set_register(register, value);
+                    format!("{register}={value}")
+                }
+                _ => "usage: set <a|b|c> <value>".to_string(),
+            }),
+            ["regs" | "r"] => {
+                let [a, b, c] = debugger.computer().registers();
+                let pointer = debugger.computer().instruction_pointer;
+                Some(format!("ip={pointer} A={a} B={b} C={c}"))
+            }
+            ["out" | "o"] => Some(debugger.computer().output.iter().join(",")),
+            ["trace" | "t"] => Some(debugger.dump_trace()),
+            ["disas"] => Some(disassemble(debugger.instructions())),
+            ["reset"] => {
+                debugger.reset();
+                Some("reset".to_string())
+            }
+            _ => Some(format!("unknown command: {line} (try help)")),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{response}")?;
+        }
+        write!(output, "(d17) ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn describe_stop(stop: StopReason, debugger: &Debugger) -> String {
+    let pointer = debugger.computer().instruction_pointer;
+    let location = match debugger.current_instruction() {
+        Some(instruction) => format!("{pointer}: {instruction}"),
+        None => format!("{pointer}"),
+    };
+    match stop {
+        StopReason::Breakpoint(_) => format!("stopped at breakpoint {location}"),
+        StopReason::Watch { register, old, new } => {
+            format!("{register} changed {old} -> {new}, stopped at {location}")
+        }
+        StopReason::Stepped => format!("stepped to {location}"),
+        StopReason::Halted => format!(
+            "halted at {location} with output {}",
+            debugger.computer().output.iter().join(",")
+        ),
+    }
+}
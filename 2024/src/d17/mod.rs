@@ -1,7 +1,18 @@
+#[cfg(test)]
 use crate::common::test;
+#[cfg(test)]
+use compile::CompiledProgram;
 use itertools::Itertools;
 use regex::Regex;
+use std::fmt;
 
+#[cfg(test)]
+pub mod compile;
+pub mod debugger;
+#[cfg(test)]
+pub mod symbolic;
+
+#[cfg(test)]
 const MODULE: &str = module_path!();
 
 #[test]
@@ -24,64 +35,48 @@ fn p2_input() {
     test("input", MODULE, p2, 236548287712877);
 }
 
-fn p1(input: &str) -> String {
-    let (mut computer, instructions, _) = parse_input(input);
+#[test]
+fn disassemble_example() {
+    let (_, instructions, _) = parse_input(include_str!("example2.txt"));
+    assert_eq!("0: adv 3\n1: out A\n2: jnz 0", disassemble(&instructions));
+}
 
-    while let Some(instruction) = instructions.get(computer.instruction_pointer) {
-        computer.execute(instruction);
-    }
+#[cfg(test)]
+fn p1(input: &str) -> String {
+    let (computer, instructions, _) = parse_input(input);
+    let compiled = CompiledProgram::compile(&instructions, &computer);
 
-    computer.output.iter().map(|v| v.to_string()).join(",")
+    compiled
+        .run(computer.register_a)
+        .iter()
+        .map(|v| v.to_string())
+        .join(",")
 }
 
+#[cfg(test)]
 fn p2(input: &str) -> usize {
     let (computer, instructions, raw_instructions) = parse_input(input);
-    let raw_instructions_rev = raw_instructions.into_iter().rev().collect_vec();
+    let analysis = symbolic::analyze_loop(&instructions).unwrap_or_else(|err| panic!("{err}"));
+    let compiled = CompiledProgram::compile(&instructions, &computer);
 
-    value_for_register_a_that_outputs_self_instruction(
-        0,
-        &raw_instructions_rev,
-        &instructions,
-        &computer,
-    )
-    .unwrap()
-}
-
-fn value_for_register_a_that_outputs_self_instruction(
-    prev_a_register: usize,
-    raw_instructions: &[usize],
-    instructions: &[Instruction],
-    original_computer: &Computer,
-) -> Option<usize> {
-    let Some(target_output) = raw_instructions.first() else {
-        return Some(prev_a_register);
-    };
-    let curr_a_register_shifted_3_bits = prev_a_register * 8;
-    for lower_3_bits in 0_usize..8 {
-        let mut computer = original_computer.clone();
-        computer.register_a = curr_a_register_shifted_3_bits + lower_3_bits;
-        while let Some(instruction) = instructions.get(computer.instruction_pointer) {
-            computer.execute(instruction);
-            if !computer.output.is_empty() {
-                break;
-            }
-        }
-        if &computer.output[0] == target_output {
-            let maybe_register_a = value_for_register_a_that_outputs_self_instruction(
-                curr_a_register_shifted_3_bits + lower_3_bits,
-                &raw_instructions[1..],
-                instructions,
-                original_computer,
-            );
-            if maybe_register_a.is_some() {
-                return maybe_register_a;
-            }
-        }
-    }
-    None
+    // the analysis only models the loop body, so check each candidate by actually running it
+    analysis
+        .solve(&raw_instructions)
+        .into_iter()
+        .find(|&register_a| compiled.run(register_a) == raw_instructions)
+        .expect("some value of A should make the program print itself")
 }
 
-fn parse_input(input: &str) -> (Computer, Vec<Instruction>, Vec<usize>) {
+/// Lists each instruction as a mnemonic, prefixed with the instruction pointer that reaches it.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .enumerate()
+        .map(|(pointer, instruction)| format!("{pointer}: {instruction}"))
+        .join("\n")
+}
+
+pub fn parse_input(input: &str) -> (Computer, Vec<Instruction>, Vec<usize>) {
     let re = Regex::new(
         r"Register A: (\d+)
 Register B: (\d+)
@@ -119,7 +114,7 @@ Program: (.+)",
 }
 
 #[derive(Debug, Clone)]
-struct Computer {
+pub struct Computer {
     register_a: usize,
     register_b: usize,
     register_c: usize,
@@ -179,7 +174,7 @@ impl Computer {
 }
 
 #[derive(Debug)]
-enum Instruction {
+pub enum Instruction {
     Adv(ComboOperand),
     Bxl(LiteralOperand),
     Bst(ComboOperand),
@@ -206,11 +201,32 @@ impl Instruction {
     }
 }
 
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Adv(operand) => write!(f, "adv {operand}"),
+            Instruction::Bxl(operand) => write!(f, "bxl {operand}"),
+            Instruction::Bst(operand) => write!(f, "bst {operand}"),
+            Instruction::Jnz(operand) => write!(f, "jnz {operand}"),
+            Instruction::Bxc => write!(f, "bxc"),
+            Instruction::Out(operand) => write!(f, "out {operand}"),
+            Instruction::Bdv(operand) => write!(f, "bdv {operand}"),
+            Instruction::Cdv(operand) => write!(f, "cdv {operand}"),
+        }
+    }
+}
+
 #[derive(Debug)]
-struct LiteralOperand(usize);
+pub struct LiteralOperand(usize);
+
+impl fmt::Display for LiteralOperand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 #[derive(Debug)]
-enum ComboOperand {
+pub enum ComboOperand {
     Literal(usize),
     RegisterA,
     RegisterB,
@@ -228,3 +244,14 @@ impl ComboOperand {
         }
     }
 }
+
+impl fmt::Display for ComboOperand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComboOperand::Literal(value) => write!(f, "{value}"),
+            ComboOperand::RegisterA => write!(f, "A"),
+            ComboOperand::RegisterB => write!(f, "B"),
+            ComboOperand::RegisterC => write!(f, "C"),
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use super::{ComboOperand, Instruction, LiteralOperand};
+use itertools::Itertools;
+use std::{collections::BTreeSet, fmt};
+
+#[test]
+fn analyze_example() {
+    let (_, instructions, _) = super::parse_input(include_str!("example2.txt"));
+    let analysis = analyze_loop(&instructions).unwrap();
+
+    assert_eq!(3, analysis.shift);
+    assert_eq!("((A >> 3) % 8)", analysis.outputs.iter().join(", "));
+}
+
+#[test]
+fn solve_example_for_itself() {
+    let (_, instructions, raw_instructions) = super::parse_input(include_str!("example2.txt"));
+    let analysis = analyze_loop(&instructions).unwrap();
+
+    // the lowest 3 bits are shifted out before the first output, so they can be anything
+    assert_eq!(
+        (117440..117448).collect::<BTreeSet<_>>(),
+        analysis.solve(&raw_instructions)
+    );
+}
+
+#[test]
+fn solve_for_any_target() {
+    let (computer, instructions, _) = super::parse_input(include_str!("example.txt"));
+    let analysis = analyze_loop(&instructions).unwrap();
+    let solutions = analysis.solve(&[4, 6, 3, 5, 6, 3, 5, 2, 1, 0]);
+
+    assert!(solutions.contains(&computer.register_a));
+    for register_a in solutions {
+        let mut computer = computer.clone();
+        computer.register_a = register_a;
+        while let Some(instruction) = instructions.get(computer.instruction_pointer) {
+            computer.execute(instruction);
+        }
+        assert_eq!(vec![4, 6, 3, 5, 6, 3, 5, 2, 1, 0], computer.output);
+    }
+}
+
+#[test]
+fn rejects_output_depending_on_previous_iteration() {
+    // out B; adv 3; jnz 0 prints whatever B was left with
+    let instructions = [
+        Instruction::new(5, 5),
+        Instruction::new(0, 3),
+        Instruction::new(3, 0),
+    ];
+    assert!(matches!(
+        analyze_loop(&instructions),
+        Err(SymbolicError::DependsOnPreviousIteration(_))
+    ));
+}
+
+/// A bit-vector expression over the register values at the start of a loop iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    A,
+    B,
+    C,
+    Const(usize),
+    Xor(Box<Expr>, Box<Expr>),
+    Shr(Box<Expr>, Box<Expr>),
+    Mod8(Box<Expr>),
+}
+
+impl Expr {
+    fn xor(lhs: Expr, rhs: Expr) -> Expr {
+        match (lhs, rhs) {
+            (Expr::Const(lhs), Expr::Const(rhs)) => Expr::Const(lhs ^ rhs),
+            (expr, Expr::Const(0)) | (Expr::Const(0), expr) => expr,
+            (lhs, rhs) if lhs == rhs => Expr::Const(0),
+            (Expr::Xor(expr, inner), Expr::Const(outer)) if matches!(*inner, Expr::Const(_)) => {
+                let Expr::Const(inner) = *inner else {
+                    unreachable!()
+                };
+                Expr::xor(*expr, Expr::Const(inner ^ outer))
+            }
+            (lhs, rhs) => Expr::Xor(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    fn shr(value: Expr, amount: Expr) -> Expr {
+        match (value, amount) {
+            (Expr::Const(value), Expr::Const(amount)) => Expr::Const(shift_right(value, amount)),
+            (value, Expr::Const(0)) => value,
+            (Expr::Shr(value, inner), Expr::Const(outer)) if matches!(*inner, Expr::Const(_)) => {
+                let Expr::Const(inner) = *inner else {
+                    unreachable!()
+                };
+                Expr::Shr(value, Box::new(Expr::Const(inner + outer)))
+            }
+            (value, amount) => Expr::Shr(Box::new(value), Box::new(amount)),
+        }
+    }
+
+    fn mod8(value: Expr) -> Expr {
+        match value {
+            Expr::Const(value) => Expr::Const(value % 8),
+            Expr::Mod8(value) => Expr::Mod8(value),
+            value => Expr::Mod8(Box::new(value)),
+        }
+    }
+
+    /// Evaluates the expression with concrete starting values for the registers.
+    pub fn eval(&self, a: usize, b: usize, c: usize) -> usize {
+        match self {
+            Expr::A => a,
+            Expr::B => b,
+            Expr::C => c,
+            Expr::Const(value) => *value,
+            Expr::Xor(lhs, rhs) => lhs.eval(a, b, c) ^ rhs.eval(a, b, c),
+            Expr::Shr(value, amount) => shift_right(value.eval(a, b, c), amount.eval(a, b, c)),
+            Expr::Mod8(value) => value.eval(a, b, c) % 8,
+        }
+    }
+
+    fn reads_b_or_c(&self) -> bool {
+        match self {
+            Expr::B | Expr::C => true,
+            Expr::A | Expr::Const(_) => false,
+            Expr::Xor(lhs, rhs) | Expr::Shr(lhs, rhs) => lhs.reads_b_or_c() || rhs.reads_b_or_c(),
+            Expr::Mod8(value) => value.reads_b_or_c(),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::A => write!(f, "A"),
+            Expr::B => write!(f, "B"),
+            Expr::C => write!(f, "C"),
+            Expr::Const(value) => write!(f, "{value}"),
+            Expr::Xor(lhs, rhs) => write!(f, "({lhs} ^ {rhs})"),
+            Expr::Shr(value, amount) => write!(f, "({value} >> {amount})"),
+            Expr::Mod8(value) => write!(f, "({value} % 8)"),
+        }
+    }
+}
+
+/// `adv`, `bdv` and `cdv` divide by a power of two, which is a right shift that saturates to zero.
+fn shift_right(value: usize, amount: usize) -> usize {
+    u32::try_from(amount)
+        .ok()
+        .and_then(|amount| value.checked_shr(amount))
+        .unwrap_or(0)
+}
+
+#[derive(Debug)]
+pub enum SymbolicError {
+    /// The program has to end with `jnz 0` and contain no other jumps.
+    NotASingleLoop,
+    /// An output or the next value of A depends on B or C from the previous iteration.
+    DependsOnPreviousIteration(Expr),
+    /// A has to be shifted right by a constant number of bits every iteration.
+    NotShiftingA(Expr),
+    NoOutput,
+}
+
+impl fmt::Display for SymbolicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolicError::NotASingleLoop => {
+                write!(f, "program is not a single loop ending in jnz 0")
+            }
+            SymbolicError::DependsOnPreviousIteration(expr) => {
+                write!(f, "{expr} depends on the previous iteration's B or C")
+            }
+            SymbolicError::NotShiftingA(expr) => {
+                write!(f, "A becomes {expr} instead of a constant right shift")
+            }
+            SymbolicError::NoOutput => write!(f, "program never prints anything"),
+        }
+    }
+}
+
+/// What one pass through a program of the form `body; jnz 0` does, in terms of the value of A at
+/// the start of the pass.
+#[derive(Debug)]
+pub struct LoopAnalysis {
+    /// The values printed during a pass, in order.
+    pub outputs: Vec<Expr>,
+    /// How many bits A is shifted right by during a pass.
+    pub shift: usize,
+}
+
+/// Runs the loop body once with symbolic registers to derive its output function.
+pub fn analyze_loop(instructions: &[Instruction]) -> Result<LoopAnalysis, SymbolicError> {
+    let Some((Instruction::Jnz(LiteralOperand(0)), body)) = instructions.split_last() else {
+        return Err(SymbolicError::NotASingleLoop);
+    };
+
+    let (mut a, mut b, mut c) = (Expr::A, Expr::B, Expr::C);
+    let mut outputs = vec![];
+    for instruction in body {
+        let combo = |operand: &ComboOperand| match operand {
+            ComboOperand::Literal(value) => Expr::Const(*value),
+            ComboOperand::RegisterA => a.clone(),
+            ComboOperand::RegisterB => b.clone(),
+            ComboOperand::RegisterC => c.clone(),
+        };
+        match instruction {
+            Instruction::Adv(operand) => a = Expr::shr(a.clone(), combo(operand)),
+            Instruction::Bxl(LiteralOperand(value)) => b = Expr::xor(b, Expr::Const(*value)),
+            Instruction::Bst(operand) => b = Expr::mod8(combo(operand)),
+            Instruction::Jnz(_) => return Err(SymbolicError::NotASingleLoop),
+            Instruction::Bxc => b = Expr::xor(b, c.clone()),
+            Instruction::Out(operand) => outputs.push(Expr::mod8(combo(operand))),
+            Instruction::Bdv(operand) => b = Expr::shr(a.clone(), combo(operand)),
+            Instruction::Cdv(operand) => c = Expr::shr(a.clone(), combo(operand)),
+        }
+    }
+
+    if outputs.is_empty() {
+        return Err(SymbolicError::NoOutput);
+    }
+    if let Some(output) = outputs.iter().find(|output| output.reads_b_or_c()) {
+        return Err(SymbolicError::DependsOnPreviousIteration(output.clone()));
+    }
+    let shift = match &a {
+        Expr::Shr(value, amount) => match (value.as_ref(), amount.as_ref()) {
+            (Expr::A, Expr::Const(shift)) => *shift,
+            _ => return Err(SymbolicError::NotShiftingA(a)),
+        },
+        _ => return Err(SymbolicError::NotShiftingA(a)),
+    };
+
+    Ok(LoopAnalysis { outputs, shift })
+}
+
+impl LoopAnalysis {
+    /// Every starting value of A for which the program prints exactly `target`.
+    ///
+    /// Pass `i` sees `A >> (shift * i)`, so the outputs of the last pass only depend on the
+    /// highest bits of A. Working backwards from the last pass, each one adds `shift` new low bits
+    /// to every partial solution, and only the extensions that print the right values survive.
+    pub fn solve(&self, target: &[usize]) -> BTreeSet<usize> {
+        let per_pass = self.outputs.len();
+        if target.is_empty() || target.len() % per_pass != 0 {
+            return BTreeSet::new();
+        }
+        let passes = target.chunks(per_pass).collect_vec();
+
+        // before the last pass A has to be non-zero for `jnz` to have looped, unless it is the
+        // first pass
+        let mut partial_solutions = vec![0];
+        for (pass, expected) in passes.iter().enumerate().rev() {
+            partial_solutions = partial_solutions
+                .into_iter()
+                .flat_map(|higher_bits| {
+                    (0..1 << self.shift)
+                        .map(move |lower_bits| (higher_bits << self.shift) | lower_bits)
+                })
+                .filter(|&a| pass == 0 || pass + 1 < passes.len() || a != 0)
+                .filter(|&a| {
+                    self.outputs
+                        .iter()
+                        .map(|output| output.eval(a, 0, 0))
+                        .eq(expected.iter().copied())
+                })
+                .collect();
+        }
+
+        partial_solutions.into_iter().collect()
+    }
+}
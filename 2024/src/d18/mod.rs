@@ -1,6 +1,6 @@
 use crate::common::{get_cross_neighbors, test_with_params, Point2};
+use advent_common::search::{bfs, SearchState};
 use itertools::Itertools;
-use pathfinding::prelude::astar;
 use std::collections::HashSet;
 
 const MODULE: &str = module_path!();
@@ -58,30 +58,38 @@ fn length_of_shortest_path(
     max_y: isize,
     corrupted_tiles: &HashSet<Point2<isize>>,
 ) -> Option<usize> {
-    let x_range = 0..=max_x;
-    let y_range = 0..=max_y;
-    let found_path = astar(
-        &Point2 { x: 0_isize, y: 0 },
-        |curr| {
-            get_cross_neighbors(*curr)
-                .into_iter()
-                .filter_map(|neighbor| {
-                    if x_range.contains(&neighbor.x)
-                        && y_range.contains(&neighbor.y)
-                        && !corrupted_tiles.contains(&neighbor)
-                    {
-                        Some((neighbor, 1))
-                    } else {
-                        None
-                    }
-                })
-                .collect_vec()
-        },
-        |curr| curr.x.abs_diff(max_x) + curr.y.abs_diff(max_y),
-        |curr| curr.x == max_x && curr.y == max_y,
-    );
-
-    found_path.map(|(_, len)| len)
+    let memory = MemorySpace {
+        max_x,
+        max_y,
+        corrupted_tiles,
+    };
+    bfs(Point2 { x: 0_isize, y: 0 }, &memory).map(|path| path.cost)
+}
+
+struct MemorySpace<'a> {
+    max_x: isize,
+    max_y: isize,
+    corrupted_tiles: &'a HashSet<Point2<isize>>,
+}
+
+impl SearchState<MemorySpace<'_>> for Point2<isize> {
+    type Cost = usize;
+
+    fn successors(&self, memory: &MemorySpace) -> Vec<(Self, usize)> {
+        get_cross_neighbors(*self)
+            .into_iter()
+            .filter(|neighbor| {
+                (0..=memory.max_x).contains(&neighbor.x)
+                    && (0..=memory.max_y).contains(&neighbor.y)
+                    && !memory.corrupted_tiles.contains(neighbor)
+            })
+            .map(|neighbor| (neighbor, 1))
+            .collect_vec()
+    }
+
+    fn is_goal(&self, memory: &MemorySpace) -> bool {
+        self.x == memory.max_x && self.y == memory.max_y
+    }
 }
 
 fn parse_input(input: &str) -> Vec<Point2<isize>> {
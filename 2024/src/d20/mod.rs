@@ -1,8 +1,8 @@
 use crate::common::{
     get_cross_neighbors, manhattan_dist, parse_to_char_map, test_with_params, Point2,
 };
+use advent_common::search::{dijkstra_all, SearchState};
 use itertools::Itertools;
-use pathfinding::prelude::dijkstra_all;
 use std::collections::HashMap;
 
 const MODULE: &str = module_path!();
@@ -50,13 +50,12 @@ fn qty_shortcuts_saving_at_least(
     end: &Point2<isize>,
     grid: &Grid,
 ) -> usize {
-    let mut reachable_from_start = dijkstra_all(start, |curr| get_neighbors(curr, grid));
-    reachable_from_start.insert(*start, (*start, 0));
+    let reachable_from_start = dijkstra_all(*start, grid);
+    let reachable_from_end = dijkstra_all(*end, grid);
+    let dist_from_start = |pos| reachable_from_start.cost(pos).unwrap();
+    let dist_to_end = |pos| reachable_from_end.cost(pos).unwrap();
 
-    let mut reachable_from_end = dijkstra_all(end, |curr| get_neighbors(curr, grid));
-    reachable_from_end.insert(*end, (*end, 0));
-
-    let orig_dist = reachable_from_start[end].1;
+    let orig_dist = dist_from_start(end);
 
     grid.iter()
         .filter(|(_, v)| v != &&'#')
@@ -71,25 +70,33 @@ fn qty_shortcuts_saving_at_least(
         })
         .flatten()
         .filter(|(dist_12, k1, k2)| {
-            let dist_s12e = reachable_from_start[k1].1 + *dist_12 + reachable_from_end[k2].1;
+            let dist_s12e = dist_from_start(k1) + *dist_12 + dist_to_end(k2);
             dist_s12e + time_to_save <= orig_dist
         })
         .count()
 }
 
-fn get_neighbors(curr: &Point2<isize>, grid: &Grid) -> Vec<(Point2<isize>, usize)> {
-    get_cross_neighbors(*curr)
-        .into_iter()
-        .filter_map(|n| {
-            if let Some(c) = grid.get(&n)
-                && c != &'#'
-            {
-                Some((n, 1))
-            } else {
-                None
-            }
-        })
-        .collect_vec()
+impl SearchState<Grid> for Point2<isize> {
+    type Cost = usize;
+
+    fn successors(&self, grid: &Grid) -> Vec<(Self, usize)> {
+        get_cross_neighbors(*self)
+            .into_iter()
+            .filter_map(|n| {
+                if let Some(c) = grid.get(&n)
+                    && c != &'#'
+                {
+                    Some((n, 1))
+                } else {
+                    None
+                }
+            })
+            .collect_vec()
+    }
+
+    fn is_goal(&self, grid: &Grid) -> bool {
+        grid[self] == 'E'
+    }
 }
 
 type Grid = HashMap<Point2<isize>, char>;
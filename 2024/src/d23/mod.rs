@@ -1,7 +1,10 @@
 use crate::common::test;
+use advent_common::graph::{Edges, Graph};
 use itertools::Itertools;
+#[cfg(test)]
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use std::collections::{BTreeMap, BTreeSet};
+#[cfg(test)]
+use std::collections::BTreeSet;
 
 const MODULE: &str = module_path!();
 
@@ -30,54 +33,80 @@ fn p2_input() {
     );
 }
 
+#[test]
+fn bron_kerbosch_matches_levels() {
+    let input = bench_input();
+    let graph = parse_input(&input);
+    assert_eq!(graph.max_clique(), largest_clique_by_levels(&graph));
+}
+
+#[bench]
+fn bench_bron_kerbosch(bencher: &mut test::Bencher) {
+    let input = bench_input();
+    let graph = parse_input(&input);
+    bencher.iter(|| test::black_box(graph.max_clique()));
+}
+
+#[bench]
+fn bench_levels(bencher: &mut test::Bencher) {
+    let input = bench_input();
+    let graph = parse_input(&input);
+    bencher.iter(|| test::black_box(largest_clique_by_levels(&graph)));
+}
+
+/// A network shaped like the puzzle input: 520 computers with about 13 connections each, and one
+/// planted party of 13.
+#[cfg(test)]
+fn bench_input() -> String {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(23);
+    let mut names = ('a'..='z')
+        .cartesian_product('a'..='z')
+        .map(|(a, b)| format!("{a}{b}"))
+        .collect_vec();
+    names.shuffle(&mut rng);
+    names.truncate(520);
+
+    let random = (0..names.len())
+        .tuple_combinations()
+        .filter(|_| rng.gen_ratio(12, 519));
+    let party = (0..13).tuple_combinations();
+    random
+        .chain(party)
+        .map(|(a, b)| format!("{}-{}", names[a], names[b]))
+        .join("\n")
+}
+
 fn p1(input: &str) -> usize {
     let graph = parse_input(input);
 
-    let mut component_groups = vec![];
-    let mut visited = BTreeSet::new();
-    for (curr, neighbors) in &graph {
-        if visited.contains(curr) {
-            continue;
-        }
-        visited.insert(*curr);
-
-        for (a, b) in neighbors
-            .iter()
-            .filter(|n| !visited.contains(*n))
-            .tuple_combinations()
-        {
-            if !graph[a].contains(b) {
-                continue;
-            }
+    graph.cliques_with_prefix("t", 3).len()
+}
 
-            component_groups.push([*curr, *a, *b])
-        }
-    }
+fn p2(input: &str) -> String {
+    let graph = parse_input(input);
 
-    component_groups
+    graph
+        .max_clique()
         .iter()
-        .filter(|group| group.iter().any(|component| component.starts_with("t")))
-        .count()
+        .map(|node| graph.label(*node))
+        .sorted()
+        .join(",")
 }
 
-fn p2(input: &str) -> String {
-    let graph = parse_input(input);
+/// The largest clique found by growing every clique one node at a time until none can grow.
+#[cfg(test)]
+fn largest_clique_by_levels(graph: &Graph) -> Vec<usize> {
+    let neighbours = graph
+        .nodes()
+        .map(|node| graph.neighbours(node).iter().copied().collect())
+        .collect_vec();
 
-    let mut largest_cliques = graph
-        .keys()
-        .cloned()
-        .tuple_combinations()
-        .filter_map(|(a, b)| {
-            if graph[a].contains(b) {
-                Some(BTreeSet::from([a, b]))
-            } else {
-                None
-            }
-        })
-        .collect();
+    let mut largest_cliques = graph.edges().map(|(a, b)| BTreeSet::from([a, b])).collect();
 
     loop {
-        let cliques = k_sized_cliques(&largest_cliques, &graph);
+        let cliques = k_sized_cliques(&largest_cliques, &neighbours);
         if cliques.is_empty() {
             break;
         }
@@ -85,24 +114,24 @@ fn p2(input: &str) -> String {
     }
 
     largest_cliques
-        .iter()
+        .into_iter()
         .next()
         .unwrap()
-        .iter()
-        .sorted()
-        .join(",")
+        .into_iter()
+        .collect()
 }
 
-fn k_sized_cliques<'a>(
-    k_minus_1_cliques: &BTreeSet<BTreeSet<&'a str>>,
-    graph: &BTreeMap<&'a str, BTreeSet<&'a str>>,
-) -> BTreeSet<BTreeSet<&'a str>> {
+#[cfg(test)]
+fn k_sized_cliques(
+    k_minus_1_cliques: &BTreeSet<BTreeSet<usize>>,
+    neighbours: &[BTreeSet<usize>],
+) -> BTreeSet<BTreeSet<usize>> {
     k_minus_1_cliques
         .par_iter()
         .flat_map(|k_minus_1_clique| {
             let mut all_neighbors = k_minus_1_clique
                 .iter()
-                .map(|member| &graph[member])
+                .map(|member| &neighbours[*member])
                 .collect_vec();
             let mut k_clique_candidates = all_neighbors.pop().unwrap().clone();
             for neighbors in all_neighbors {
@@ -121,12 +150,6 @@ fn k_sized_cliques<'a>(
         .collect()
 }
 
-fn parse_input(input: &str) -> BTreeMap<&str, BTreeSet<&str>> {
-    let mut map: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
-    for line in input.lines() {
-        let (left, right) = line.split("-").collect_tuple().unwrap();
-        map.entry(left).or_default().insert(right);
-        map.entry(right).or_default().insert(left);
-    }
-    map
+fn parse_input(input: &str) -> Graph {
+    Graph::parse_pairs(input, "-", Edges::Undirected)
 }
@@ -0,0 +1,577 @@
+use super::{parse_input, LogicOperator};
+use advent_common::graph::{Edges, Graph};
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{fmt, fmt::Write};
+
+#[test]
+fn evaluates_example() {
+    let circuit = Circuit::parse(include_str!("example.txt"));
+    assert_eq!(Ok(2024), circuit.evaluate());
+}
+
+#[test]
+fn detects_cycles() {
+    let circuit = Circuit::parse("x00: 1\n\nx00 AND b -> a\na OR x00 -> b\nb XOR a -> z00");
+    assert_eq!(
+        Err(CircuitError::Cycle(vec![
+            String::from("a"),
+            String::from("b")
+        ])),
+        circuit.evaluate()
+    );
+}
+
+#[test]
+fn detects_undriven_wires() {
+    let circuit = Circuit::parse("x00: 1\n\nx00 AND q -> z00");
+    assert_eq!(
+        Err(CircuitError::UndrivenWire(String::from("q"))),
+        circuit.evaluate()
+    );
+}
+
+#[test]
+fn verifies_adder_exhaustively() {
+    let input = ripple_carry_adder(5, &[]);
+    let circuit = Circuit::parse(&input);
+    assert_eq!(Ok(()), circuit.verify_adder(&AdderTests::Exhaustive));
+
+    let input = ripple_carry_adder(5, &[("z02", "c02")]);
+    let circuit = Circuit::parse(&input);
+    let Err(AdderFault::WrongSum { bit, .. }) = circuit.verify_adder(&AdderTests::Exhaustive)
+    else {
+        panic!("swapped adder passed verification");
+    };
+    assert_eq!(2, bit);
+}
+
+#[test]
+fn reports_swap_that_creates_cycle() {
+    let input = ripple_carry_adder(5, &[("z03", "s03")]);
+    let circuit = Circuit::parse(&input);
+    assert!(matches!(
+        circuit.verify_adder(&AdderTests::Exhaustive),
+        Err(AdderFault::Circuit(CircuitError::Cycle(_)))
+    ));
+}
+
+#[test]
+fn finds_planted_swaps() {
+    let planted = [
+        ("z05", "c05"),
+        ("s11", "a11"),
+        ("z20", "t20"),
+        ("a31", "z31"),
+    ];
+    let input = ripple_carry_adder(45, &planted);
+    let circuit = Circuit::parse(&input);
+    let tests = AdderTests::random(64);
+
+    assert!(circuit.find_swaps(3, &tests).is_none());
+    let swaps = circuit.find_swaps(4, &tests).unwrap();
+    assert_eq!(
+        "a11,a31,c05,s11,t20,z05,z20,z31",
+        swaps.iter().flat_map(|(a, b)| [a, b]).sorted().join(",")
+    );
+}
+
+#[test]
+fn dot_snapshot() {
+    let input = ripple_carry_adder(2, &[]);
+    let circuit = Circuit::parse(&input);
+    insta::assert_snapshot!(circuit.to_dot());
+}
+
+/// A textbook ripple-carry adder over `bits` bit inputs, with the named gate outputs swapped.
+///
+/// Bit `i` computes `s = x ^ y`, `a = x & y`, `z = s ^ carry`, `t = s & carry` and
+/// `c = a | t`, except that bit 0 has no carry in and the last carry is the top z bit.
+#[cfg(test)]
+pub fn ripple_carry_adder(bits: usize, swaps: &[(&str, &str)]) -> String {
+    let mut gates = vec![
+        (
+            String::from("x00"),
+            "XOR",
+            String::from("y00"),
+            String::from("z00"),
+        ),
+        (
+            String::from("x00"),
+            "AND",
+            String::from("y00"),
+            String::from("c00"),
+        ),
+    ];
+    for i in 1..bits {
+        let carry_in = format!("c{:02}", i - 1);
+        let carry_out = if i + 1 == bits {
+            format!("z{bits:02}")
+        } else {
+            format!("c{i:02}")
+        };
+        let sum = format!("s{i:02}");
+        gates.extend([
+            (format!("x{i:02}"), "XOR", format!("y{i:02}"), sum.clone()),
+            (
+                format!("x{i:02}"),
+                "AND",
+                format!("y{i:02}"),
+                format!("a{i:02}"),
+            ),
+            (sum.clone(), "XOR", carry_in.clone(), format!("z{i:02}")),
+            (sum, "AND", carry_in, format!("t{i:02}")),
+            (format!("a{i:02}"), "OR", format!("t{i:02}"), carry_out),
+        ]);
+    }
+    for (a, b) in swaps {
+        for (_, _, _, output) in &mut gates {
+            if output == a {
+                *output = b.to_string();
+            } else if output == b {
+                *output = a.to_string();
+            }
+        }
+    }
+
+    let inputs = ["x", "y"]
+        .iter()
+        .flat_map(|prefix| (0..bits).map(move |i| format!("{prefix}{i:02}: 0")));
+    let gates = gates
+        .into_iter()
+        .map(|(left, operator, right, output)| format!("{left} {operator} {right} -> {output}"));
+    format!("{}\n\n{}", inputs.format("\n"), gates.format("\n"))
+}
+
+#[derive(Debug, Clone)]
+struct Gate {
+    left: usize,
+    right: usize,
+    operator: LogicOperator,
+    output: usize,
+}
+
+/// A gate network with its wires interned, so that it can be evaluated in topological order and
+/// rewired cheaply.
+#[derive(Debug, Clone)]
+pub struct Circuit<'a> {
+    /// Just the wires, interned in name order. The gates keep their own connections since the
+    /// search rewires them.
+    wires: Graph<'a>,
+    initial: Vec<(usize, usize)>,
+    gates: Vec<Gate>,
+    /// The gate writing to each wire, if any.
+    drivers: Vec<Option<usize>>,
+    /// The `x`, `y` and `z` wires, lowest bit first.
+    buses: [Vec<usize>; 3],
+    /// Wires that may be read without a gate driving them: initial values and the x and y bits.
+    is_source: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mark {
+    Unvisited,
+    OnPath,
+    Done,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CircuitError {
+    /// The wires on a loop of gates that all wait on each other, starting from the lowest name.
+    Cycle(Vec<String>),
+    /// A gate reads a wire that is neither an input nor the output of another gate.
+    UndrivenWire(String),
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitError::Cycle(wires) => write!(f, "gates form a cycle: {}", wires.join(" -> ")),
+            CircuitError::UndrivenWire(wire) => write!(f, "nothing drives wire {wire}"),
+        }
+    }
+}
+
+/// Which `x`/`y` pairs to check an adder with.
+#[derive(Debug)]
+pub enum AdderTests {
+    /// Every pair of inputs. Only practical for a handful of bits.
+    Exhaustive,
+    /// Every combination of `x`, `y` and carry at each bit, then `samples` random pairs.
+    Random { samples: usize, seed: u64 },
+}
+
+impl AdderTests {
+    pub fn random(samples: usize) -> Self {
+        AdderTests::Random { samples, seed: 24 }
+    }
+
+    fn cases(&self, bits: usize) -> Vec<(usize, usize)> {
+        match self {
+            AdderTests::Exhaustive => (0..1 << bits).cartesian_product(0..1 << bits).collect_vec(),
+            AdderTests::Random { samples, seed } => {
+                let mask = (1 << bits) - 1;
+                let per_bit = (0..bits).flat_map(|bit| {
+                    (0..8).map(move |pattern| {
+                        let carry = if bit > 0 {
+                            (pattern >> 2) << (bit - 1)
+                        } else {
+                            0
+                        };
+                        (
+                            ((pattern & 1) << bit) | carry,
+                            (((pattern >> 1) & 1) << bit) | carry,
+                        )
+                    })
+                });
+                let mut rng = StdRng::seed_from_u64(*seed);
+                let random =
+                    (0..*samples).map(|_| (rng.gen::<usize>() & mask, rng.gen::<usize>() & mask));
+                per_bit
+                    .chain([(mask, 1), (mask, mask)])
+                    .chain(random)
+                    .collect_vec()
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AdderFault {
+    Circuit(CircuitError),
+    /// `x + y` came out wrong, and `bit` is the lowest z bit that differs.
+    WrongSum {
+        bit: usize,
+        x: usize,
+        y: usize,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl<'a> Circuit<'a> {
+    pub fn parse(input: &'a str) -> Self {
+        let (initializer, gate_map) = parse_input(input);
+
+        let mut wires = Graph::new(Edges::Directed);
+        for wire in initializer
+            .keys()
+            .chain(gate_map.keys())
+            .chain(gate_map.values().flat_map(|gate| [&gate.left, &gate.right]))
+            .sorted()
+        {
+            wires.intern(wire);
+        }
+        let ids = |wire: &str| wires.id(wire).unwrap();
+
+        let gates = gate_map
+            .into_iter()
+            .sorted_by_key(|(output, _)| *output)
+            .map(|(output, gate)| Gate {
+                left: ids(gate.left),
+                right: ids(gate.right),
+                operator: gate.logic_operator,
+                output: ids(output),
+            })
+            .collect_vec();
+        let mut drivers = vec![None; wires.len()];
+        for (idx, gate) in gates.iter().enumerate() {
+            drivers[gate.output] = Some(idx);
+        }
+        let initial = initializer
+            .into_iter()
+            .map(|(wire, value)| (ids(wire), value))
+            .sorted()
+            .collect_vec();
+        let buses: [Vec<_>; 3] = ['x', 'y', 'z'].map(|prefix| {
+            (0..)
+                .map_while(|bit| wires.id(&format!("{prefix}{bit:02}")))
+                .collect()
+        });
+
+        let mut is_source = vec![false; wires.len()];
+        for wire in buses[0].iter().chain(&buses[1]) {
+            is_source[*wire] = true;
+        }
+        for (wire, _) in &initial {
+            is_source[*wire] = true;
+        }
+
+        Self {
+            wires,
+            initial,
+            gates,
+            drivers,
+            buses,
+            is_source,
+        }
+    }
+
+    /// The value of the z number once every gate has settled, starting from the initial values.
+    pub fn evaluate(&self) -> Result<usize, CircuitError> {
+        let order = self.topological_order()?;
+        let mut values = vec![0; self.wires.len()];
+        for &(wire, value) in &self.initial {
+            values[wire] = value as u64;
+        }
+        self.propagate(&order, &mut values);
+        Ok(Self::read_bus(&values, &self.buses[2], 0))
+    }
+
+    /// Gates ordered so that every gate comes after the gates driving its inputs.
+    fn topological_order(&self) -> Result<Vec<usize>, CircuitError> {
+        let mut marks = vec![Mark::Unvisited; self.gates.len()];
+        let mut order = Vec::with_capacity(self.gates.len());
+        for gate in 0..self.gates.len() {
+            self.visit(gate, &mut marks, &mut vec![], &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Depth-first search through the gates driving `gate`, adding each one to `order` once
+    /// everything it reads has been. Reaching a gate that is still on `path` closes a cycle.
+    fn visit(
+        &self,
+        gate: usize,
+        marks: &mut [Mark],
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), CircuitError> {
+        match marks[gate] {
+            Mark::Done => return Ok(()),
+            Mark::OnPath => {
+                let start = path.iter().position(|other| *other == gate).unwrap();
+                let mut cycle = path[start..]
+                    .iter()
+                    .rev()
+                    .map(|gate| self.wires.label(self.gates[*gate].output))
+                    .collect_vec();
+                let lowest = cycle.iter().position_min().unwrap();
+                cycle.rotate_left(lowest);
+                return Err(CircuitError::Cycle(
+                    cycle.into_iter().map(String::from).collect(),
+                ));
+            }
+            Mark::Unvisited => {}
+        }
+
+        marks[gate] = Mark::OnPath;
+        path.push(gate);
+        let Gate { left, right, .. } = self.gates[gate];
+        for input in [left, right] {
+            match self.drivers[input] {
+                Some(driver) => self.visit(driver, marks, path, order)?,
+                None if self.is_source[input] => {}
+                None => {
+                    return Err(CircuitError::UndrivenWire(
+                        self.wires.label(input).to_string(),
+                    ))
+                }
+            }
+        }
+        path.pop();
+        marks[gate] = Mark::Done;
+        order.push(gate);
+        Ok(())
+    }
+
+    /// Each wire holds 64 independent lanes, so one pass evaluates up to 64 sets of inputs.
+    fn propagate(&self, order: &[usize], values: &mut [u64]) {
+        for &idx in order {
+            let gate = &self.gates[idx];
+            let (left, right) = (values[gate.left], values[gate.right]);
+            values[gate.output] = match gate.operator {
+                LogicOperator::And => left & right,
+                LogicOperator::Or => left | right,
+                LogicOperator::Xor => left ^ right,
+            };
+        }
+    }
+
+    fn is_bus(&self, wire: usize) -> bool {
+        let name = self.wires.label(wire);
+        name.len() > 1
+            && name.starts_with(['x', 'y', 'z'])
+            && name[1..].bytes().all(|byte| byte.is_ascii_digit())
+    }
+
+    fn read_bus(values: &[u64], bus: &[usize], lane: usize) -> usize {
+        bus.iter()
+            .enumerate()
+            .map(|(bit, wire)| (((values[*wire] >> lane) & 1) as usize) << bit)
+            .sum()
+    }
+
+    /// Checks that z is x + y for the given pairs, reporting the failure at the lowest bit.
+    pub fn verify_adder(&self, tests: &AdderTests) -> Result<(), AdderFault> {
+        let order = self.topological_order().map_err(AdderFault::Circuit)?;
+        self.check_cases(&order, &tests.cases(self.buses[0].len()))
+    }
+
+    fn check_cases(&self, order: &[usize], cases: &[(usize, usize)]) -> Result<(), AdderFault> {
+        let [x_bus, y_bus, z_bus] = &self.buses;
+        let z_mask = (1 << z_bus.len()) - 1;
+
+        let mut fault = None;
+        for chunk in cases.chunks(64) {
+            let mut values = vec![0_u64; self.wires.len()];
+            for (lane, (x, y)) in chunk.iter().enumerate() {
+                for (bit, wire) in x_bus.iter().enumerate() {
+                    values[*wire] |= (((x >> bit) & 1) as u64) << lane;
+                }
+                for (bit, wire) in y_bus.iter().enumerate() {
+                    values[*wire] |= (((y >> bit) & 1) as u64) << lane;
+                }
+            }
+            self.propagate(order, &mut values);
+
+            for (lane, &(x, y)) in chunk.iter().enumerate() {
+                let expected = (x + y) & z_mask;
+                let actual = Self::read_bus(&values, z_bus, lane);
+                let bit = (expected ^ actual).trailing_zeros() as usize;
+                if expected != actual && fault.as_ref().map_or(true, |(lowest, _)| bit < *lowest) {
+                    fault = Some((
+                        bit,
+                        AdderFault::WrongSum {
+                            bit,
+                            x,
+                            y,
+                            expected,
+                            actual,
+                        },
+                    ));
+                }
+            }
+        }
+        fault.map_or(Ok(()), |(_, fault)| Err(fault))
+    }
+
+    /// Exchanges the outputs of the gates driving `a` and `b`.
+    pub fn swap_outputs(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.wires.id(a).unwrap(), self.wires.id(b).unwrap());
+        let (gate_a, gate_b) = (self.drivers[a].unwrap(), self.drivers[b].unwrap());
+        self.gates[gate_a].output = b;
+        self.gates[gate_b].output = a;
+        self.drivers.swap(a, b);
+    }
+
+    /// The highest x/y bit each wire depends on.
+    fn support(&self, order: &[usize]) -> Vec<Option<usize>> {
+        let mut support = (0..self.wires.len())
+            .map(|wire| {
+                let name = self.wires.label(wire);
+                (self.is_bus(wire) && !name.starts_with('z')).then(|| name[1..].parse().unwrap())
+            })
+            .collect_vec();
+        for &idx in order {
+            let gate = &self.gates[idx];
+            support[gate.output] = support[gate.left].max(support[gate.right]);
+        }
+        support
+    }
+
+    /// The fewest output swaps, up to `max_pairs`, after which the circuit passes `tests` as an
+    /// adder.
+    ///
+    /// Swaps are found one at a time from the lowest failing bit upwards: the next swap has to
+    /// involve a gate whose inputs reach no higher than one bit either side of it, and has to
+    /// fix that bit without breaking any below it. Otherwise any pair of gates is tried.
+    pub fn find_swaps(
+        &self,
+        max_pairs: usize,
+        tests: &AdderTests,
+    ) -> Option<Vec<(String, String)>> {
+        let cases = tests.cases(self.buses[0].len());
+        (0..=max_pairs).find_map(|pairs| self.clone().search_swaps(pairs, &cases))
+    }
+
+    fn search_swaps(
+        &mut self,
+        pairs: usize,
+        cases: &[(usize, usize)],
+    ) -> Option<Vec<(String, String)>> {
+        let order = self.topological_order().ok()?;
+        let bit = match self.check_cases(&order, cases) {
+            Ok(()) => return Some(vec![]),
+            Err(AdderFault::WrongSum { bit, .. }) if pairs > 0 => bit,
+            Err(_) => return None,
+        };
+
+        let support = self.support(&order);
+        let z = self.buses[2].get(bit).copied();
+        let outputs = self.gates.iter().map(|gate| gate.output).collect_vec();
+        let is_local = |wire: usize| {
+            Some(wire) == z
+                || support[wire].is_some_and(|support| support + 1 >= bit && support <= bit + 1)
+        };
+        // most swaps leave the failing bit broken, which the cases around it show quickly
+        let around_bit = cases
+            .iter()
+            .filter(|(x, y)| (x | y) >> (bit + 1) == 0 && (x | y) >> bit.saturating_sub(1) != 0)
+            .copied()
+            .collect_vec();
+        let fixes_bit = |circuit: &Self, cases: &[(usize, usize)]| {
+            let Ok(order) = circuit.topological_order() else {
+                return false;
+            };
+            [&around_bit[..], cases]
+                .iter()
+                .all(|cases| match circuit.check_cases(&order, cases) {
+                    Ok(()) => true,
+                    Err(AdderFault::WrongSum { bit: next, .. }) => next > bit,
+                    Err(AdderFault::Circuit(_)) => false,
+                })
+        };
+
+        for (i, &a) in outputs.iter().enumerate() {
+            if !is_local(a) {
+                continue;
+            }
+            for (j, &b) in outputs.iter().enumerate() {
+                if a == b || (is_local(b) && j < i) {
+                    continue;
+                }
+                let (a, b) = (self.wires.label(a), self.wires.label(b));
+                self.swap_outputs(a, b);
+                if fixes_bit(self, cases) {
+                    if let Some(mut swaps) = self.search_swaps(pairs - 1, cases) {
+                        swaps.insert(0, (a.to_string(), b.to_string()));
+                        return Some(swaps);
+                    }
+                }
+                self.swap_outputs(a, b);
+            }
+        }
+        None
+    }
+
+    /// A Graphviz digraph with a node per wire, labelled with the gate that drives it.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph circuit {\n    rankdir=LR;\n");
+        for (wire, name) in self.wires.labels().iter().enumerate() {
+            let shape = match name.chars().next() {
+                _ if !self.is_bus(wire) => "ellipse",
+                Some('z') => "doublecircle",
+                _ => "box",
+            };
+            let label = match self.drivers[wire] {
+                Some(gate) => format!("{name}\\n{}", self.gates[gate].operator),
+                None => name.to_string(),
+            };
+            writeln!(dot, "    {name} [label=\"{label}\", shape={shape}];").unwrap();
+        }
+        for gate in &self.gates {
+            for input in [gate.left, gate.right] {
+                writeln!(
+                    dot,
+                    "    {} -> {};",
+                    self.wires.label(input),
+                    self.wires.label(gate.output)
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
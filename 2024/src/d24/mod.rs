@@ -1,6 +1,11 @@
 use crate::common::{test, test_with_params};
+use advent_common::differential::{differential_test, iterations};
+use circuit::{AdderTests, Circuit};
 use itertools::Itertools;
-use std::collections::HashMap;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use std::{collections::HashMap, fmt};
+
+mod circuit;
 
 const MODULE: &str = module_path!();
 
@@ -25,70 +30,74 @@ fn p2_input() {
     );
 }
 
-fn p1(input: &str) -> usize {
-    let (initializer, graph, mut gate_map) = parse_input(input);
-    let mut outputs = HashMap::new();
-
-    for (key, value) in initializer {
-        trigger_switch(key, value, &mut outputs, &mut gate_map, &graph)
-    }
-
-    let mut ans = 0;
-    let mut mult = 1_usize;
-    for i in 0.. {
-        let key = format!("z{i:02}");
-        let Some(value) = outputs.get(key.as_str()) else {
-            break;
-        };
-
-        ans += value * mult;
-        mult *= 2;
-    }
-    ans
+#[test]
+fn p2_planted_swaps() {
+    let input = circuit::ripple_carry_adder(12, &[("z03", "c03"), ("s07", "a07")]);
+    assert_eq!("a07,c03,s07,z03", p2(&input, 2));
 }
 
-fn p2(input: &str, _pairs_needing_swapped: usize) -> String {
-    let (_, graph, gate_map) = parse_input(input);
+#[test]
+fn p1_differential() {
+    let result = differential_test(
+        iterations(1000),
+        8,
+        generate_input,
+        shrink_input,
+        |circuit| {
+            let input = circuit.to_string();
+            p1(&input) == p1_reference(&input)
+        },
+    );
+    assert_eq!(Ok(()), result.map_err(|circuit| circuit.to_string()));
+}
 
-    let mut swapped = vec![];
-    for i in 1.. {
-        let x = format!("x{i:02}");
-        let z = format!("z{i:02}");
+fn p1(input: &str) -> usize {
+    Circuit::parse(input).evaluate().unwrap()
+}
 
-        if !graph.contains_key(x.as_str()) {
-            break;
+/// Sweeps over every gate until no wire changes, rather than sorting the gates like `Circuit`.
+fn p1_reference(input: &str) -> usize {
+    let (initializer, gate_map) = parse_input(input);
+    let mut values = initializer;
+
+    loop {
+        let mut changed = false;
+        for (output, gate) in &gate_map {
+            if values.contains_key(output) {
+                continue;
+            }
+            if let (Some(left), Some(right)) = (values.get(gate.left), values.get(gate.right)) {
+                let value = match gate.logic_operator {
+                    LogicOperator::And => left & right,
+                    LogicOperator::Or => left | right,
+                    LogicOperator::Xor => left ^ right,
+                };
+                values.insert(output, value);
+                changed = true;
+            }
         }
-
-        let xor1 = graph[x.as_str()]
-            .iter()
-            .find(|out| gate_map[*out].logic_operator == LogicOperator::Xor)
-            .unwrap();
-
-        let and1 = graph[x.as_str()]
-            .iter()
-            .find(|out| gate_map[*out].logic_operator == LogicOperator::And)
-            .unwrap();
-
-        let Some(xor2) = graph[xor1].iter().find(|s| {
-            let gate = &gate_map[*s];
-            (gate.left == *xor1 || gate.right == *xor1) && gate.logic_operator == LogicOperator::Xor
-        }) else {
-            swapped.push(xor1.to_string());
-            swapped.push(and1.to_string());
-            continue;
-        };
-
-        if *xor2 != z {
-            swapped.push(z);
-            swapped.push(xor2.to_string());
+        if !changed {
+            break;
         }
     }
 
-    swapped.sort();
-    swapped.iter().join(",")
+    values
+        .iter()
+        .filter_map(|(wire, value)| {
+            let bit = wire.strip_prefix('z')?.parse::<u32>().ok()?;
+            Some(value << bit)
+        })
+        .sum()
 }
 
-fn parse_input(input: &str) -> (HashMap<&str, usize>, Graph, HashMap<&str, LogicGate>) {
+fn p2(input: &str, pairs_needing_swapped: usize) -> String {
+    let swaps = Circuit::parse(input)
+        .find_swaps(pairs_needing_swapped, &AdderTests::random(256))
+        .expect("no swaps within the limit make an adder");
+    swaps.iter().flat_map(|(a, b)| [a, b]).sorted().join(",")
+}
+
+fn parse_input(input: &str) -> (HashMap<&str, usize>, HashMap<&str, LogicGate>) {
     let (init_str, gate_str) = input.split("\n\n").collect_tuple().unwrap();
 
     let initializer = init_str
@@ -99,7 +108,6 @@ fn parse_input(input: &str) -> (HashMap<&str, usize>, Graph, HashMap<&str, Logic
         })
         .collect();
 
-    let mut graph = HashMap::new();
     let mut gate_map = HashMap::new();
 
     for line in gate_str.lines() {
@@ -113,74 +121,124 @@ fn parse_input(input: &str) -> (HashMap<&str, usize>, Graph, HashMap<&str, Logic
         let gate = LogicGate {
             logic_operator,
             left,
-            left_value: None,
             right,
-            right_value: None,
         };
-        graph.entry(left).or_insert(vec![]).push(result);
-        graph.entry(right).or_insert(vec![]).push(result);
         gate_map.insert(result, gate);
     }
-    (initializer, graph, gate_map)
-}
-
-fn trigger_switch<'b, 'a: 'b>(
-    source: &'a str,
-    value: usize,
-    outputs: &'b mut HashMap<&'a str, usize>,
-    gate_map: &'b mut HashMap<&'a str, LogicGate>,
-    graph: &Graph<'a>,
-) {
-    let Some(destinations) = graph.get(source) else {
-        return;
-    };
-    for dest in destinations {
-        let gate = gate_map.get_mut(dest).unwrap();
-        if gate.left == source {
-            gate.left_value = Some(value);
-        } else if gate.right == source {
-            gate.right_value = Some(value);
-        } else {
-            panic!();
-        }
-
-        if let Some(result) = gate.evaluate() {
-            outputs.insert(dest, result);
-            trigger_switch(dest, result, outputs, gate_map, graph)
-        }
-    }
+    (initializer, gate_map)
 }
 
-type Graph<'a> = HashMap<&'a str, Vec<&'a str>>;
-
 #[derive(Debug)]
 struct LogicGate<'a> {
     logic_operator: LogicOperator,
     left: &'a str,
-    left_value: Option<usize>,
     right: &'a str,
-    right_value: Option<usize>,
-}
-
-impl LogicGate<'_> {
-    fn evaluate(&self) -> Option<usize> {
-        if let Some(left) = self.left_value
-            && let Some(right) = self.right_value
-        {
-            Some(match self.logic_operator {
-                LogicOperator::And => left & right,
-                LogicOperator::Or => left | right,
-                LogicOperator::Xor => left ^ right,
-            })
-        } else {
-            None
-        }
-    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum LogicOperator {
     And,
     Or,
     Xor,
 }
+
+impl fmt::Display for LogicOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogicOperator::And => write!(f, "AND"),
+            LogicOperator::Or => write!(f, "OR"),
+            LogicOperator::Xor => write!(f, "XOR"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct GeneratedCircuit {
+    initializer: Vec<(String, usize)>,
+    gates: Vec<(String, &'static str, String, String)>,
+}
+
+impl std::fmt::Display for GeneratedCircuit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (wire, value) in &self.initializer {
+            writeln!(f, "{wire}: {value}")?;
+        }
+        writeln!(f)?;
+        for (left, operator, right, output) in &self.gates {
+            writeln!(f, "{left} {operator} {right} -> {output}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates `size` bit x/y inputs feeding a random acyclic network of gates, some of whose
+/// outputs are named `z00`, `z01`, ... in topological order.
+fn generate_input(rng: &mut StdRng, size: usize) -> GeneratedCircuit {
+    let size = size.clamp(1, 30);
+    let initializer = ["x", "y"]
+        .iter()
+        .flat_map(|prefix| (0..size).map(move |i| format!("{prefix}{i:02}")))
+        .map(|wire| (wire, rng.gen_range(0..2)))
+        .collect_vec();
+
+    let num_gates = size * 3;
+    let num_outputs = size + 1;
+    let mut is_output = vec![true; num_outputs];
+    is_output.resize(num_gates, false);
+    is_output.shuffle(rng);
+
+    let mut wires = initializer
+        .iter()
+        .map(|(wire, _)| wire.clone())
+        .collect_vec();
+    let mut gates = vec![];
+    let mut z_idx = 0;
+    for (gate_idx, is_output) in is_output.into_iter().enumerate() {
+        let output = if is_output {
+            z_idx += 1;
+            format!("z{:02}", z_idx - 1)
+        } else {
+            format!("g{gate_idx:02}")
+        };
+        let (left, right) = wires
+            .choose_multiple(rng, 2)
+            .cloned()
+            .collect_tuple()
+            .unwrap();
+        let operator = *["AND", "OR", "XOR"].choose(rng).unwrap();
+        gates.push((left, operator, right, output.clone()));
+        wires.push(output);
+    }
+    gates.shuffle(rng);
+
+    GeneratedCircuit { initializer, gates }
+}
+
+/// Candidate smaller failures: any set input bit cleared, or any gate removed whose output is
+/// neither read by another gate nor part of the z number.
+fn shrink_input(circuit: &GeneratedCircuit) -> Vec<GeneratedCircuit> {
+    let cleared_bits = (0..circuit.initializer.len())
+        .filter(|idx| circuit.initializer[*idx].1 == 1)
+        .map(|idx| {
+            let mut smaller = circuit.clone();
+            smaller.initializer[idx].1 = 0;
+            smaller
+        });
+
+    let removed_gates = (0..circuit.gates.len())
+        .filter(|idx| {
+            let output = &circuit.gates[*idx].3;
+            !output.starts_with('z')
+                && circuit
+                    .gates
+                    .iter()
+                    .all(|(left, _, right, _)| left != output && right != output)
+        })
+        .map(|idx| {
+            let mut smaller = circuit.clone();
+            smaller.gates.remove(idx);
+            smaller
+        });
+
+    cleared_bits.chain(removed_gates).collect_vec()
+}
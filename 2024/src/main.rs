@@ -1,9 +1,14 @@
 #![feature(let_chains)]
 #![feature(hash_extract_if)]
 #![feature(extract_if)]
+#![feature(test)]
+
+use std::env;
 
 pub mod common;
 #[cfg(test)]
+extern crate test;
+#[cfg(test)]
 mod d01;
 #[cfg(test)]
 mod d02;
@@ -35,7 +40,6 @@ mod d14;
 mod d15;
 #[cfg(test)]
 mod d16;
-#[cfg(test)]
 mod d17;
 #[cfg(test)]
 mod d18;
@@ -55,5 +59,9 @@ mod d24;
 mod d25;
 
 fn main() {
-    println!("Advent of Code 2024");
+    let args = env::args().collect::<Vec<_>>();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[1..] {
+        ["debug-d17", program_file] => d17::debugger::run(program_file).unwrap(),
+        _ => println!("Advent of Code 2024"),
+    }
 }
@@ -1,4 +1,5 @@
 use crate::common::Direction::{Down, Left, Right, Up};
+use advent_common::timing;
 use itertools::{Itertools, traits};
 use num::{Integer, integer::gcd};
 use num_traits::Num;
@@ -11,7 +12,7 @@ use std::{
     hash::Hash,
     ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
     str::pattern::Pattern,
-    time,
+    thread, time,
 };
 
 pub const DOWN_USIZE: Vector2<usize> = Vector2::<usize> { x: 0, y: 1 };
@@ -59,8 +60,19 @@ pub fn test_with_params<P, T: Debug + Eq, F: Fn(&str, P) -> T>(
     };
 
     println!("Elapsed: {time} {units}");
+
+    // the test harness names each thread after its test, which tells p1 and p2
+    // apart
+    let test_name = thread::current()
+        .name()
+        .map_or_else(|| format!("{module_name}::{file_name}"), str::to_string);
+    timing::record(&manifest_dir, test_name, elapsed, TIME_BUDGETS);
 }
 
+/// Time budgets in milliseconds for the days known to be slow, by test name or
+/// by day, for [`timing::record`]. Everything else gets a second.
+const TIME_BUDGETS: &[(&str, u64)] = &[];
+
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug, Ord, PartialOrd)]
 pub struct Point2<T> {
     pub(crate) x: T,
@@ -1,5 +1,8 @@
 use crate::common::test;
+use advent_common::differential::{differential_test, iterations};
 use itertools::Itertools;
+use rand::{Rng, rngs::StdRng};
+use std::collections::HashSet;
 
 const MODULE: &str = module_path!();
 
@@ -23,6 +26,22 @@ fn p2_input() {
     test("input", MODULE, p2, 352340558684863);
 }
 
+#[test]
+fn differential() {
+    let result = differential_test(
+        iterations(1000),
+        10,
+        generate_input,
+        shrink_input,
+        |input| {
+            let input = to_input_string(input);
+            p1(&input) == p1_reference(&input)
+                && p2(&input) == p2_reference(&input)
+        },
+    );
+    assert_eq!(Ok(()), result.map_err(|input| to_input_string(&input)));
+}
+
 fn p1(input: &str) -> usize {
     let (ranges_str, ingredients_str) =
         input.split("\n\n").collect_tuple().unwrap();
@@ -81,3 +100,105 @@ fn p2(input: &str) -> usize {
         .map(|(start, end)| end - start + 1)
         .sum()
 }
+
+type Ranges = Vec<(usize, usize)>;
+
+fn parse_reference(input: &str) -> (Ranges, Vec<usize>) {
+    let (ranges_str, ingredients_str) =
+        input.split("\n\n").collect_tuple().unwrap();
+    let ranges = ranges_str
+        .lines()
+        .map(|line| {
+            line.split('-')
+                .map(|num| num.parse::<usize>().unwrap())
+                .collect_tuple()
+                .unwrap()
+        })
+        .collect_vec();
+    let ingredients = ingredients_str
+        .lines()
+        .map(|line| line.parse::<usize>().unwrap())
+        .collect_vec();
+    (ranges, ingredients)
+}
+
+fn p1_reference(input: &str) -> usize {
+    let (ranges, ingredients) = parse_reference(input);
+    ingredients
+        .iter()
+        .filter(|ingredient| {
+            ranges
+                .iter()
+                .any(|(start, end)| start <= *ingredient && *ingredient <= end)
+        })
+        .count()
+}
+
+/// Enumerates every fresh ID, so only usable on small generated ranges.
+fn p2_reference(input: &str) -> usize {
+    let (ranges, _) = parse_reference(input);
+    ranges
+        .iter()
+        .flat_map(|(start, end)| *start..=*end)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Generates `size` ranges and `size` ingredient IDs drawn from `1..=size * 10`
+/// so that ranges frequently overlap, touch or nest.
+fn generate_input(rng: &mut StdRng, size: usize) -> (Ranges, Vec<usize>) {
+    let max_id = size.max(1) * 10;
+    let ranges = (0..size.max(1))
+        .map(|_| {
+            let start = rng.gen_range(1..=max_id);
+            let end = rng.gen_range(start..=(start + size).min(max_id));
+            (start, end)
+        })
+        .collect_vec();
+    let ingredients = (0..size.max(1))
+        .map(|_| rng.gen_range(1..=max_id))
+        .collect_vec();
+    (ranges, ingredients)
+}
+
+/// Candidate smaller failures: drop a range or an ingredient (keeping at least
+/// one of each), or pull in either end of a range.
+fn shrink_input(
+    (ranges, ingredients): &(Ranges, Vec<usize>),
+) -> Vec<(Ranges, Vec<usize>)> {
+    let mut candidates = vec![];
+    for idx in 0..ranges.len() {
+        let (start, end) = ranges[idx];
+        if ranges.len() > 1 {
+            let mut smaller = ranges.clone();
+            smaller.remove(idx);
+            candidates.push((smaller, ingredients.clone()));
+        }
+        if start < end {
+            for narrowed in [(start + 1, end), (start, end - 1)] {
+                let mut smaller = ranges.clone();
+                smaller[idx] = narrowed;
+                candidates.push((smaller, ingredients.clone()));
+            }
+        }
+    }
+    for idx in 0..ingredients.len() {
+        if ingredients.len() > 1 {
+            let mut smaller = ingredients.clone();
+            smaller.remove(idx);
+            candidates.push((ranges.clone(), smaller));
+        }
+    }
+    candidates
+}
+
+fn to_input_string((ranges, ingredients): &(Ranges, Vec<usize>)) -> String {
+    format!(
+        "{}\n\n{}",
+        ranges
+            .iter()
+            .map(|(start, end)| format!("{start}-{end}"))
+            .join("\n"),
+        ingredients.iter().join("\n")
+    )
+}
@@ -1,4 +1,4 @@
-use crate::common::test;
+use crate::common::{Point2, test};
 use itertools::Itertools;
 
 const MODULE: &str = module_path!();
@@ -8,6 +8,32 @@ fn p1_input() {
     test("input", MODULE, p1, 476);
 }
 
+#[test]
+#[ignore = "slow reference solver, run with --ignored"]
+fn p1_reference_input() {
+    test("input", MODULE, p1_reference, 476);
+}
+
+#[test]
+#[ignore = "slow reference solver, run with --ignored"]
+fn p1_reference_example() {
+    test("example", MODULE, p1_reference, 2);
+}
+
+#[test]
+fn p1_reference_agrees() {
+    let input = include_str!("example.txt");
+    let first_regions = &input[..input.trim_end().rfind('\n').unwrap()];
+    assert_eq!(2, p1_reference(first_regions));
+    assert_eq!(2, p1(first_regions));
+
+    // the last example region has the area for its presents, but they cannot
+    // be packed into it, so the area check counts one region too many. Every
+    // region in the real input has either room to spare or too little area,
+    // which is why p1_reference_input still agrees.
+    assert_eq!(3, p1(input));
+}
+
 fn p1(input: &str) -> usize {
     let inputs = input.split("\n\n").collect_vec();
     let present_sizes = inputs[..=5]
@@ -19,6 +45,7 @@ fn p1(input: &str) -> usize {
     // each present, they wouldn't actually fit in the required area even if you broke apart each present into
     // independent blocks. the remaining input lines leave a lot of "wiggle room", leading me to believe that there
     // are a lot of valid arrangements (but we only need to know if there is at least 1).
+    // the last example region is not like that, so this gets the example wrong (see p1_reference_agrees).
     inputs[6]
         .lines()
         .filter(|line| {
@@ -44,3 +71,159 @@ fn p1(input: &str) -> usize {
         })
         .count()
 }
+
+/// Packs the presents into each region for real, trying every orientation of
+/// every remaining present at the first undecided cell. Exact, but exponential.
+fn p1_reference(input: &str) -> usize {
+    let inputs = input.split("\n\n").collect_vec();
+    let (regions_str, presents_strs) = inputs.split_last().unwrap();
+    let presents = presents_strs
+        .iter()
+        .map(|present| orientations(&parse_present(present)))
+        .collect_vec();
+    let present_sizes = presents.iter().map(|p| p[0].len()).collect_vec();
+
+    regions_str
+        .lines()
+        .filter(|line| {
+            let (dims_str, counts_str) =
+                line.split(':').collect_tuple().unwrap();
+            let (width, height) = dims_str
+                .split('x')
+                .map(|dim| dim.parse::<usize>().unwrap())
+                .collect_tuple()
+                .unwrap();
+            let mut present_counts = counts_str
+                .split_whitespace()
+                .map(|count| count.parse::<usize>().unwrap())
+                .collect_vec();
+
+            let needed_area = present_sizes
+                .iter()
+                .zip(&present_counts)
+                .map(|(size, count)| size * count)
+                .sum::<usize>();
+            let Some(slack) = (width * height).checked_sub(needed_area) else {
+                return false;
+            };
+
+            let mut region = Region {
+                width,
+                filled: vec![false; width * height],
+            };
+            region.pack(0, slack, &mut present_counts, &presents)
+        })
+        .count()
+}
+
+type Present = Vec<Point2<isize>>;
+
+fn parse_present(present: &str) -> Present {
+    present
+        .lines()
+        .skip(1)
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars().enumerate().filter(|(_, c)| c == &'#').map(
+                move |(x, _)| Point2 {
+                    x: x as isize,
+                    y: y as isize,
+                },
+            )
+        })
+        .collect()
+}
+
+/// All distinct rotations and reflections, each normalized to start at 0,0 and
+/// sorted so that the first cell is the topmost, then leftmost.
+fn orientations(present: &Present) -> Vec<Present> {
+    let mut current = present.clone();
+    let mut all = vec![];
+    for _ in 0..4 {
+        current = current.iter().map(|p| Point2 { x: -p.y, y: p.x }).collect();
+        let reflected =
+            current.iter().map(|p| Point2 { x: -p.x, y: p.y }).collect();
+        all.push(normalized(&current));
+        all.push(normalized(&reflected));
+    }
+    all.into_iter().unique().collect()
+}
+
+fn normalized(present: &Present) -> Present {
+    let min_x = present.iter().map(|p| p.x).min().unwrap();
+    let min_y = present.iter().map(|p| p.y).min().unwrap();
+    present
+        .iter()
+        .map(|p| Point2 {
+            x: p.x - min_x,
+            y: p.y - min_y,
+        })
+        .sorted_by_key(|p| (p.y, p.x))
+        .collect()
+}
+
+struct Region {
+    width: usize,
+    filled: Vec<bool>,
+}
+
+impl Region {
+    fn pack(
+        &mut self,
+        from: usize,
+        slack: usize,
+        present_counts: &mut [usize],
+        presents: &[Vec<Present>],
+    ) -> bool {
+        if present_counts.iter().all(|count| *count == 0) {
+            return true;
+        }
+        let Some(idx) =
+            (from..self.filled.len()).find(|idx| !self.filled[*idx])
+        else {
+            return false;
+        };
+        let anchor = Point2 {
+            x: (idx % self.width) as isize,
+            y: (idx / self.width) as isize,
+        };
+
+        for (present_idx, orientations) in presents.iter().enumerate() {
+            if present_counts[present_idx] == 0 {
+                continue;
+            }
+            for orientation in orientations {
+                // line the first cell of the present up with the empty cell
+                let offset = anchor - orientation[0];
+                let Some(cells) = orientation
+                    .iter()
+                    .map(|cell| self.empty_idx(*cell + offset))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    continue;
+                };
+
+                cells.iter().for_each(|cell| self.filled[*cell] = true);
+                present_counts[present_idx] -= 1;
+                let packed =
+                    self.pack(idx + 1, slack, present_counts, presents);
+                present_counts[present_idx] += 1;
+                cells.iter().for_each(|cell| self.filled[*cell] = false);
+                if packed {
+                    return true;
+                }
+            }
+        }
+
+        // otherwise, this cell stays empty for good
+        slack > 0 && self.pack(idx + 1, slack - 1, present_counts, presents)
+    }
+
+    fn empty_idx(&self, point: Point2<isize>) -> Option<usize> {
+        let height = self.filled.len() / self.width;
+        let x = usize::try_from(point.x).ok().filter(|x| *x < self.width)?;
+        let y = usize::try_from(point.y).ok().filter(|y| *y < height)?;
+        let idx = y * self.width + x;
+        (!self.filled[idx]).then_some(idx)
+    }
+}
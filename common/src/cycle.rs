@@ -0,0 +1,153 @@
+/// After `start` steps a sequence of states is back in a state it was in, and from then on it
+/// repeats every `length` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Period {
+    /// The fewest steps that end in the same state as `steps` steps do.
+    pub fn equivalent(&self, steps: usize) -> usize {
+        if steps < self.start {
+            steps
+        } else {
+            self.start + (steps - self.start) % self.length
+        }
+    }
+}
+
+/// Where stepping from `initial` starts going round in circles, comparing whole states.
+pub fn find_cycle<S: Clone + Eq>(initial: &S, step: impl FnMut(&mut S)) -> Period {
+    find_cycle_by_key(initial, step, S::clone)
+}
+
+/// Where stepping from `initial` starts going round in circles, comparing only what `key` picks
+/// out of each state. States never have to be kept, so large ones are only cloned to restart.
+pub fn find_cycle_by_key<S: Clone, K: Eq>(
+    initial: &S,
+    step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+) -> Period {
+    find_cycle_within(initial, step, key, usize::MAX).unwrap()
+}
+
+/// [`find_cycle_by_key`], giving up if no key has come round again after `budget` steps.
+///
+/// This is Brent's algorithm: a hare runs ahead in stretches of doubling length and a tortoise
+/// waits at the start of each stretch, so the hare catches it once a stretch covers the whole
+/// loop. That gives the length, and two walkers started that far apart meet at the start.
+pub fn find_cycle_within<S: Clone, K: Eq>(
+    initial: &S,
+    mut step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+    budget: usize,
+) -> Option<Period> {
+    let mut hare = initial.clone();
+    let mut tortoise = key(&hare);
+    let (mut stretch, mut length) = (1, 0);
+    for _ in 0..budget {
+        step(&mut hare);
+        length += 1;
+        let hare_key = key(&hare);
+        if hare_key == tortoise {
+            let mut behind = initial.clone();
+            let mut ahead = initial.clone();
+            (0..length).for_each(|_| step(&mut ahead));
+            let mut start = 0;
+            while key(&behind) != key(&ahead) {
+                step(&mut behind);
+                step(&mut ahead);
+                start += 1;
+            }
+            return Some(Period { start, length });
+        }
+        if length == stretch {
+            tortoise = hare_key;
+            stretch *= 2;
+            length = 0;
+        }
+    }
+    None
+}
+
+/// The state after `steps` steps from `initial`, skipping every full time round the cycle.
+pub fn state_after<S: Clone + Eq>(initial: &S, steps: usize, step: impl FnMut(&mut S)) -> S {
+    state_after_by_key(initial, steps, step, S::clone)
+}
+
+/// [`state_after`], comparing states by `key` as in [`find_cycle_by_key`].
+pub fn state_after_by_key<S: Clone, K: Eq>(
+    initial: &S,
+    steps: usize,
+    mut step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+) -> S {
+    let period = find_cycle_by_key(initial, &mut step, key);
+    let mut state = initial.clone();
+    (0..period.equivalent(steps)).for_each(|_| step(&mut state));
+    state
+}
+
+#[cfg(test)]
+fn period_by_remembering<S: Clone, K: Eq + std::hash::Hash>(
+    initial: &S,
+    mut step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+) -> Period {
+    let mut seen = std::collections::HashMap::new();
+    let mut state = initial.clone();
+    for steps in 0.. {
+        if let Some(start) = seen.insert(key(&state), steps) {
+            return Period {
+                start,
+                length: steps - start,
+            };
+        }
+        step(&mut state);
+    }
+    unreachable!()
+}
+
+#[test]
+fn test_find_cycle_matches_remembering() {
+    for modulus in 1..60_u64 {
+        for initial in 0..modulus {
+            let step = |x: &mut u64| *x = (*x * *x + 3) % modulus;
+            assert_eq!(
+                period_by_remembering(&initial, step, |x| *x),
+                find_cycle(&initial, step),
+                "x -> x * x + 3 mod {modulus}, from {initial}",
+            );
+        }
+    }
+}
+
+#[test]
+fn test_find_cycle_by_key() {
+    // the counter never repeats, but after a run-up of 3 the key goes round every 7 steps
+    let step = |count: &mut usize| *count += 1;
+    let key = |count: &usize| (*count).min(3) + count.saturating_sub(3) % 7;
+    let period = Period {
+        start: 3,
+        length: 7,
+    };
+    assert_eq!(period, find_cycle_by_key(&0, step, key));
+    assert_eq!(None, find_cycle_within(&0, step, key, 9));
+    assert_eq!(Some(period), find_cycle_within(&0, step, key, 100));
+}
+
+#[test]
+fn test_state_after() {
+    let step = |x: &mut u64| *x = (*x * *x + 3) % 1000;
+    for steps in [0, 1, 5, 50, 1000] {
+        let mut expected = 7;
+        (0..steps).for_each(|_| step(&mut expected));
+        assert_eq!(expected, state_after(&7, steps, step));
+    }
+    let period = find_cycle(&7, step);
+    assert_eq!(
+        state_after(&7, period.equivalent(1_000_000_000), step),
+        state_after(&7, 1_000_000_000, step)
+    );
+}
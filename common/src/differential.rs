@@ -0,0 +1,76 @@
+use rand::{rngs::StdRng, SeedableRng};
+use std::env;
+
+/// Cross-checks a solver against a brute-force reference on generated inputs.
+///
+/// Each of `iterations` seeded inputs is passed to `agrees`. On the first disagreement, the
+/// input is greedily shrunk with `shrink` and the smallest failing input found is returned.
+pub fn differential_test<I: Clone>(
+    iterations: usize,
+    size: usize,
+    generate: impl Fn(&mut StdRng, usize) -> I,
+    shrink: impl Fn(&I) -> Vec<I>,
+    agrees: impl Fn(&I) -> bool,
+) -> Result<(), I> {
+    for seed in 0..iterations as u64 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let input = generate(&mut rng, size);
+        if !agrees(&input) {
+            return Err(shrink_failure(input, &shrink, &agrees));
+        }
+    }
+    Ok(())
+}
+
+/// How many inputs a differential test should try: `AOC_DIFFERENTIAL_ITERATIONS` if it is set,
+/// so that a long run can be asked for without a code change, and `default` otherwise.
+pub fn iterations(default: usize) -> usize {
+    env::var("AOC_DIFFERENTIAL_ITERATIONS")
+        .ok()
+        .and_then(|iterations| iterations.parse().ok())
+        .unwrap_or(default)
+}
+
+fn shrink_failure<I: Clone>(
+    mut failing: I,
+    shrink: impl Fn(&I) -> Vec<I>,
+    agrees: impl Fn(&I) -> bool,
+) -> I {
+    // keep taking the first smaller candidate that still fails until none do
+    while let Some(smaller) = shrink(&failing)
+        .into_iter()
+        .find(|candidate| !agrees(candidate))
+    {
+        failing = smaller;
+    }
+    failing
+}
+
+#[test]
+fn differential_shrinks_to_minimal_failure() {
+    use rand::Rng;
+
+    // "solver" disagrees with the "reference" whenever the list contains a 7
+    let result = differential_test(
+        100,
+        10,
+        |rng, size| (0..size).map(|_| rng.gen_range(0..10)).collect::<Vec<_>>(),
+        |input: &Vec<usize>| {
+            (0..input.len())
+                .map(|idx| {
+                    let mut smaller = input.clone();
+                    smaller.remove(idx);
+                    smaller
+                })
+                .collect()
+        },
+        |input| !input.contains(&7),
+    );
+    assert_eq!(Err(vec![7]), result);
+}
+
+#[test]
+fn differential_passes_when_all_agree() {
+    let result = differential_test(50, 5, |_, size| size, |_| vec![], |size| *size == 5);
+    assert_eq!(Ok(()), result);
+}
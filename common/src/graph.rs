@@ -0,0 +1,256 @@
+use itertools::Itertools;
+use std::{collections::HashMap, ops::Range};
+
+mod clique;
+mod cut;
+mod dag;
+mod flow;
+mod longest;
+
+pub use cut::MinCut;
+pub use dag::{Cycle, Dominators};
+pub use flow::{FlowCut, FlowNetwork};
+pub use longest::{Junctions, Slopes};
+
+/// Whether adding an edge from `a` to `b` also adds one from `b` back to `a`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edges {
+    Directed,
+    Undirected,
+}
+
+/// A graph over string labels. Each label is interned to a dense id the first time it is seen,
+/// so anything kept per node can live in a `Vec` indexed by id.
+#[derive(Clone, Debug)]
+pub struct Graph<'a> {
+    edges: Edges,
+    labels: Vec<&'a str>,
+    ids: HashMap<&'a str, usize>,
+    adjacency: Vec<Vec<usize>>,
+}
+
+/// Adjacency in compressed sparse row form: the neighbours of node `n` are
+/// `targets[offsets[n]..offsets[n + 1]]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Csr {
+    pub offsets: Vec<usize>,
+    pub targets: Vec<usize>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(edges: Edges) -> Self {
+        Self {
+            edges,
+            labels: vec![],
+            ids: HashMap::new(),
+            adjacency: vec![],
+        }
+    }
+
+    /// Parses one edge per line, such as `start-A` with a `separator` of `-`.
+    pub fn parse_pairs(input: &'a str, separator: &str, edges: Edges) -> Self {
+        Self::parse_lines(input, separator, separator, edges)
+    }
+
+    /// Parses a node and its neighbours per line, such as `jqt: rhn xhk nvd`.
+    pub fn parse_lists(input: &'a str, edges: Edges) -> Self {
+        Self::parse_lines(input, ": ", " ", edges)
+    }
+
+    /// Parses a node and its neighbours per line, such as `a -> b, c`.
+    pub fn parse_arrows(input: &'a str, edges: Edges) -> Self {
+        Self::parse_lines(input, " -> ", ", ", edges)
+    }
+
+    fn parse_lines(input: &'a str, separator: &str, item_separator: &str, edges: Edges) -> Self {
+        let mut graph = Self::new(edges);
+        for line in input.lines().filter(|line| !line.is_empty()) {
+            let (from, neighbours) = line
+                .split_once(separator)
+                .unwrap_or_else(|| panic!("expected {separator:?} in {line:?}"));
+            let from = graph.intern(from);
+            for to in neighbours.split(item_separator).filter(|to| !to.is_empty()) {
+                let to = graph.intern(to);
+                graph.connect(from, to);
+            }
+        }
+        graph
+    }
+
+    /// The id of `label`, adding it as a node with no edges if it is new.
+    pub fn intern(&mut self, label: &'a str) -> usize {
+        if let Some(id) = self.ids.get(label) {
+            return *id;
+        }
+        self.labels.push(label);
+        self.adjacency.push(vec![]);
+        self.ids.insert(label, self.labels.len() - 1);
+        self.labels.len() - 1
+    }
+
+    /// Adds an edge between two labels, interning them as needed, and returns their ids.
+    pub fn add_edge(&mut self, from: &'a str, to: &'a str) -> (usize, usize) {
+        let (from, to) = (self.intern(from), self.intern(to));
+        self.connect(from, to);
+        (from, to)
+    }
+
+    /// Adds an edge between two nodes that already exist.
+    pub fn connect(&mut self, from: usize, to: usize) {
+        self.adjacency[from].push(to);
+        if self.edges == Edges::Undirected && from != to {
+            self.adjacency[to].push(from);
+        }
+    }
+
+    pub fn id(&self, label: &str) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    pub fn label(&self, id: usize) -> &'a str {
+        self.labels[id]
+    }
+
+    /// Every label, indexed by id.
+    pub fn labels(&self) -> &[&'a str] {
+        &self.labels
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn nodes(&self) -> Range<usize> {
+        0..self.len()
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.edges == Edges::Directed
+    }
+
+    /// The nodes an edge leads to from `id`, in the order the edges were added.
+    pub fn neighbours(&self, id: usize) -> &[usize] {
+        &self.adjacency[id]
+    }
+
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.adjacency[from].contains(&to)
+    }
+
+    /// Every edge as `(from, to)`. Undirected edges are listed once, from the lower id.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.adjacency
+            .iter()
+            .enumerate()
+            .flat_map(move |(from, neighbours)| {
+                neighbours
+                    .iter()
+                    .filter(move |to| self.is_directed() || from <= **to)
+                    .map(move |to| (from, *to))
+            })
+    }
+
+    /// The same nodes, with every edge pointing the other way.
+    pub fn reversed(&self) -> Self {
+        let mut reversed = Self {
+            adjacency: vec![vec![]; self.len()],
+            ..self.clone()
+        };
+        for (from, to) in self.edges() {
+            reversed.connect(to, from);
+        }
+        reversed
+    }
+
+    pub fn csr(&self) -> Csr {
+        let offsets = [0]
+            .into_iter()
+            .chain(self.adjacency.iter().scan(0, |offset, neighbours| {
+                *offset += neighbours.len();
+                Some(*offset)
+            }))
+            .collect_vec();
+        Csr {
+            offsets,
+            targets: self.adjacency.concat(),
+        }
+    }
+}
+
+impl Csr {
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn neighbours(&self, id: usize) -> &[usize] {
+        &self.targets[self.offsets[id]..self.offsets[id + 1]]
+    }
+}
+
+/// Node labels for graphs built by hand in tests, some starting with `t` for the prefix searches.
+#[cfg(test)]
+const NAMES: [&str; 10] = ["ta", "tb", "c", "d", "e", "f", "g", "h", "i", "j"];
+
+/// A graph of `num_nodes` nodes, with ids in the order of [`NAMES`], joined by `edges`.
+#[cfg(test)]
+fn graph_of(kind: Edges, num_nodes: usize, edges: &[(usize, usize)]) -> Graph<'static> {
+    let mut graph = Graph::new(kind);
+    for name in &NAMES[..num_nodes] {
+        graph.intern(name);
+    }
+    for (from, to) in edges {
+        graph.connect(*from, *to);
+    }
+    graph
+}
+
+#[test]
+fn test_parse_formats() {
+    let pairs = Graph::parse_pairs("start-A\nA-c\nA-end\n", "-", Edges::Undirected);
+    assert_eq!(["start", "A", "c", "end"], pairs.labels());
+    assert_eq!([0, 2, 3], pairs.neighbours(1));
+    assert_eq!(3, pairs.edges().count());
+
+    let lists = Graph::parse_lists("jqt: rhn xhk\nrhn: xhk", Edges::Directed);
+    let (jqt, xhk) = (lists.id("jqt").unwrap(), lists.id("xhk").unwrap());
+    assert_eq!(
+        ["rhn", "xhk"],
+        lists
+            .neighbours(jqt)
+            .iter()
+            .map(|id| lists.label(*id))
+            .collect_vec()
+            .as_slice()
+    );
+    assert!(lists.neighbours(xhk).is_empty());
+    assert_eq!(None, lists.id("nvd"));
+
+    let arrows = Graph::parse_arrows("broadcaster -> a, b\na -> b", Edges::Directed);
+    assert_eq!(vec![(0, 1), (0, 2), (1, 2)], arrows.edges().collect_vec());
+    assert!(arrows.has_edge(1, 2));
+    assert!(!arrows.has_edge(2, 1));
+}
+
+#[test]
+fn test_reversed_and_csr() {
+    let graph = Graph::parse_arrows("a -> b, c\nb -> c\nc -> a", Edges::Directed);
+    let reversed = graph.reversed();
+    assert_eq!(graph.labels(), reversed.labels());
+    assert_eq!([2], reversed.neighbours(0));
+    assert_eq!([0, 1], reversed.neighbours(2));
+
+    let csr = graph.csr();
+    assert_eq!(vec![0, 2, 3, 4], csr.offsets);
+    assert_eq!(graph.len(), csr.len());
+    for node in graph.nodes() {
+        assert_eq!(graph.neighbours(node), csr.neighbours(node));
+    }
+}
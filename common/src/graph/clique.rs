@@ -0,0 +1,372 @@
+use super::Graph;
+#[cfg(test)]
+use super::{graph_of, Edges};
+use itertools::Itertools;
+
+/// A set of node ids packed into bits, so that neighbourhoods intersect a word at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NodeSet(Vec<u64>);
+
+impl NodeSet {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn insert(&mut self, node: usize) {
+        self.0[node / 64] |= 1 << (node % 64);
+    }
+
+    fn remove(&mut self, node: usize) {
+        self.0[node / 64] &= !(1 << (node % 64));
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    fn zip_with(&self, other: &Self, op: impl Fn(u64, u64) -> u64) -> Self {
+        Self(
+            self.0
+                .iter()
+                .zip(&other.0)
+                .map(|(a, b)| op(*a, *b))
+                .collect(),
+        )
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(idx, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    idx * 64 + bit
+                })
+            })
+        })
+    }
+}
+
+impl Graph<'_> {
+    /// Every clique that no other node could join, by Bron–Kerbosch with Tomita pivoting. Edge
+    /// directions are ignored. Each clique is sorted, and so is the list.
+    pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        let adjacency = self.adjacency_sets();
+        let mut cliques = vec![];
+        bron_kerbosch(
+            &adjacency,
+            &mut vec![],
+            self.all_nodes(),
+            NodeSet::new(self.len()),
+            &mut |clique| cliques.push(clique.iter().copied().sorted().collect_vec()),
+        );
+        cliques.sort();
+        cliques
+    }
+
+    /// The largest clique, sorted. Of several that are equally large, the one that sorts first.
+    pub fn max_clique(&self) -> Vec<usize> {
+        let adjacency = self.adjacency_sets();
+        let mut largest = vec![];
+        max_clique(
+            &adjacency,
+            &mut vec![],
+            self.all_nodes(),
+            NodeSet::new(self.len()),
+            &mut largest,
+        );
+        largest
+    }
+
+    /// Every clique of exactly `k` nodes, maximal or not, each sorted and in sorted order. A clique
+    /// has at least one node, so there are none of size 0.
+    pub fn cliques_of_size(&self, k: usize) -> Vec<Vec<usize>> {
+        if k == 0 {
+            return vec![];
+        }
+        let adjacency = self.adjacency_sets();
+        let mut cliques = vec![];
+        extend_to_size(&adjacency, &mut vec![], self.all_nodes(), k, &mut cliques);
+        cliques.sort();
+        cliques
+    }
+
+    /// Every clique of `k` nodes that includes `node`.
+    pub fn cliques_containing(&self, node: usize, k: usize) -> Vec<Vec<usize>> {
+        self.cliques_of_size_with(k, |member| member == node)
+    }
+
+    /// Every clique of `k` nodes where at least one label starts with `prefix`.
+    pub fn cliques_with_prefix(&self, prefix: &str, k: usize) -> Vec<Vec<usize>> {
+        self.cliques_of_size_with(k, |member| self.label(member).starts_with(prefix))
+    }
+
+    /// Every clique of `k` nodes with at least one member that `wanted` accepts. Each is built up
+    /// from the lowest wanted member it has, so none is found twice.
+    pub fn cliques_of_size_with(
+        &self,
+        k: usize,
+        wanted: impl Fn(usize) -> bool,
+    ) -> Vec<Vec<usize>> {
+        if k == 0 {
+            return vec![];
+        }
+        let adjacency = self.adjacency_sets();
+        let mut lower_wanted = NodeSet::new(self.len());
+        let mut cliques = vec![];
+        for node in self.nodes().filter(|node| wanted(*node)) {
+            let candidates = adjacency[node].zip_with(&lower_wanted, |a, b| a & !b);
+            extend_to_size(&adjacency, &mut vec![node], candidates, k, &mut cliques);
+            lower_wanted.insert(node);
+        }
+        for clique in &mut cliques {
+            clique.sort();
+        }
+        cliques.sort();
+        cliques
+    }
+
+    fn all_nodes(&self) -> NodeSet {
+        let mut all = NodeSet::new(self.len());
+        self.nodes().for_each(|node| all.insert(node));
+        all
+    }
+
+    /// Each node's neighbours either way, leaving out self loops.
+    fn adjacency_sets(&self) -> Vec<NodeSet> {
+        let mut sets = vec![NodeSet::new(self.len()); self.len()];
+        for (from, to) in self.edges().filter(|(from, to)| from != to) {
+            sets[from].insert(to);
+            sets[to].insert(from);
+        }
+        sets
+    }
+}
+
+/// Reports every maximal clique that extends `clique` with some of `candidates`. Those in
+/// `excluded` would also extend it, but every clique containing them has already been reported.
+fn bron_kerbosch(
+    adjacency: &[NodeSet],
+    clique: &mut Vec<usize>,
+    mut candidates: NodeSet,
+    mut excluded: NodeSet,
+    report: &mut impl FnMut(&[usize]),
+) {
+    if candidates.is_empty() {
+        if excluded.is_empty() {
+            report(clique);
+        }
+        return;
+    }
+    for node in branches(adjacency, &candidates, &excluded).iter() {
+        clique.push(node);
+        bron_kerbosch(
+            adjacency,
+            clique,
+            candidates.zip_with(&adjacency[node], |a, b| a & b),
+            excluded.zip_with(&adjacency[node], |a, b| a & b),
+            report,
+        );
+        clique.pop();
+        candidates.remove(node);
+        excluded.insert(node);
+    }
+}
+
+/// Bron–Kerbosch again, but giving up on any branch that cannot beat `largest`.
+fn max_clique(
+    adjacency: &[NodeSet],
+    clique: &mut Vec<usize>,
+    mut candidates: NodeSet,
+    mut excluded: NodeSet,
+    largest: &mut Vec<usize>,
+) {
+    if candidates.is_empty() {
+        let sorted = clique.iter().copied().sorted().collect_vec();
+        if excluded.is_empty()
+            && (sorted.len() > largest.len() || sorted.len() == largest.len() && sorted < *largest)
+        {
+            *largest = sorted;
+        }
+        return;
+    }
+    if clique.len() + candidates.len() < largest.len() {
+        return;
+    }
+    for node in branches(adjacency, &candidates, &excluded).iter() {
+        clique.push(node);
+        max_clique(
+            adjacency,
+            clique,
+            candidates.zip_with(&adjacency[node], |a, b| a & b),
+            excluded.zip_with(&adjacency[node], |a, b| a & b),
+            largest,
+        );
+        clique.pop();
+        candidates.remove(node);
+        excluded.insert(node);
+    }
+}
+
+/// The candidates worth branching on. Any maximal clique here contains the pivot or one of its
+/// non-neighbours, so picking the pivot with the most candidate neighbours leaves the fewest.
+fn branches(adjacency: &[NodeSet], candidates: &NodeSet, excluded: &NodeSet) -> NodeSet {
+    let pivot = candidates
+        .zip_with(excluded, |a, b| a | b)
+        .iter()
+        .max_by_key(|node| candidates.zip_with(&adjacency[*node], |a, b| a & b).len())
+        .unwrap();
+    candidates.zip_with(&adjacency[pivot], |a, b| a & !b)
+}
+
+/// Pushes every way of growing `clique` to `k` nodes from `candidates`, adding them in id order.
+fn extend_to_size(
+    adjacency: &[NodeSet],
+    clique: &mut Vec<usize>,
+    candidates: NodeSet,
+    k: usize,
+    cliques: &mut Vec<Vec<usize>>,
+) {
+    if clique.len() == k {
+        cliques.push(clique.clone());
+        return;
+    }
+    if clique.len() + candidates.len() < k {
+        return;
+    }
+    let mut remaining = candidates;
+    for node in remaining.clone().iter() {
+        remaining.remove(node);
+        clique.push(node);
+        let next = remaining.zip_with(&adjacency[node], |a, b| a & b);
+        extend_to_size(adjacency, clique, next, k, cliques);
+        clique.pop();
+    }
+}
+
+#[test]
+fn finds_cliques() {
+    // a 4-clique 0-3 sharing node 3 with the triangle 3-5, plus a lone edge 6-7
+    let graph = graph_of(
+        Edges::Undirected,
+        8,
+        &[
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+            (3, 4),
+            (3, 5),
+            (4, 5),
+            (6, 7),
+        ],
+    );
+    assert_eq!(
+        vec![vec![0, 1, 2, 3], vec![3, 4, 5], vec![6, 7]],
+        graph.maximal_cliques()
+    );
+    assert_eq!(vec![0, 1, 2, 3], graph.max_clique());
+    assert_eq!(
+        vec![
+            vec![0, 1, 2],
+            vec![0, 1, 3],
+            vec![0, 2, 3],
+            vec![1, 2, 3],
+            vec![3, 4, 5]
+        ],
+        graph.cliques_of_size(3)
+    );
+    assert_eq!(
+        vec![vec![0, 1, 3], vec![0, 2, 3], vec![1, 2, 3], vec![3, 4, 5]],
+        graph.cliques_containing(3, 3)
+    );
+    // both `ta` and `tb` are in some of these, but each is only listed once
+    assert_eq!(
+        vec![vec![0, 1, 2], vec![0, 1, 3], vec![0, 2, 3], vec![1, 2, 3]],
+        graph.cliques_with_prefix("t", 3)
+    );
+    assert_eq!(vec![vec![0, 1, 2, 3]], graph.cliques_of_size(4));
+    assert!(graph.cliques_of_size(5).is_empty());
+    assert_eq!(8, graph.cliques_of_size(1).len());
+    // no clique is empty, however it is asked for
+    assert!(graph.cliques_of_size(0).is_empty());
+    assert!(graph.cliques_of_size_with(0, |_| true).is_empty());
+    assert!(graph.cliques_containing(3, 0).is_empty());
+    assert!(graph_of(Edges::Undirected, 0, &[])
+        .cliques_of_size(0)
+        .is_empty());
+}
+
+#[test]
+fn cliques_match_brute_force() {
+    use crate::differential::differential_test;
+    use rand::Rng;
+
+    let result = differential_test(
+        200,
+        9,
+        |rng, size| {
+            let num_nodes = rng.gen_range(1..=size);
+            let edges = (0..num_nodes)
+                .tuple_combinations()
+                .filter(|_| rng.gen_bool(0.6))
+                .collect_vec();
+            (num_nodes, edges)
+        },
+        |(num_nodes, edges)| {
+            (0..edges.len())
+                .map(|idx| {
+                    let mut fewer = edges.clone();
+                    fewer.remove(idx);
+                    (*num_nodes, fewer)
+                })
+                .collect_vec()
+        },
+        |(num_nodes, edges)| {
+            let graph = graph_of(Edges::Undirected, *num_nodes, edges);
+            let is_clique = |nodes: &Vec<usize>| {
+                nodes
+                    .iter()
+                    .tuple_combinations()
+                    .all(|(a, b)| graph.has_edge(*a, *b))
+            };
+            let all_cliques = (0..*num_nodes).powerset().filter(is_clique).collect_vec();
+            let maximal = all_cliques
+                .iter()
+                .filter(|clique| {
+                    graph
+                        .nodes()
+                        .filter(|node| !clique.contains(node))
+                        .all(|node| {
+                            graph
+                                .neighbours(node)
+                                .iter()
+                                .filter(|n| clique.contains(n))
+                                .count()
+                                < clique.len()
+                        })
+                })
+                .filter(|clique| !clique.is_empty())
+                .cloned()
+                .sorted()
+                .collect_vec();
+            let largest = all_cliques.iter().map(|clique| clique.len()).max().unwrap();
+
+            (1..=4).all(|k| {
+                let of_size = all_cliques.iter().filter(|c| c.len() == k).cloned();
+                graph.cliques_of_size(k) == of_size.clone().sorted().collect_vec()
+                    && graph.cliques_containing(0, k)
+                        == of_size.filter(|c| c.contains(&0)).sorted().collect_vec()
+            }) && graph.maximal_cliques() == maximal
+                && graph.max_clique().len() == largest
+        },
+    );
+    assert_eq!(Ok(()), result);
+}
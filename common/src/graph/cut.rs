@@ -0,0 +1,186 @@
+use super::Graph;
+#[cfg(test)]
+use super::{graph_of, Edges};
+use itertools::Itertools;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// A smallest set of edges whose removal splits a graph in two.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MinCut {
+    /// The cut edges as `(from, to)`, with `from` on the same side as node 0.
+    pub edges: Vec<(usize, usize)>,
+    /// The nodes on each side, in id order. Node 0 is always on the first side.
+    pub sides: [Vec<usize>; 2],
+}
+
+impl Graph<'_> {
+    /// A global minimum cut by Stoer–Wagner, with edge directions ignored and parallel edges
+    /// counted separately. Ties between cuts of the same size always resolve the same way. `None`
+    /// if there are fewer than two nodes to separate.
+    pub fn min_cut(&self) -> Option<MinCut> {
+        if self.len() < 2 {
+            return None;
+        }
+
+        let mut weights = vec![HashMap::<usize, usize>::new(); self.len()];
+        for (from, to) in self.edges().filter(|(from, to)| from != to) {
+            *weights[from].entry(to).or_default() += 1;
+            *weights[to].entry(from).or_default() += 1;
+        }
+        let mut members = self.nodes().map(|node| vec![node]).collect_vec();
+        let mut alive = self.nodes().collect_vec();
+
+        let mut best: Option<(usize, Vec<usize>)> = None;
+        while alive.len() > 1 {
+            let (s, t, cut_of_phase) = maximum_adjacency_order(&weights, &alive);
+            if best
+                .as_ref()
+                .map_or(true, |(weight, _)| cut_of_phase < *weight)
+            {
+                best = Some((cut_of_phase, members[t].clone()));
+            }
+
+            // merge t into s
+            let merged = std::mem::take(&mut members[t]);
+            members[s].extend(merged);
+            for (neighbour, weight) in std::mem::take(&mut weights[t]) {
+                weights[neighbour].remove(&t);
+                if neighbour != s {
+                    *weights[s].entry(neighbour).or_default() += weight;
+                    *weights[neighbour].entry(s).or_default() += weight;
+                }
+            }
+            alive.retain(|node| *node != t);
+        }
+
+        let (_, side) = best.unwrap();
+        let mut in_side = vec![false; self.len()];
+        for node in side {
+            in_side[node] = true;
+        }
+        if in_side[0] {
+            in_side.iter_mut().for_each(|in_side| *in_side = !*in_side);
+        }
+        let edges = self
+            .edges()
+            .filter(|(from, to)| in_side[*from] != in_side[*to])
+            .map(|(from, to)| {
+                if in_side[from] {
+                    (to, from)
+                } else {
+                    (from, to)
+                }
+            })
+            .collect_vec();
+        let (second, first) = self.nodes().partition(|node| in_side[*node]);
+
+        Some(MinCut {
+            edges,
+            sides: [first, second],
+        })
+    }
+}
+
+/// One phase of Stoer–Wagner: adds the most tightly connected node to a growing set until every
+/// node is in it. Returns the last two nodes added and how strongly the last was connected to the
+/// rest, which is the size of the cut separating it from everything else.
+fn maximum_adjacency_order(
+    weights: &[HashMap<usize, usize>],
+    alive: &[usize],
+) -> (usize, usize, usize) {
+    let mut added = vec![false; weights.len()];
+    let mut connection = vec![0; weights.len()];
+    // ties go to the lowest id, so that each phase is the same every run
+    let mut heap = alive
+        .iter()
+        .map(|node| (0, Reverse(*node)))
+        .collect::<BinaryHeap<_>>();
+
+    let (mut previous, mut last) = (alive[0], alive[0]);
+    while let Some((weight, Reverse(node))) = heap.pop() {
+        if added[node] || weight != connection[node] {
+            continue;
+        }
+        added[node] = true;
+        (previous, last) = (last, node);
+        for (neighbour, edge_weight) in &weights[node] {
+            if !added[*neighbour] {
+                connection[*neighbour] += edge_weight;
+                heap.push((connection[*neighbour], Reverse(*neighbour)));
+            }
+        }
+    }
+    (previous, last, connection[last])
+}
+
+#[test]
+fn test_small_cuts() {
+    // two triangles joined by a single edge
+    let bridged = graph_of(
+        Edges::Undirected,
+        6,
+        &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (2, 3)],
+    );
+    let cut = bridged.min_cut().unwrap();
+    assert_eq!(vec![(2, 3)], cut.edges);
+    assert_eq!([vec![0, 1, 2], vec![3, 4, 5]], cut.sides);
+
+    // parallel edges each count
+    let doubled = graph_of(
+        Edges::Undirected,
+        3,
+        &[(0, 1), (0, 1), (1, 2), (1, 2), (1, 2)],
+    );
+    assert_eq!(vec![(0, 1), (0, 1)], doubled.min_cut().unwrap().edges);
+
+    let disconnected = graph_of(Edges::Undirected, 4, &[(0, 1), (2, 3)]);
+    let cut = disconnected.min_cut().unwrap();
+    assert!(cut.edges.is_empty());
+    assert_eq!([vec![0, 1], vec![2, 3]], cut.sides);
+
+    assert_eq!(None, graph_of(Edges::Undirected, 1, &[]).min_cut());
+}
+
+#[test]
+fn test_cut_matches_brute_force() {
+    use crate::differential::differential_test;
+    use rand::Rng;
+
+    let brute_force = |num_nodes: usize, edges: &[(usize, usize)]| {
+        (1..1_usize << (num_nodes - 1))
+            .map(|mask| {
+                // the last node is never in the mask, so each split is only tried once
+                let side = |node: usize| mask & (1 << node) != 0;
+                edges
+                    .iter()
+                    .filter(|(from, to)| side(*from) != side(*to))
+                    .count()
+            })
+            .min()
+            .unwrap()
+    };
+    let result = differential_test(
+        200,
+        8,
+        |rng, size| {
+            let num_nodes = rng.gen_range(2..=size);
+            let edges = (0..rng.gen_range(0..num_nodes * 3))
+                .map(|_| (rng.gen_range(0..num_nodes), rng.gen_range(0..num_nodes)))
+                .filter(|(from, to)| from != to)
+                .collect_vec();
+            (num_nodes, edges)
+        },
+        |_| vec![],
+        |(num_nodes, edges)| {
+            let cut = graph_of(Edges::Undirected, *num_nodes, edges)
+                .min_cut()
+                .unwrap();
+            let split = cut.sides.iter().all(|side| !side.is_empty());
+            split && cut.edges.len() == brute_force(*num_nodes, edges)
+        },
+    );
+    assert_eq!(Ok(()), result);
+}
@@ -0,0 +1,405 @@
+use super::Graph;
+#[cfg(test)]
+use super::{graph_of, Edges};
+use itertools::Itertools;
+use std::collections::VecDeque;
+
+/// Nodes that each have an edge to the next, and the last back to the first, so that none of them
+/// can come first in a topological order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle(pub Vec<usize>);
+
+/// The dominator tree of everything reachable from a root: node `d` dominates node `n` if every
+/// path from the root to `n` goes through `d`.
+#[derive(Clone, Debug)]
+pub struct Dominators {
+    root: usize,
+    idom: Vec<Option<usize>>,
+}
+
+impl Graph<'_> {
+    /// Every node, ordered so that each edge points from an earlier node to a later one, or the
+    /// first cycle found if there is no such order.
+    pub fn topo_sort(&self) -> Result<Vec<usize>, Cycle> {
+        self.topo_sort_of(&self.nodes().collect_vec())
+    }
+
+    /// A topological order of just `nodes`, counting only the edges between them.
+    pub fn topo_sort_of(&self, nodes: &[usize]) -> Result<Vec<usize>, Cycle> {
+        let mut include = vec![false; self.len()];
+        for node in nodes {
+            include[*node] = true;
+        }
+        match self.depth_first(nodes, &include) {
+            (_, Some(cycle)) => Err(cycle),
+            (mut postorder, None) => {
+                postorder.reverse();
+                Ok(postorder)
+            }
+        }
+    }
+
+    /// How many different paths lead from `from` to `to`, with parallel edges counted separately.
+    /// Fails if a cycle sits on any of them, since then there are infinitely many.
+    pub fn count_paths(&self, from: usize, to: usize) -> Result<usize, Cycle> {
+        self.count_paths_via(from, to, &[])
+    }
+
+    /// How many paths from `from` to `to` visit every one of `waypoints`, in whatever order they
+    /// come.
+    pub fn count_paths_via(
+        &self,
+        from: usize,
+        to: usize,
+        waypoints: &[usize],
+    ) -> Result<usize, Cycle> {
+        let from_start = self.reachable_from(from);
+        let to_end = self.reversed().reachable_from(to);
+        let relevant = from_start
+            .iter()
+            .zip(to_end)
+            .map(|(a, b)| *a && b)
+            .collect_vec();
+        if !relevant[from] || waypoints.iter().any(|node| !relevant[*node]) {
+            return Ok(0);
+        }
+
+        let relevant_nodes = self.nodes().filter(|node| relevant[*node]);
+        let order = self.topo_sort_of(&relevant_nodes.collect_vec())?;
+        let mut position = vec![0; self.len()];
+        for (idx, node) in order.iter().enumerate() {
+            position[*node] = idx;
+        }
+        // every node on a path is after the ones before it in any topological order, so the
+        // waypoints can only be visited in that order
+        let stops = [from]
+            .into_iter()
+            .chain(waypoints.iter().copied().sorted_by_key(|n| position[*n]))
+            .chain([to])
+            .collect_vec();
+
+        Ok(stops
+            .iter()
+            .tuple_windows()
+            .map(|(a, b)| self.count_paths_in_order(&order, &relevant, *a, *b))
+            .product())
+    }
+
+    /// The dominator tree of the nodes reachable from `root`, by the iterative algorithm of Cooper,
+    /// Harvey and Kennedy.
+    pub fn dominators(&self, root: usize) -> Dominators {
+        let (postorder, _) = self.depth_first(&[root], &vec![true; self.len()]);
+        let mut rank = vec![0; self.len()];
+        for (idx, node) in postorder.iter().enumerate() {
+            rank[*node] = idx;
+        }
+        let predecessors = self.reversed();
+
+        let mut idom = vec![None; self.len()];
+        idom[root] = Some(root);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in postorder.iter().rev().skip(1) {
+                let new_idom = predecessors
+                    .neighbours(*node)
+                    .iter()
+                    .copied()
+                    .filter(|pred| idom[*pred].is_some())
+                    .reduce(|a, b| intersect(&idom, &rank, a, b));
+                if new_idom != idom[*node] {
+                    idom[*node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        idom[root] = None;
+        Dominators { root, idom }
+    }
+
+    /// Paths from `from` to `to` in a topologically ordered set of nodes.
+    fn count_paths_in_order(
+        &self,
+        order: &[usize],
+        include: &[bool],
+        from: usize,
+        to: usize,
+    ) -> usize {
+        let mut paths = vec![0; self.len()];
+        paths[to] = 1;
+        for node in order.iter().rev().filter(|node| **node != to) {
+            paths[*node] = self
+                .neighbours(*node)
+                .iter()
+                .filter(|next| include[**next])
+                .map(|next| paths[*next])
+                .sum();
+        }
+        paths[from]
+    }
+
+    fn reachable_from(&self, start: usize) -> Vec<bool> {
+        let mut reached = vec![false; self.len()];
+        reached[start] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for next in self.neighbours(node) {
+                if !reached[*next] {
+                    reached[*next] = true;
+                    queue.push_back(*next);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Depth-first search from each of `roots` in turn, through nodes that are `include`d. Returns
+    /// them in postorder, along with the first cycle found.
+    fn depth_first(&self, roots: &[usize], include: &[bool]) -> (Vec<usize>, Option<Cycle>) {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum State {
+            Unseen,
+            Open,
+            Done,
+        }
+
+        let mut state = vec![State::Unseen; self.len()];
+        let mut postorder = vec![];
+        let mut cycle = None;
+        for root in roots {
+            if !include[*root] || state[*root] != State::Unseen {
+                continue;
+            }
+            state[*root] = State::Open;
+            // each open node and how many of its edges have been followed
+            let mut stack = vec![(*root, 0)];
+            while let Some((node, followed)) = stack.last_mut() {
+                let node = *node;
+                let Some(next) = self.neighbours(node).get(*followed) else {
+                    state[node] = State::Done;
+                    postorder.push(node);
+                    stack.pop();
+                    continue;
+                };
+                *followed += 1;
+                if !include[*next] {
+                    continue;
+                }
+                match state[*next] {
+                    State::Unseen => {
+                        state[*next] = State::Open;
+                        stack.push((*next, 0));
+                    }
+                    State::Open if cycle.is_none() => {
+                        let start = stack.iter().position(|(n, _)| n == next).unwrap();
+                        cycle = Some(Cycle(stack[start..].iter().map(|(n, _)| *n).collect()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        (postorder, cycle)
+    }
+}
+
+/// The nearest common dominator of `a` and `b`, walking up whichever is deeper in postorder.
+fn intersect(idom: &[Option<usize>], rank: &[usize], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while rank[a] < rank[b] {
+            a = idom[a].unwrap();
+        }
+        while rank[b] < rank[a] {
+            b = idom[b].unwrap();
+        }
+    }
+    a
+}
+
+impl Dominators {
+    /// The closest strict dominator of `node`, or `None` for the root and for anything unreachable
+    /// from it.
+    pub fn immediate(&self, node: usize) -> Option<usize> {
+        self.idom[node]
+    }
+
+    /// `node` followed by each of its dominators, ending at the root. Just `node` if it is
+    /// unreachable.
+    pub fn chain(&self, node: usize) -> Vec<usize> {
+        let mut chain = vec![node];
+        while let Some(idom) = self.immediate(*chain.last().unwrap()) {
+            chain.push(idom);
+        }
+        chain
+    }
+
+    pub fn dominates(&self, dominator: usize, node: usize) -> bool {
+        let reachable = node == self.root || self.idom[node].is_some();
+        reachable && self.chain(node).contains(&dominator)
+    }
+}
+
+#[test]
+fn topo_sort_and_cycles() {
+    let graph = graph_of(
+        Edges::Directed,
+        5,
+        &[(3, 1), (1, 0), (3, 2), (2, 0), (4, 3)],
+    );
+    assert_eq!(Ok(vec![4, 3, 2, 1, 0]), graph.topo_sort());
+    assert_eq!(Ok(vec![3, 1, 0]), graph.topo_sort_of(&[0, 1, 3]));
+
+    let looped = graph_of(Edges::Directed, 4, &[(0, 1), (1, 2), (2, 3), (3, 1)]);
+    assert_eq!(Err(Cycle(vec![1, 2, 3])), looped.topo_sort());
+    // the cycle doesn't matter if a node on it is left out
+    assert_eq!(Ok(vec![0, 1, 2]), looped.topo_sort_of(&[0, 1, 2]));
+    assert_eq!(
+        Err(Cycle(vec![0])),
+        graph_of(Edges::Directed, 1, &[(0, 0)]).topo_sort()
+    );
+}
+
+#[test]
+fn path_counts() {
+    let graph = Graph::parse_lists(
+        "svr: aaa bbb\naaa: fft\nfft: ccc\nbbb: tty\ntty: ccc\nccc: ddd eee\n\
+         ddd: hub\nhub: fff\neee: dac\ndac: fff\nfff: ggg hhh\nggg: out\n\
+         hhh: out",
+        Edges::Directed,
+    );
+    let node = |label| graph.id(label).unwrap();
+    let (svr, out) = (node("svr"), node("out"));
+
+    assert_eq!(Ok(8), graph.count_paths(svr, out));
+    assert_eq!(Ok(0), graph.count_paths(out, svr));
+    assert_eq!(Ok(1), graph.count_paths(out, out));
+    assert_eq!(
+        Ok(2),
+        graph.count_paths_via(svr, out, &[node("dac"), node("fft")])
+    );
+    assert_eq!(
+        Ok(2),
+        graph.count_paths_via(svr, out, &[node("fft"), node("dac")])
+    );
+    // no path goes through both sides of the fork
+    assert_eq!(
+        Ok(0),
+        graph.count_paths_via(svr, out, &[node("fft"), node("tty")])
+    );
+
+    // a cycle only matters if it is on a path between the two nodes
+    let looped = graph_of(
+        Edges::Directed,
+        5,
+        &[(0, 1), (1, 2), (2, 1), (0, 3), (3, 4)],
+    );
+    assert_eq!(Ok(1), looped.count_paths(0, 4));
+    assert_eq!(Err(Cycle(vec![1, 2])), looped.count_paths(0, 2));
+}
+
+#[test]
+fn dominator_tree() {
+    // 0 -> 1 -> 2 -> 4, 0 -> 1 -> 3 -> 4, with 5 unreachable
+    let graph = graph_of(
+        Edges::Directed,
+        6,
+        &[(0, 1), (1, 2), (1, 3), (2, 4), (3, 4), (5, 4)],
+    );
+    let dominators = graph.dominators(0);
+    assert_eq!(None, dominators.immediate(0));
+    assert_eq!(Some(1), dominators.immediate(2));
+    assert_eq!(Some(1), dominators.immediate(4));
+    assert_eq!(None, dominators.immediate(5));
+    assert_eq!(vec![4, 1, 0], dominators.chain(4));
+    assert!(dominators.dominates(1, 4));
+    assert!(dominators.dominates(4, 4));
+    assert!(!dominators.dominates(2, 4));
+    assert!(!dominators.dominates(0, 5));
+}
+
+#[test]
+fn dag_matches_brute_force() {
+    use crate::differential::differential_test;
+    use rand::Rng;
+
+    fn paths(
+        edges: &[(usize, usize)],
+        from: usize,
+        to: usize,
+        avoiding: Option<usize>,
+    ) -> Vec<Vec<usize>> {
+        if Some(from) == avoiding {
+            return vec![];
+        }
+        if from == to {
+            return vec![vec![to]];
+        }
+        edges
+            .iter()
+            .filter(|(a, _)| *a == from)
+            .flat_map(|(_, b)| paths(edges, *b, to, avoiding))
+            .map(|mut path| {
+                path.insert(0, from);
+                path
+            })
+            .collect()
+    }
+
+    let result = differential_test(
+        300,
+        8,
+        |rng, size| {
+            let num_nodes = rng.gen_range(2..=size);
+            // edges only go up, so the graph is acyclic
+            let edges = (0..rng.gen_range(0..num_nodes * 2))
+                .map(|_| {
+                    let from = rng.gen_range(0..num_nodes - 1);
+                    (from, rng.gen_range(from + 1..num_nodes))
+                })
+                .collect_vec();
+            let waypoints = (0..rng.gen_range(0..3))
+                .map(|_| rng.gen_range(0..num_nodes))
+                .unique()
+                .collect_vec();
+            (num_nodes, edges, waypoints)
+        },
+        |(num_nodes, edges, waypoints)| {
+            (0..edges.len())
+                .map(|idx| {
+                    let mut fewer = edges.clone();
+                    fewer.remove(idx);
+                    (*num_nodes, fewer, waypoints.clone())
+                })
+                .collect_vec()
+        },
+        |(num_nodes, edges, waypoints)| {
+            let graph = graph_of(Edges::Directed, *num_nodes, edges);
+            let last = num_nodes - 1;
+            let all_paths = paths(edges, 0, last, None);
+            let through_waypoints = all_paths
+                .iter()
+                .filter(|path| waypoints.iter().all(|w| path.contains(w)))
+                .count();
+            let order = graph.topo_sort().unwrap();
+            let ordered = edges.iter().all(|(from, to)| {
+                order.iter().position(|n| n == from) < order.iter().position(|n| n == to)
+            });
+
+            let dominators = graph.dominators(0);
+            let dominated =
+                (0..*num_nodes)
+                    .cartesian_product(0..*num_nodes)
+                    .all(|(dominator, node)| {
+                        let reachable = !paths(edges, 0, node, None).is_empty();
+                        let avoidable =
+                            dominator != node && !paths(edges, 0, node, Some(dominator)).is_empty();
+                        dominators.dominates(dominator, node) == (reachable && !avoidable)
+                    });
+
+            ordered
+                && dominated
+                && graph.count_paths(0, last) == Ok(all_paths.len())
+                && graph.count_paths_via(0, last, waypoints) == Ok(through_waypoints)
+        },
+    );
+    assert_eq!(Ok(()), result);
+}
@@ -0,0 +1,484 @@
+use super::Graph;
+use itertools::Itertools;
+use std::collections::VecDeque;
+
+/// A network of edges with capacities, and optionally costs, over dense node ids. Every edge is
+/// stored next to its residual partner, so edge `e` and `e ^ 1` always undo each other.
+#[derive(Clone, Debug)]
+pub struct FlowNetwork {
+    /// For each node, the indices of the edges leaving it, residual ones included.
+    adjacency: Vec<Vec<usize>>,
+    edges: Vec<FlowEdge>,
+}
+
+#[derive(Clone, Debug)]
+struct FlowEdge {
+    from: usize,
+    to: usize,
+    capacity: i64,
+    flow: i64,
+    cost: i64,
+}
+
+impl FlowEdge {
+    fn residual(&self) -> i64 {
+        self.capacity - self.flow
+    }
+}
+
+/// The edges a maximum flow saturates between the side the source can still reach and the rest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlowCut {
+    /// The cut edges as `(from, to)`, with `from` on the source side.
+    pub edges: Vec<(usize, usize)>,
+    /// The source side then the sink side, in id order.
+    pub sides: [Vec<usize>; 2],
+}
+
+impl FlowNetwork {
+    pub fn new(num_nodes: usize) -> Self {
+        Self {
+            adjacency: vec![vec![]; num_nodes],
+            edges: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    /// Adds an edge that can carry up to `capacity` from `from` to `to`, returning its id.
+    pub fn add_edge(&mut self, from: usize, to: usize, capacity: i64) -> usize {
+        self.add_edge_with_cost(from, to, capacity, 0)
+    }
+
+    /// Adds an edge where each unit of flow costs `cost`, returning its id.
+    pub fn add_edge_with_cost(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: i64,
+        cost: i64,
+    ) -> usize {
+        self.push_pair(from, to, capacity, 0, cost)
+    }
+
+    /// Adds an edge that can carry up to `capacity` either way, returning its id. Flow from `to` to
+    /// `from` shows up as negative flow on it.
+    pub fn add_undirected_edge(&mut self, a: usize, b: usize, capacity: i64) -> usize {
+        self.push_pair(a, b, capacity, capacity, 0)
+    }
+
+    fn push_pair(&mut self, from: usize, to: usize, capacity: i64, back: i64, cost: i64) -> usize {
+        let id = self.edges.len();
+        self.edges.push(FlowEdge {
+            from,
+            to,
+            capacity,
+            flow: 0,
+            cost,
+        });
+        self.edges.push(FlowEdge {
+            from: to,
+            to: from,
+            capacity: back,
+            flow: 0,
+            cost: -cost,
+        });
+        self.adjacency[from].push(id);
+        self.adjacency[to].push(id + 1);
+        id
+    }
+
+    /// How much is flowing along an edge returned by one of the `add_` methods.
+    pub fn flow(&self, edge: usize) -> i64 {
+        self.edges[edge].flow
+    }
+
+    /// Removes all flow, so the network can be solved again for other terminals.
+    pub fn reset(&mut self) {
+        self.edges.iter_mut().for_each(|edge| edge.flow = 0);
+    }
+
+    fn push(&mut self, edge: usize, amount: i64) {
+        self.edges[edge].flow += amount;
+        self.edges[edge ^ 1].flow -= amount;
+    }
+
+    /// Pushes as much flow as possible from `source` to `sink` with Dinic's algorithm, on top of
+    /// any flow already there, and returns how much was added. Nothing flows from a node to itself.
+    pub fn max_flow(&mut self, source: usize, sink: usize) -> i64 {
+        if source == sink {
+            return 0;
+        }
+        let mut total = 0;
+        while let Some(levels) = self.levels(source, sink) {
+            let mut next_edge = vec![0; self.len()];
+            loop {
+                let pushed = self.blocking_flow(source, sink, i64::MAX, &levels, &mut next_edge);
+                if pushed == 0 {
+                    break;
+                }
+                total += pushed;
+            }
+        }
+        total
+    }
+
+    /// Breadth first distances from `source` over edges with room left, if `sink` is reachable.
+    fn levels(&self, source: usize, sink: usize) -> Option<Vec<Option<usize>>> {
+        let mut levels = vec![None; self.len()];
+        levels[source] = Some(0);
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for edge in &self.adjacency[node] {
+                let edge = &self.edges[*edge];
+                if edge.residual() > 0 && levels[edge.to].is_none() {
+                    levels[edge.to] = levels[node].map(|level| level + 1);
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        levels[sink].map(|_| levels)
+    }
+
+    fn blocking_flow(
+        &mut self,
+        node: usize,
+        sink: usize,
+        limit: i64,
+        levels: &[Option<usize>],
+        next_edge: &mut [usize],
+    ) -> i64 {
+        if node == sink {
+            return limit;
+        }
+        // edges that led nowhere are skipped for the rest of the phase
+        while next_edge[node] < self.adjacency[node].len() {
+            let edge = self.adjacency[node][next_edge[node]];
+            let FlowEdge { to, .. } = self.edges[edge];
+            let residual = self.edges[edge].residual();
+            if residual > 0 && levels[to] == levels[node].map(|level| level + 1) {
+                let pushed = self.blocking_flow(to, sink, limit.min(residual), levels, next_edge);
+                if pushed > 0 {
+                    self.push(edge, pushed);
+                    return pushed;
+                }
+            }
+            next_edge[node] += 1;
+        }
+        0
+    }
+
+    /// Sends up to `limit` units from `source` to `sink` as cheaply as possible, always augmenting
+    /// along a cheapest path. Returns the flow sent and its total cost. Costs may be negative, as
+    /// long as no cycle of edges with room left has a negative total.
+    pub fn min_cost_flow(&mut self, source: usize, sink: usize, limit: i64) -> (i64, i64) {
+        if source == sink {
+            return (0, 0);
+        }
+        let (mut flow, mut cost) = (0, 0);
+        while flow < limit {
+            let Some(path) = self.cheapest_path(source, sink) else {
+                break;
+            };
+            let amount = path
+                .iter()
+                .map(|edge| self.edges[*edge].residual())
+                .fold(limit - flow, i64::min);
+            for edge in &path {
+                self.push(*edge, amount);
+                cost += amount * self.edges[*edge].cost;
+            }
+            flow += amount;
+        }
+        (flow, cost)
+    }
+
+    /// The edges of a cheapest path with room left, by Bellman-Ford so that the negative costs of
+    /// residual edges are handled.
+    fn cheapest_path(&self, source: usize, sink: usize) -> Option<Vec<usize>> {
+        let mut distance = vec![None; self.len()];
+        let mut via = vec![None; self.len()];
+        let mut queued = vec![false; self.len()];
+        distance[source] = Some(0);
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            queued[node] = false;
+            let here = distance[node].unwrap();
+            for edge_id in &self.adjacency[node] {
+                let edge = &self.edges[*edge_id];
+                let candidate = here + edge.cost;
+                if edge.residual() > 0 && distance[edge.to].map_or(true, |d| candidate < d) {
+                    distance[edge.to] = Some(candidate);
+                    via[edge.to] = Some(*edge_id);
+                    if !queued[edge.to] {
+                        queued[edge.to] = true;
+                        queue.push_back(edge.to);
+                    }
+                }
+            }
+        }
+
+        distance[sink]?;
+        let mut path = vec![];
+        let mut node = sink;
+        while let Some(edge) = via[node].filter(|_| node != source) {
+            path.push(edge);
+            node = self.edges[edge].from;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// The minimum cut matching the current flow, which is maximal once `max_flow` has run: the
+    /// nodes `source` can still push more to, and the full edges leaving them.
+    pub fn min_cut(&self, source: usize) -> FlowCut {
+        let mut reachable = vec![false; self.len()];
+        reachable[source] = true;
+        let mut to_visit = vec![source];
+        while let Some(node) = to_visit.pop() {
+            for edge in &self.adjacency[node] {
+                let edge = &self.edges[*edge];
+                if edge.residual() > 0 && !reachable[edge.to] {
+                    reachable[edge.to] = true;
+                    to_visit.push(edge.to);
+                }
+            }
+        }
+
+        let edges = self
+            .edges
+            .iter()
+            .filter(|edge| edge.capacity > 0 && reachable[edge.from] && !reachable[edge.to])
+            .map(|edge| (edge.from, edge.to))
+            .collect_vec();
+        let (source_side, sink_side) = (0..self.len()).partition(|node| reachable[*node]);
+        FlowCut {
+            edges,
+            sides: [source_side, sink_side],
+        }
+    }
+
+    /// Splits the current flow from `source` to `sink` into paths, each with the amount it
+    /// carries. Any flow going round in cycles is left out, and so is any flow that leaves
+    /// `source` but ends somewhere other than `sink`, as when it was pushed between other nodes.
+    pub fn decompose(&self, source: usize, sink: usize) -> Vec<(Vec<usize>, i64)> {
+        // net flow along each pair, pointing whichever way it actually goes
+        let mut remaining = self
+            .edges
+            .iter()
+            .step_by(2)
+            .map(|edge| match edge.flow {
+                flow if flow >= 0 => (edge.from, edge.to, flow),
+                flow => (edge.to, edge.from, -flow),
+            })
+            .collect_vec();
+        let mut leaving = vec![vec![]; self.len()];
+        for (idx, (from, _, _)) in remaining.iter().enumerate() {
+            leaving[*from].push(idx);
+        }
+        let next_arc = |remaining: &[(usize, usize, i64)], node: usize| {
+            leaving[node]
+                .iter()
+                .copied()
+                .find(|arc| remaining[*arc].2 > 0)
+        };
+
+        let mut paths = vec![];
+        while next_arc(&remaining, source).is_some() {
+            let (mut nodes, mut arcs) = (vec![source], vec![]);
+            // where flow is conserved, whatever arrives at a node other than the sink also leaves
+            while let Some(arc) = next_arc(&remaining, *nodes.last().unwrap()) {
+                let next = remaining[arc].1;
+                if let Some(start) = nodes.iter().position(|node| *node == next) {
+                    // went round a cycle, so cancel it and carry on from where it started
+                    let mut cycle = arcs.split_off(start);
+                    cycle.push(arc);
+                    let amount = cycle.iter().map(|arc| remaining[*arc].2).min().unwrap();
+                    for arc in cycle {
+                        remaining[arc].2 -= amount;
+                    }
+                    nodes.truncate(start + 1);
+                    continue;
+                }
+                arcs.push(arc);
+                nodes.push(next);
+                if next == sink {
+                    break;
+                }
+            }
+            // a walk that cancelled its way back to the source has nothing left to remove
+            let Some(amount) = arcs.iter().map(|arc| remaining[*arc].2).min() else {
+                continue;
+            };
+            for arc in &arcs {
+                remaining[*arc].2 -= amount;
+            }
+            // a walk that got stuck is dropped, but its flow is still removed so the next one
+            // does not take it again
+            if *nodes.last().unwrap() == sink {
+                paths.push((nodes, amount));
+            }
+        }
+        paths
+    }
+}
+
+impl Graph<'_> {
+    /// A flow network with the same node ids, where every edge can carry `capacity`. Undirected
+    /// edges can carry it either way.
+    pub fn flow_network(&self, capacity: i64) -> FlowNetwork {
+        let mut network = FlowNetwork::new(self.len());
+        for (from, to) in self.edges() {
+            if self.is_directed() {
+                network.add_edge(from, to, capacity);
+            } else {
+                network.add_undirected_edge(from, to, capacity);
+            }
+        }
+        network
+    }
+}
+
+#[test]
+fn test_max_flow_textbook() {
+    // the network from CLRS figure 26.1
+    let mut network = FlowNetwork::new(6);
+    for (from, to, capacity) in [
+        (0, 1, 16),
+        (0, 2, 13),
+        (2, 1, 4),
+        (1, 3, 12),
+        (3, 2, 9),
+        (2, 4, 14),
+        (4, 3, 7),
+        (3, 5, 20),
+        (4, 5, 4),
+    ] {
+        network.add_edge(from, to, capacity);
+    }
+    assert_eq!(23, network.max_flow(0, 5));
+    assert_eq!(0, network.max_flow(0, 5));
+
+    let cut = network.min_cut(0);
+    assert_eq!(vec![(1, 3), (4, 3), (4, 5)], cut.edges);
+    assert_eq!([vec![0, 1, 2, 4], vec![3, 5]], cut.sides);
+
+    let paths = network.decompose(0, 5);
+    assert_eq!(23, paths.iter().map(|(_, amount)| amount).sum::<i64>());
+    for (path, _) in &paths {
+        assert_eq!((Some(&0), Some(&5)), (path.first(), path.last()));
+    }
+
+    network.reset();
+    assert_eq!(23, network.max_flow(0, 5));
+}
+
+#[test]
+fn test_same_terminals() {
+    let mut network = FlowNetwork::new(2);
+    network.add_edge(0, 1, 5);
+    network.add_edge(1, 0, 5);
+    assert_eq!(0, network.max_flow(0, 0));
+    assert_eq!((0, 0), network.min_cost_flow(1, 1, i64::MAX));
+    assert!(network.decompose(0, 0).is_empty());
+}
+
+#[test]
+fn test_decompose_other_terminals() {
+    // 0 -> 1 -> 2 -> 3 and 0 -> 2, decomposed for the terminals it was solved for and others
+    let mut network = FlowNetwork::new(4);
+    for (from, to, capacity) in [(0, 1, 2), (1, 2, 2), (2, 3, 5), (0, 2, 3)] {
+        network.add_edge(from, to, capacity);
+    }
+    assert_eq!(5, network.max_flow(0, 3));
+    assert_eq!(
+        vec![(vec![0, 1, 2, 3], 2), (vec![0, 2, 3], 3)],
+        network.decompose(0, 3)
+    );
+
+    // for other terminals flow is not conserved, and walks that get stuck at 3 are dropped
+    assert_eq!(vec![(vec![1, 2, 3], 2)], network.decompose(1, 3));
+    assert_eq!(vec![(vec![0, 1], 2)], network.decompose(0, 1));
+    assert!(network.decompose(1, 0).is_empty());
+    assert!(network.decompose(3, 0).is_empty());
+}
+
+#[test]
+fn test_min_cost_flow() {
+    // two routes of capacity 2, the direct one dearer, and a shortcut between them
+    let mut network = FlowNetwork::new(4);
+    let direct = network.add_edge_with_cost(0, 3, 2, 10);
+    network.add_edge_with_cost(0, 1, 2, 1);
+    network.add_edge_with_cost(1, 3, 1, 1);
+    network.add_edge_with_cost(1, 2, 2, 2);
+    network.add_edge_with_cost(2, 3, 2, 2);
+
+    assert_eq!((3, 2 + 5 + 10), network.min_cost_flow(0, 3, 3));
+    assert_eq!(1, network.flow(direct));
+    network.reset();
+    assert_eq!((4, 2 + 5 + 20), network.min_cost_flow(0, 3, i64::MAX));
+
+    // an assignment problem: workers 1-3 to jobs 4-6, minimising the total cost
+    let costs = [[4, 1, 3], [2, 0, 5], [3, 2, 2]];
+    let mut network = FlowNetwork::new(8);
+    for (worker, job_costs) in costs.iter().enumerate() {
+        network.add_edge(0, 1 + worker, 1);
+        network.add_edge(4 + worker, 7, 1);
+        for (job, cost) in job_costs.iter().enumerate() {
+            network.add_edge_with_cost(1 + worker, 4 + job, 1, *cost);
+        }
+    }
+    assert_eq!((3, 5), network.min_cost_flow(0, 7, i64::MAX));
+}
+
+#[test]
+fn test_max_flow_matches_min_cost_flow() {
+    use crate::differential::differential_test;
+    use rand::Rng;
+
+    let build = |edges: &[(usize, usize, i64, i64)]| {
+        let mut network = FlowNetwork::new(6);
+        for (from, to, capacity, cost) in edges {
+            network.add_edge_with_cost(*from, *to, *capacity, *cost);
+        }
+        network
+    };
+    let result = differential_test(
+        200,
+        12,
+        |rng, size| {
+            (0..size)
+                .map(|_| {
+                    let (from, to) = (rng.gen_range(0..5), rng.gen_range(1..6));
+                    (from, to, rng.gen_range(0..10), rng.gen_range(0..5))
+                })
+                .filter(|(from, to, _, _)| from != to)
+                .collect_vec()
+        },
+        |_| vec![],
+        |edges| {
+            let mut dinic = build(edges);
+            let flow = dinic.max_flow(0, 5);
+            let cut = dinic.min_cut(0);
+            let cut_capacity = edges
+                .iter()
+                .filter(|(from, to, _, _)| cut.edges.contains(&(*from, *to)))
+                .map(|(_, _, capacity, _)| capacity)
+                .sum::<i64>();
+            let decomposed = dinic
+                .decompose(0, 5)
+                .iter()
+                .map(|(_, amount)| amount)
+                .sum::<i64>();
+            let (cheapest_flow, _) = build(edges).min_cost_flow(0, 5, i64::MAX);
+            flow == cut_capacity && flow == decomposed && flow == cheapest_flow
+        },
+    );
+    assert_eq!(Ok(()), result);
+}
@@ -0,0 +1,267 @@
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// How a maze treats slope tiles (`^`, `>`, `v`, `<`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slopes {
+    /// Stepping onto a slope forces the next step to go the way it points.
+    Downhill,
+    /// Slopes are ordinary path tiles.
+    Climbable,
+}
+
+/// A maze reduced to its junctions: the start, the goal and every point where paths branch, with
+/// each corridor between two of them collapsed into a single weighted edge.
+#[derive(Clone, Debug)]
+pub struct Junctions<P> {
+    /// The position of each junction, indexed by id. The start is 0 and the goal is 1.
+    pub points: Vec<P>,
+    /// For each junction, the junctions a corridor leads to and its length in steps.
+    edges: Vec<Vec<(usize, usize)>>,
+    /// `edges` as bitmasks, for cheap reachability.
+    successors: Vec<u64>,
+}
+
+const START: usize = 0;
+const GOAL: usize = 1;
+
+/// Path prefixes this many corridors deep are searched in parallel.
+const PARALLEL_DEPTH: usize = 3;
+
+impl<P: Copy + Eq + Hash> Junctions<P> {
+    /// Explores everything reachable from `start` through `neighbours` and contracts it. A point
+    /// is a junction if it touches three or more others, counting edges either way. Corridors that
+    /// run into a dead end or that `neighbours` only allows entering are dropped.
+    ///
+    /// Panics if there are more than 64 junctions.
+    pub fn contract<I: IntoIterator<Item = P>>(
+        start: P,
+        goal: P,
+        neighbours: impl Fn(P) -> I,
+    ) -> Self {
+        let mut successors = HashMap::<P, Vec<P>>::new();
+        let mut touching = HashMap::<P, HashSet<P>>::new();
+        // in the order found, so that junction ids are the same every run
+        let mut order = vec![];
+        let mut queue = VecDeque::from([start]);
+        while let Some(point) = queue.pop_front() {
+            if successors.contains_key(&point) {
+                continue;
+            }
+            let next = neighbours(point).into_iter().collect_vec();
+            for neighbour in &next {
+                touching.entry(point).or_default().insert(*neighbour);
+                touching.entry(*neighbour).or_default().insert(point);
+                queue.push_back(*neighbour);
+            }
+            successors.insert(point, next);
+            order.push(point);
+        }
+
+        let mut points = vec![start, goal];
+        points.extend(
+            order
+                .into_iter()
+                .filter(|point| *point != start && *point != goal && touching[point].len() > 2),
+        );
+        assert!(
+            points.len() <= 64,
+            "{} junctions do not fit a u64",
+            points.len()
+        );
+        let ids: HashMap<P, usize> = points.iter().enumerate().map(|(id, p)| (*p, id)).collect();
+
+        let mut edges = vec![vec![]; points.len()];
+        for (from, point) in points.iter().enumerate() {
+            let Some(first_steps) = successors.get(point) else {
+                continue;
+            };
+            for first in first_steps {
+                let (mut prev, mut curr, mut length) = (*point, *first, 1);
+                let to = loop {
+                    if let Some(id) = ids.get(&curr) {
+                        break Some(*id);
+                    }
+                    let Some(next) = successors[&curr].iter().find(|next| **next != prev) else {
+                        break None;
+                    };
+                    (prev, curr, length) = (curr, *next, length + 1);
+                };
+                if let Some(to) = to.filter(|to| *to != from) {
+                    edges[from].push((to, length));
+                }
+            }
+        }
+        // of several corridors between the same two junctions, only the longest matters
+        for out in &mut edges {
+            out.sort_unstable_by_key(|(to, length)| (*to, std::cmp::Reverse(*length)));
+            out.dedup_by_key(|(to, _)| *to);
+        }
+        let successors = edges
+            .iter()
+            .map(|out| out.iter().fold(0, |mask, (to, _)| mask | 1 << to))
+            .collect();
+
+        Self {
+            points,
+            edges,
+            successors,
+        }
+    }
+
+    /// The length of the longest path from the start to the goal that visits no point twice, or
+    /// `None` if the goal cannot be reached.
+    pub fn longest_path(&self) -> Option<usize> {
+        self.search(START, 1 << START, &mut HashMap::new())
+    }
+
+    /// Same as [`Self::longest_path`], but with the first few corridors enumerated up front and the
+    /// rest of the search split across threads.
+    pub fn par_longest_path(&self) -> Option<usize>
+    where
+        P: Sync,
+    {
+        let mut prefixes = vec![];
+        self.prefixes(START, 1 << START, 0, PARALLEL_DEPTH, &mut prefixes);
+        prefixes
+            .into_par_iter()
+            .filter_map(|(node, visited, length)| {
+                Some(length + self.search(node, visited, &mut HashMap::new())?)
+            })
+            .max()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The corridors out of junction `id` as `(to, length)`.
+    pub fn edges(&self, id: usize) -> &[(usize, usize)] {
+        &self.edges[id]
+    }
+
+    /// The longest way on from `node` to the goal, given the junctions already `visited`. That
+    /// only depends on which unvisited junctions `node` can still reach, so those are the memo
+    /// key, and anything that has cut itself off from the goal stops early.
+    fn search(
+        &self,
+        node: usize,
+        visited: u64,
+        memo: &mut HashMap<(usize, u64), Option<usize>>,
+    ) -> Option<usize> {
+        if node == GOAL {
+            return Some(0);
+        }
+        let reachable = self.reachable(node, visited);
+        if reachable & (1 << GOAL) == 0 {
+            return None;
+        }
+        if let Some(longest) = memo.get(&(node, reachable)) {
+            return *longest;
+        }
+        let longest = self.edges[node]
+            .iter()
+            .filter(|(to, _)| visited & (1 << to) == 0)
+            .filter_map(|(to, length)| Some(length + self.search(*to, visited | 1 << to, memo)?))
+            .max();
+        memo.insert((node, reachable), longest);
+        longest
+    }
+
+    /// The unvisited junctions reachable from `node` without passing through visited ones.
+    fn reachable(&self, node: usize, visited: u64) -> u64 {
+        let mut reached: u64 = 1 << node;
+        let mut frontier = reached;
+        while frontier != 0 {
+            let next = frontier.trailing_zeros() as usize;
+            frontier &= frontier - 1;
+            let new = self.successors[next] & !visited & !reached;
+            reached |= new;
+            frontier |= new;
+        }
+        reached
+    }
+
+    /// Every path `depth` corridors long from `node`, or shorter if it ends at the goal, as the
+    /// junction it ends at, the junctions it visited and its length.
+    fn prefixes(
+        &self,
+        node: usize,
+        visited: u64,
+        length: usize,
+        depth: usize,
+        prefixes: &mut Vec<(usize, u64, usize)>,
+    ) {
+        if depth == 0 || node == GOAL {
+            prefixes.push((node, visited, length));
+            return;
+        }
+        for (to, step) in &self.edges[node] {
+            if visited & (1 << to) == 0 {
+                self.prefixes(*to, visited | 1 << to, length + step, depth - 1, prefixes);
+            }
+        }
+    }
+}
+
+impl Junctions<(usize, usize)> {
+    /// Contracts a grid maze of `#` walls and open tiles, from the only open tile in the top row to
+    /// the only one in the bottom row.
+    pub fn from_maze(grid: &[Vec<char>], slopes: Slopes) -> Self {
+        let open_col = |row: &[char]| row.iter().position(|c| *c != '#').unwrap();
+        let start = (0, open_col(&grid[0]));
+        let goal = (grid.len() - 1, open_col(&grid[grid.len() - 1]));
+        Self::contract(start, goal, |(row, col)| {
+            let forced = match (slopes, grid[row][col]) {
+                (Slopes::Climbable, _) => None,
+                (Slopes::Downhill, '^') => Some((-1, 0)),
+                (Slopes::Downhill, '>') => Some((0, 1)),
+                (Slopes::Downhill, 'v') => Some((1, 0)),
+                (Slopes::Downhill, '<') => Some((0, -1)),
+                _ => None,
+            };
+            [(-1, 0), (0, 1), (1, 0), (0, -1)]
+                .into_iter()
+                .filter(move |dir| forced.map_or(true, |forced| forced == *dir))
+                .filter_map(move |(dr, dc)| {
+                    let next = (row.checked_add_signed(dr)?, col.checked_add_signed(dc)?);
+                    let tile = grid.get(next.0)?.get(next.1)?;
+                    (*tile != '#').then_some(next)
+                })
+        })
+    }
+}
+
+#[test]
+fn test_contract_small_mazes() {
+    let maze = |rows: &[&str]| {
+        rows.iter()
+            .map(|row| row.chars().collect_vec())
+            .collect_vec()
+    };
+
+    // a loop around a pillar: two corridors between the same junctions, only one kept
+    let looped = maze(&["#.###", "#...#", "#.#.#", "#...#", "###.#"]);
+    let junctions = Junctions::from_maze(&looped, Slopes::Climbable);
+    assert_eq!(4, junctions.len());
+    assert_eq!(Some(6), junctions.longest_path());
+
+    // a slope pointing back at the start makes the goal unreachable
+    let blocked = maze(&["#.#", "#^#", "#.#"]);
+    assert_eq!(
+        None,
+        Junctions::from_maze(&blocked, Slopes::Downhill).longest_path()
+    );
+    assert_eq!(
+        Some(2),
+        Junctions::from_maze(&blocked, Slopes::Climbable).longest_path()
+    );
+}
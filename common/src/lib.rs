@@ -0,0 +1,8 @@
+//! Helpers shared by every year's solutions.
+
+pub mod cycle;
+pub mod differential;
+pub mod graph;
+pub mod number;
+pub mod search;
+pub mod timing;
@@ -0,0 +1,210 @@
+use itertools::Itertools;
+
+/// The greatest common divisor of `a` and `b`, never negative, and some `x` and `y` with
+/// `a * x + b * y` equal to it.
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The `x` in `0..modulus` with `a * x` one more than a multiple of `modulus`, if `a` and
+/// `modulus` share no factor.
+pub fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    let (gcd, x, _) = extended_gcd(a, modulus);
+    (gcd == 1).then(|| x.rem_euclid(modulus))
+}
+
+/// Every `x` that leaves each remainder for its modulus, as one remainder modulo the lcm of the
+/// moduli, or `None` if there is no such `x`.
+///
+/// The moduli need not be coprime: `x = 1 mod 4` and `x = 3 mod 6` is `x = 9 mod 12`, while
+/// `x = 1 mod 4` and `x = 2 mod 6` has no answer.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    congruences
+        .iter()
+        .try_fold((0, 1), |(remainder, modulus), (other, other_modulus)| {
+            let (gcd, x, _) = extended_gcd(modulus, *other_modulus);
+            let difference = other - remainder;
+            if difference % gcd != 0 {
+                return None;
+            }
+            // remainder + modulus * k works for some k, which x scales into place
+            let step = *other_modulus / gcd;
+            let k = (difference / gcd) as i128 * x as i128 % step as i128;
+            let lcm = modulus * step;
+            let combined = (remainder as i128 + modulus as i128 * k).rem_euclid(lcm as i128);
+            Some((combined as i64, lcm))
+        })
+}
+
+/// The steps on which something happens: some of the steps before `start`, then each of `hits`
+/// again every `period` steps. Every hit is in `start..start + period`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    transient: Vec<usize>,
+    start: usize,
+    period: usize,
+    hits: Vec<usize>,
+}
+
+impl Schedule {
+    /// The schedule that hits on each of `hits`, in any order, and keeps repeating the ones from
+    /// `start` on every `period` steps. Hits from `start + period` on are left out, as they are
+    /// repeats.
+    pub fn new(hits: Vec<usize>, start: usize, period: usize) -> Self {
+        let (transient, hits) = hits
+            .into_iter()
+            .filter(|hit| *hit < start + period)
+            .sorted()
+            .dedup()
+            .partition(|hit| *hit < start);
+        Self {
+            transient,
+            start,
+            period,
+            hits,
+        }
+    }
+
+    pub fn first(&self) -> Option<usize> {
+        self.transient
+            .first()
+            .or_else(|| self.hits.first())
+            .copied()
+    }
+
+    pub fn contains(&self, step: usize) -> bool {
+        if step < self.start {
+            self.transient.contains(&step)
+        } else {
+            self.hits
+                .contains(&(self.start + (step - self.start) % self.period))
+        }
+    }
+
+    /// The steps on which both happen, lining up each pair of repeating hits by [`crt`].
+    pub fn intersect(&self, other: &Schedule) -> Schedule {
+        let start = self.start.max(other.start);
+        let transient = (0..start)
+            .filter(|step| self.contains(*step) && other.contains(*step))
+            .collect_vec();
+
+        let period = num::integer::lcm(self.period, other.period);
+        let mut hits = vec![];
+        for (hit, other_hit) in self.hits.iter().cartesian_product(&other.hits) {
+            let congruences = [
+                (*hit as i64, self.period as i64),
+                (*other_hit as i64, other.period as i64),
+            ];
+            if let Some((remainder, _)) = crt(&congruences) {
+                let remainder = remainder as usize;
+                hits.push(start + (remainder + period - start % period) % period);
+            }
+        }
+        hits.sort();
+        hits.dedup();
+
+        Schedule {
+            transient,
+            start,
+            period,
+            hits,
+        }
+    }
+}
+
+/// The first step on which everything happens at once, for things that each settle into a cycle
+/// after some offset of their own.
+pub fn first_common_step(schedules: &[Schedule]) -> Option<usize> {
+    schedules
+        .iter()
+        .cloned()
+        .reduce(|common, schedule| common.intersect(&schedule))?
+        .first()
+}
+
+#[test]
+fn test_extended_gcd() {
+    for (a, b) in (-30..30).cartesian_product(-30..30) {
+        let (gcd, x, y) = extended_gcd(a, b);
+        assert_eq!(num::integer::gcd(a, b), gcd, "gcd of {a} and {b}");
+        assert_eq!(gcd, a * x + b * y, "coefficients for {a} and {b}");
+    }
+    assert_eq!(Some(4), mod_inverse(3, 11));
+    assert_eq!(Some(10), mod_inverse(-1, 11));
+    assert_eq!(None, mod_inverse(4, 6));
+}
+
+#[test]
+fn test_crt() {
+    assert_eq!(Some((9, 12)), crt(&[(1, 4), (3, 6)]));
+    assert_eq!(None, crt(&[(1, 4), (2, 6)]));
+    assert_eq!(Some((23, 105)), crt(&[(2, 3), (3, 5), (2, 7)]));
+    assert_eq!(Some((0, 1)), crt(&[]));
+
+    for moduli in (1..13_i64).combinations(2) {
+        let (a, b) = (moduli[0], moduli[1]);
+        for (r, s) in (0..a).cartesian_product(0..b) {
+            let lcm = num::integer::lcm(a, b);
+            let expected = (0..lcm).find(|x| x % a == r && x % b == s);
+            assert_eq!(
+                expected.map(|x| (x, lcm)),
+                crt(&[(r, a), (s, b)]),
+                "{r} mod {a}, {s} mod {b}"
+            );
+        }
+    }
+    // moduli near a billion, where multiplying them out along the way needs more than an i32
+    assert_eq!(
+        Some((500_000_021_500_000_125, 1_000_000_016_000_000_063)),
+        crt(&[(-1, 1_000_000_007), (2_999_999_999, 1_000_000_009)])
+    );
+}
+
+#[test]
+fn test_first_common_step() {
+    // 2, 5, 8, ... and 1, 5, 9, ... first meet at 5, where the lcm of the periods would say 12
+    let every_3 = Schedule::new(vec![2], 2, 3);
+    let every_4 = Schedule::new(vec![1, 5, 9], 1, 4);
+    assert_eq!(
+        Some(5),
+        first_common_step(&[every_3.clone(), every_4.clone()])
+    );
+
+    // a one-off early hit only counts if the other also happens then
+    let early = Schedule::new(vec![0, 4, 5], 3, 3);
+    assert_eq!(
+        Some(5),
+        first_common_step(&[early.clone(), every_3.clone()])
+    );
+    assert_eq!(Some(0), first_common_step(&[early.clone(), early.clone()]));
+
+    // hits can be given in any order, and more than once
+    let unsorted = Schedule::new(vec![5, 4, 0, 4], 3, 3);
+    assert_eq!(early, unsorted);
+    assert_eq!(Some(0), unsorted.first());
+
+    // even and odd steps never line up
+    let even = Schedule::new(vec![0], 0, 2);
+    let odd = Schedule::new(vec![1], 0, 2);
+    assert_eq!(None, first_common_step(&[even.clone(), odd]));
+
+    for (a, b) in [(every_3, every_4), (early.clone(), even)] {
+        let both = a.intersect(&b);
+        for step in 0..200 {
+            assert_eq!(a.contains(step) && b.contains(step), both.contains(step));
+        }
+    }
+}
@@ -0,0 +1,574 @@
+//! Searches over states that know their own successors, each returning the path it found and how
+//! much work it took.
+//!
+//! [`astar`], [`dijkstra`], [`bfs`] and [`dijkstra_all`] hand the search to `pathfinding` and
+//! only count what it asks for. `pathfinding` has no 0-1 BFS, and the bag of paths from its
+//! `astar_bag` does not say which goals they end at or let the states on them be listed without
+//! walking every path, so [`zero_one_bfs`] and [`all_shortest_paths`] are written out here.
+
+use pathfinding::{directed, num_traits::Zero};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    hash::Hash,
+};
+
+/// A state in a search space, with whatever else it needs to find its successors in `Context`,
+/// such as the map being searched.
+pub trait SearchState<Context: ?Sized>: Clone + Eq + Hash {
+    type Cost: Copy + Ord + Zero + Debug;
+
+    /// The states one move away, and what each move costs.
+    fn successors(&self, context: &Context) -> Vec<(Self, Self::Cost)>;
+
+    /// A lower bound on the cost from here to a goal, for A*. Never overestimating is enough for
+    /// A* to find the cheapest path, and never dropping by more than a move costs is enough for
+    /// [`all_shortest_paths`] to find all of them.
+    fn heuristic(&self, _context: &Context) -> Self::Cost {
+        Self::Cost::zero()
+    }
+
+    fn is_goal(&self, context: &Context) -> bool;
+}
+
+/// How much work a search took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// States whose successors were generated.
+    pub expanded: usize,
+    /// The most states found but not yet expanded at any one time.
+    pub frontier_peak: usize,
+}
+
+/// A path from the start to a goal, start and goal included.
+#[derive(Clone, Debug)]
+pub struct Path<S, C> {
+    pub states: Vec<S>,
+    pub cost: C,
+    pub stats: Stats,
+}
+
+/// Every cheapest path to a goal, kept as the cheapest ways into each state.
+#[derive(Clone, Debug)]
+pub struct AllPaths<S, C> {
+    pub cost: C,
+    /// Every goal that can be reached at `cost`.
+    pub goals: Vec<S>,
+    pub stats: Stats,
+    start: S,
+    parents: HashMap<S, Vec<S>>,
+}
+
+/// The cheapest cost to every state reachable from a start.
+#[derive(Clone, Debug)]
+pub struct Reached<S, C> {
+    pub stats: Stats,
+    /// For each state, the state it is cheapest to arrive from and the total cost.
+    parents: HashMap<S, (Option<S>, C)>,
+}
+
+/// Counts the successors a search asks for, to fill in its [`Stats`].
+struct Tracker<S> {
+    found: HashSet<S>,
+    stats: Stats,
+}
+
+impl<S: Clone + Eq + Hash> Tracker<S> {
+    fn new(start: &S) -> Self {
+        Self {
+            found: HashSet::from([start.clone()]),
+            stats: Stats::default(),
+        }
+    }
+
+    fn expand<C: ?Sized>(&mut self, state: &S, context: &C) -> Vec<(S, S::Cost)>
+    where
+        S: SearchState<C>,
+    {
+        let successors = state.successors(context);
+        self.stats.expanded += 1;
+        self.found
+            .extend(successors.iter().map(|(next, _)| next.clone()));
+        // a state can be expanded again if a cheaper way to it turns up after all
+        let waiting = self.found.len().saturating_sub(self.stats.expanded);
+        self.stats.frontier_peak = self.stats.frontier_peak.max(waiting);
+        successors
+    }
+}
+
+/// The cheapest path to a goal by A*, guided by [`SearchState::heuristic`].
+pub fn astar<C: ?Sized, S: SearchState<C>>(start: S, context: &C) -> Option<Path<S, S::Cost>> {
+    let mut tracker = Tracker::new(&start);
+    let (states, cost) = directed::astar::astar(
+        &start,
+        |state| tracker.expand(state, context),
+        |state| state.heuristic(context),
+        |state| state.is_goal(context),
+    )?;
+    Some(Path {
+        states,
+        cost,
+        stats: tracker.stats,
+    })
+}
+
+/// The cheapest path to a goal by Dijkstra's algorithm, ignoring the heuristic.
+pub fn dijkstra<C: ?Sized, S: SearchState<C>>(start: S, context: &C) -> Option<Path<S, S::Cost>> {
+    let mut tracker = Tracker::new(&start);
+    let (states, cost) = directed::dijkstra::dijkstra(
+        &start,
+        |state| tracker.expand(state, context),
+        |state| state.is_goal(context),
+    )?;
+    Some(Path {
+        states,
+        cost,
+        stats: tracker.stats,
+    })
+}
+
+/// The path to a goal with the fewest moves, whatever they cost. Its cost is still the sum of the
+/// moves taken, the cheapest of them where two states are joined more than once.
+pub fn bfs<C: ?Sized, S: SearchState<C>>(start: S, context: &C) -> Option<Path<S, S::Cost>> {
+    let mut tracker = Tracker::new(&start);
+    let states = directed::bfs::bfs(
+        &start,
+        |state| {
+            tracker
+                .expand(state, context)
+                .into_iter()
+                .map(|(next, _)| next)
+        },
+        |state| state.is_goal(context),
+    )?;
+    let cost = states
+        .windows(2)
+        .map(|pair| {
+            pair[0]
+                .successors(context)
+                .into_iter()
+                .filter(|(next, _)| *next == pair[1])
+                .map(|(_, cost)| cost)
+                .min()
+                .unwrap()
+        })
+        .fold(S::Cost::zero(), |total, cost| total + cost);
+    Some(Path {
+        states,
+        cost,
+        stats: tracker.stats,
+    })
+}
+
+/// The cheapest path to a goal when every move either costs nothing or costs the same as every
+/// other move that costs something, by keeping a deque with free moves at the front.
+pub fn zero_one_bfs<C: ?Sized, S: SearchState<C>>(
+    start: S,
+    context: &C,
+) -> Option<Path<S, S::Cost>> {
+    let zero = S::Cost::zero();
+    let mut stats = Stats::default();
+    let mut parents = HashMap::from([(start.clone(), (None, zero))]);
+    let mut done = HashSet::new();
+    let mut deque = VecDeque::from([start]);
+    while let Some(state) = deque.pop_front() {
+        if !done.insert(state.clone()) {
+            continue;
+        }
+        if state.is_goal(context) {
+            return Some(trace(&parents, state, stats));
+        }
+        stats.expanded += 1;
+        let cost = parents[&state].1;
+        for (next, step) in state.successors(context) {
+            let improves = parents
+                .get(&next)
+                .map_or(true, |(_, known)| cost + step < *known);
+            if improves {
+                parents.insert(next.clone(), (Some(state.clone()), cost + step));
+                if step == zero {
+                    deque.push_front(next);
+                } else {
+                    deque.push_back(next);
+                }
+            }
+        }
+        stats.frontier_peak = stats.frontier_peak.max(deque.len());
+    }
+    None
+}
+
+/// Every cheapest path to a goal, by A* that remembers each equally cheap way into a state. Needs
+/// a consistent heuristic, which the default of zero is.
+pub fn all_shortest_paths<C: ?Sized, S: SearchState<C>>(
+    start: S,
+    context: &C,
+) -> Option<AllPaths<S, S::Cost>> {
+    let mut stats = Stats::default();
+    let mut costs = HashMap::from([(start.clone(), S::Cost::zero())]);
+    let mut parents = HashMap::<S, Vec<S>>::new();
+    let mut expanded = HashSet::new();
+    let mut goals = vec![];
+    let mut best = None;
+    let mut heap = BinaryHeap::from([Reverse(Queued {
+        estimate: start.heuristic(context),
+        order: 0,
+        state: start.clone(),
+    })]);
+    let mut order = 1;
+    while let Some(Reverse(Queued {
+        estimate, state, ..
+    })) = heap.pop()
+    {
+        if best.is_some_and(|best| estimate > best) {
+            break;
+        }
+        if !expanded.insert(state.clone()) {
+            continue;
+        }
+        let cost = costs[&state];
+        if state.is_goal(context) {
+            best = Some(cost);
+            goals.push(state);
+            continue;
+        }
+        stats.expanded += 1;
+        for (next, step) in state.successors(context) {
+            let next_cost = cost + step;
+            match costs.get(&next) {
+                Some(known) if next_cost > *known => continue,
+                Some(known) if next_cost == *known => {
+                    parents.entry(next).or_default().push(state.clone());
+                    continue;
+                }
+                _ => {}
+            }
+            costs.insert(next.clone(), next_cost);
+            parents.insert(next.clone(), vec![state.clone()]);
+            heap.push(Reverse(Queued {
+                estimate: next_cost + next.heuristic(context),
+                order,
+                state: next,
+            }));
+            order += 1;
+        }
+        stats.frontier_peak = stats.frontier_peak.max(heap.len());
+    }
+
+    Some(AllPaths {
+        cost: best?,
+        goals,
+        stats,
+        start,
+        parents,
+    })
+}
+
+/// The cheapest cost to every state reachable from `start`, goals or not.
+pub fn dijkstra_all<C: ?Sized, S: SearchState<C>>(start: S, context: &C) -> Reached<S, S::Cost> {
+    let mut tracker = Tracker::new(&start);
+    let reached = directed::dijkstra::dijkstra_all(&start, |state| tracker.expand(state, context));
+    let parents = reached
+        .into_iter()
+        .map(|(state, (parent, cost))| (state, (Some(parent), cost)))
+        .chain([(start, (None, S::Cost::zero()))])
+        .collect();
+    Reached {
+        stats: tracker.stats,
+        parents,
+    }
+}
+
+impl<S: Clone + Eq + Hash, C> AllPaths<S, C> {
+    /// Every state on at least one cheapest path.
+    pub fn states(&self) -> HashSet<S> {
+        let mut seen: HashSet<S> = self.goals.iter().cloned().collect();
+        let mut stack = self.goals.clone();
+        while let Some(state) = stack.pop() {
+            for parent in self.parents.get(&state).into_iter().flatten() {
+                if seen.insert(parent.clone()) {
+                    stack.push(parent.clone());
+                }
+            }
+        }
+        seen
+    }
+
+    /// Every cheapest path, from the start to a goal. There can be exponentially many. Where moves
+    /// cost nothing, states can be each other's parents, so a path never goes back to a state it
+    /// has already been through.
+    pub fn paths(&self) -> Vec<Vec<S>> {
+        let mut paths = vec![];
+        let mut stack: Vec<Vec<S>> = self.goals.iter().map(|goal| vec![goal.clone()]).collect();
+        while let Some(reversed) = stack.pop() {
+            let last = reversed.last().unwrap();
+            if *last == self.start {
+                paths.push(reversed.into_iter().rev().collect());
+                continue;
+            }
+            for parent in &self.parents[last] {
+                if reversed.contains(parent) {
+                    continue;
+                }
+                let mut longer = reversed.clone();
+                longer.push(parent.clone());
+                stack.push(longer);
+            }
+        }
+        paths
+    }
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> Reached<S, C> {
+    pub fn cost(&self, state: &S) -> Option<C> {
+        self.parents.get(state).map(|(_, cost)| *cost)
+    }
+
+    /// A cheapest path from the start to `state`, if it was reached.
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        self.parents.get(state)?;
+        let mut path = vec![state.clone()];
+        while let Some((Some(parent), _)) = self.parents.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Every reached state and its cost.
+    pub fn costs(&self) -> impl Iterator<Item = (&S, C)> + '_ {
+        self.parents.iter().map(|(state, (_, cost))| (state, *cost))
+    }
+}
+
+/// A state waiting in a heap, ordered by estimated total cost and then by when it was queued, so
+/// that searches are the same every run.
+struct Queued<S, C> {
+    estimate: C,
+    order: usize,
+    state: S,
+}
+
+impl<S, C: Ord> PartialEq for Queued<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<S, C: Ord> Eq for Queued<S, C> {}
+
+impl<S, C: Ord> PartialOrd for Queued<S, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S, C: Ord> Ord for Queued<S, C> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.estimate, self.order).cmp(&(&other.estimate, other.order))
+    }
+}
+
+/// Follows parents back from `goal` to the start.
+fn trace<S: Clone + Eq + Hash, C: Copy>(
+    parents: &HashMap<S, (Option<S>, C)>,
+    goal: S,
+    stats: Stats,
+) -> Path<S, C> {
+    let cost = parents[&goal].1;
+    let mut states = vec![goal];
+    while let Some((Some(parent), _)) = parents.get(states.last().unwrap()) {
+        states.push(parent.clone());
+    }
+    states.reverse();
+    Path {
+        states,
+        cost,
+        stats,
+    }
+}
+
+#[cfg(test)]
+struct Maze {
+    walls: HashSet<(usize, usize)>,
+    size: usize,
+    goal: (usize, usize),
+}
+
+#[cfg(test)]
+impl Maze {
+    fn parse(input: &str) -> Self {
+        let walls = input
+            .lines()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.char_indices()
+                    .filter(|(_, c)| *c == '#')
+                    .map(move |(col, _)| (row, col))
+            })
+            .collect();
+        let size = input.lines().count();
+        Self {
+            walls,
+            size,
+            goal: (size - 1, size - 1),
+        }
+    }
+}
+
+#[cfg(test)]
+impl SearchState<Maze> for (usize, usize) {
+    type Cost = usize;
+
+    fn successors(&self, maze: &Maze) -> Vec<(Self, usize)> {
+        let (row, col) = *self;
+        [
+            (row.wrapping_sub(1), col),
+            (row + 1, col),
+            (row, col.wrapping_sub(1)),
+            (row, col + 1),
+        ]
+        .into_iter()
+        .filter(|(r, c)| *r < maze.size && *c < maze.size && !maze.walls.contains(&(*r, *c)))
+        .map(|next| (next, 1))
+        .collect()
+    }
+
+    fn heuristic(&self, maze: &Maze) -> usize {
+        self.0.abs_diff(maze.goal.0) + self.1.abs_diff(maze.goal.1)
+    }
+
+    fn is_goal(&self, maze: &Maze) -> bool {
+        *self == maze.goal
+    }
+}
+
+#[cfg(test)]
+fn assert_valid_path<C, S: SearchState<C> + Debug>(
+    path: &Path<S, S::Cost>,
+    start: &S,
+    context: &C,
+) {
+    assert_eq!(Some(start), path.states.first());
+    assert!(path.states.last().unwrap().is_goal(context));
+    let cost = path
+        .states
+        .windows(2)
+        .map(|pair| {
+            let (_, cost) = pair[0]
+                .successors(context)
+                .into_iter()
+                .filter(|(next, _)| *next == pair[1])
+                .min_by_key(|(_, cost)| *cost)
+                .unwrap_or_else(|| panic!("{:?} does not lead to {:?}", pair[0], pair[1]));
+            cost
+        })
+        .fold(S::Cost::zero(), |total, cost| total + cost);
+    assert_eq!(path.cost, cost);
+}
+
+#[test]
+fn searches_agree_on_a_maze() {
+    let maze = Maze::parse(".....\n.###.\n...#.\n##.#.\n.....");
+    let start = (0, 0);
+
+    let by_astar = astar(start, &maze).unwrap();
+    let by_dijkstra = dijkstra(start, &maze).unwrap();
+    let by_bfs = bfs(start, &maze).unwrap();
+    let by_zero_one = zero_one_bfs(start, &maze).unwrap();
+    for path in [&by_astar, &by_dijkstra, &by_bfs, &by_zero_one] {
+        assert_eq!(8, path.cost);
+        assert_eq!(9, path.states.len());
+        assert_valid_path(path, &start, &maze);
+        assert!(path.stats.expanded > 0 && path.stats.frontier_peak > 0);
+    }
+    assert!(by_astar.stats.expanded <= by_dijkstra.stats.expanded);
+
+    // both ways around the wall in the middle are equally short
+    let all = all_shortest_paths(start, &maze).unwrap();
+    assert_eq!(8, all.cost);
+    assert_eq!(vec![(4, 4)], all.goals);
+    assert_eq!(2, all.paths().len());
+    for path in all.paths() {
+        assert_valid_path(
+            &Path {
+                states: path,
+                cost: all.cost,
+                stats: all.stats,
+            },
+            &start,
+            &maze,
+        );
+    }
+    assert_eq!(16, all.states().len());
+
+    let reached = dijkstra_all(start, &maze);
+    assert_eq!(Some(8), reached.cost(&(4, 4)));
+    assert_eq!(Some(4), reached.cost(&(2, 2)));
+    assert_eq!(None, reached.cost(&(1, 1)));
+    assert_eq!(Some(by_dijkstra.states), reached.path_to(&(4, 4)));
+
+    let walled_in = Maze::parse(".#\n#.");
+    assert!(astar(start, &walled_in).is_none());
+    assert!(bfs(start, &walled_in).is_none());
+    assert!(all_shortest_paths(start, &walled_in).is_none());
+}
+
+#[test]
+fn zero_one_bfs_takes_free_edges() {
+    // a line of nodes where stepping one ahead is free but jumping three ahead costs 1
+    struct Line(usize);
+    impl SearchState<Line> for usize {
+        type Cost = usize;
+
+        fn successors(&self, line: &Line) -> Vec<(usize, usize)> {
+            [(self + 1, 0), (self + 3, 1)]
+                .into_iter()
+                .filter(|(next, _)| *next <= line.0)
+                .collect()
+        }
+
+        fn is_goal(&self, line: &Line) -> bool {
+            *self == line.0
+        }
+    }
+
+    let by_zero_one = zero_one_bfs(0, &Line(9)).unwrap();
+    assert_eq!(0, by_zero_one.cost);
+    assert_eq!((0..=9).collect::<Vec<_>>(), by_zero_one.states);
+    assert_eq!(0, dijkstra(0, &Line(9)).unwrap().cost);
+    // breadth-first ignores costs and takes the fewest moves, which here are not the cheapest
+    let by_bfs = bfs(0, &Line(9)).unwrap();
+    assert_eq!(vec![0, 3, 6, 9], by_bfs.states);
+    assert_eq!(3, by_bfs.cost);
+}
+
+#[test]
+fn all_paths_survive_free_cycles() {
+    // 1 and 2 can swap for free, so each is an equally cheap way into the other
+    struct Swaps;
+    impl SearchState<Swaps> for usize {
+        type Cost = usize;
+
+        fn successors(&self, _: &Swaps) -> Vec<(usize, usize)> {
+            match self {
+                0 => vec![(1, 1)],
+                1 => vec![(2, 0), (3, 1)],
+                2 => vec![(1, 0), (3, 1)],
+                _ => vec![],
+            }
+        }
+
+        fn is_goal(&self, _: &Swaps) -> bool {
+            *self == 3
+        }
+    }
+
+    let all = all_shortest_paths(0, &Swaps).unwrap();
+    assert_eq!(2, all.cost);
+    let mut paths = all.paths();
+    paths.sort();
+    assert_eq!(vec![vec![0, 1, 2, 3], vec![0, 1, 3]], paths);
+    assert_eq!(HashSet::from([0, 1, 2, 3]), all.states());
+}
@@ -0,0 +1,98 @@
+use itertools::Itertools;
+use std::{
+    env, fs,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+/// How long a test may take when its year's table has nothing for it.
+const DEFAULT_BUDGET_MS: u64 = 1000;
+
+/// Every timing recorded by this test binary, with the budget it was held to.
+static TIMINGS: Mutex<Vec<(String, Duration, Duration)>> = Mutex::new(vec![]);
+
+/// How long `name` may take.
+///
+/// A `budgets` entry for `d22` covers every test of that day and one for `d22::p2_input` just that
+/// test, with the more specific entry winning. Anything not in the table gets
+/// `AOC_TIME_BUDGET_MS`, or a second if that is not set.
+pub fn budget(name: &str, budgets: &[(&str, u64)]) -> Duration {
+    let millis = budgets
+        .iter()
+        .filter(|(key, _)| {
+            name == *key
+                || name
+                    .strip_prefix(key)
+                    .is_some_and(|rest| rest.starts_with("::"))
+        })
+        .max_by_key(|(key, _)| key.len())
+        .map(|(_, millis)| *millis)
+        .unwrap_or_else(|| {
+            env::var("AOC_TIME_BUDGET_MS")
+                .ok()
+                .and_then(|millis| millis.parse().ok())
+                .unwrap_or(DEFAULT_BUDGET_MS)
+        });
+    Duration::from_millis(millis)
+}
+
+/// Adds a timing to this run's report in `target/timings.txt`, then holds it to its budget as
+/// [`check`] does.
+///
+/// The report is rewritten after every test, slowest first, so it is complete once `cargo test`
+/// finishes.
+pub fn record(manifest_dir: &str, name: String, elapsed: Duration, budgets: &[(&str, u64)]) {
+    let limit = budget(&name, budgets);
+    {
+        let mut timings = TIMINGS.lock().unwrap_or_else(PoisonError::into_inner);
+        timings.push((name.clone(), elapsed, limit));
+        timings.sort_by(|(name_a, a, _), (name_b, b, _)| b.cmp(a).then(name_a.cmp(name_b)));
+
+        let report = timings
+            .iter()
+            .map(|(name, elapsed, limit)| {
+                let status = if elapsed > limit { "OVER" } else { "ok" };
+                format!(
+                    "{:>10} ms  {:>10} ms  {status:<4}  {name}",
+                    elapsed.as_millis(),
+                    limit.as_millis()
+                )
+            })
+            .join("\n");
+        // the report is a convenience, so failing to write it shouldn't fail the test
+        let _ = fs::write(
+            format!("{manifest_dir}/target/timings.txt"),
+            format!("{:>13}  {:>13}\n{report}\n", "elapsed", "budget"),
+        );
+    }
+    check(&name, elapsed, budgets);
+}
+
+/// Warns if `name` took longer than its [`budget`], or panics if `AOC_TIME_BUDGET=fail`.
+pub fn check(name: &str, elapsed: Duration, budgets: &[(&str, u64)]) {
+    let limit = budget(name, budgets);
+    if elapsed <= limit {
+        return;
+    }
+    let message = format!(
+        "{name} took {} ms, over its {} ms budget",
+        elapsed.as_millis(),
+        limit.as_millis()
+    );
+    match env::var("AOC_TIME_BUDGET").as_deref() {
+        Ok("fail") => panic!("{message}"),
+        _ => eprintln!("Warning: {message}"),
+    }
+}
+
+#[test]
+fn test_budget_lookup() {
+    let budgets = [("d22", 5000), ("d22::p2_input", 20_000), ("d2", 3000)];
+    let millis = |name| budget(name, &budgets).as_millis();
+    assert_eq!(5000, millis("d22::p1_input"));
+    assert_eq!(20_000, millis("d22::p2_input"));
+    assert_eq!(3000, millis("d2::test"));
+    assert_eq!(5000, millis("d22"));
+    // a day's entry is not a prefix of other days
+    assert_eq!(budget("d23::test", &[]), budget("d23::test", &budgets));
+}